@@ -1,3 +1,6 @@
 fn main() {
-    tauri_build::build()
+    // 无界面的服务端不需要生成 Tauri 上下文
+    if std::env::var_os("CARGO_FEATURE_DESKTOP").is_some() {
+        tauri_build::build()
+    }
 }
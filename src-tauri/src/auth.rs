@@ -0,0 +1,134 @@
+use crate::database::Database;
+use crate::models::{ApiKey, CreatedApiKey, UserInfo, UserRole};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const API_KEY_PREFIX: &str = "ak_";
+
+/// 两个 v4 UUID 拼接，共 244 位随机数
+pub fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+pub fn create_api_key(db: &Database, admin_id: &str, name: &str) -> Result<CreatedApiKey, String> {
+    if name.trim().is_empty() {
+        return Err("密钥名称不能为空".to_string());
+    }
+    db.get_user(admin_id)
+        .map_err(|e| e.to_string())?
+        .filter(|u| u.role == UserRole::Admin)
+        .ok_or_else(|| "管理员不存在".to_string())?;
+
+    let secret = format!("{}{}", API_KEY_PREFIX, random_token());
+    let api_key = ApiKey {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        admin_id: admin_id.to_string(),
+        key_hash: hash_secret(&secret),
+        prefix: secret.chars().take(API_KEY_PREFIX.len() + 6).collect(),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    db.save_api_key(&api_key).map_err(|e| e.to_string())?;
+    Ok(CreatedApiKey { api_key, secret })
+}
+
+pub fn find_api_key(db: &Database, secret: &str) -> Result<Option<ApiKey>, String> {
+    if !secret.starts_with(API_KEY_PREFIX) {
+        return Ok(None);
+    }
+    let hash = hash_secret(secret);
+    Ok(db
+        .get_all_api_keys()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|k| k.key_hash == hash))
+}
+
+/// 请求方的身份：登录会话对应用户本人，API 密钥对应其所属管理员
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user_id: String,
+    pub role: UserRole,
+}
+
+struct Session {
+    principal: Principal,
+    expires_at: i64,
+}
+
+/// 登录会话只保存在内存中，重启后需要重新登录
+#[derive(Default)]
+pub struct Sessions {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Sessions {
+    /// 返回会话令牌及其过期时间
+    pub fn create(&self, user: &UserInfo, hours: i64) -> (String, i64) {
+        let token = random_token();
+        let now = chrono::Utc::now().timestamp();
+        let expires_at = now + hours.max(1) * 3600;
+
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                principal: Principal {
+                    user_id: user.id.clone(),
+                    role: user.role.clone(),
+                },
+                expires_at,
+            },
+        );
+        (token, expires_at)
+    }
+
+    pub fn get(&self, token: &str) -> Option<Principal> {
+        let now = chrono::Utc::now().timestamp();
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(token)
+            .filter(|s| s.expires_at > now)
+            .map(|s| s.principal.clone())
+    }
+
+    pub fn remove(&self, token: &str) {
+        self.sessions.lock().unwrap().remove(token);
+    }
+}
+
+/// 依次尝试登录会话和 API 密钥
+pub fn authenticate(
+    db: &Database,
+    sessions: &Sessions,
+    token: &str,
+) -> Result<Option<Principal>, String> {
+    if let Some(principal) = sessions.get(token) {
+        // 会话期间账号可能被删除或停用
+        let user = db.get_user(&principal.user_id).map_err(|e| e.to_string())?;
+        if user.is_some_and(|u| !u.disabled) {
+            return Ok(Some(principal));
+        }
+        sessions.remove(token);
+        return Ok(None);
+    }
+    let Some(key) = find_api_key(db, token)? else {
+        return Ok(None);
+    };
+    // 管理员被删除或停用后其密钥随之失效
+    let admin = db.get_user(&key.admin_id).map_err(|e| e.to_string())?;
+    Ok(admin
+        .filter(|u| u.role == UserRole::Admin && !u.disabled)
+        .map(|u| Principal {
+            user_id: u.id,
+            role: UserRole::Admin,
+        }))
+}
@@ -0,0 +1,12 @@
+//! 考勤数据库管理工具，用于找回管理员账号、批量录入位置、导出和备份等
+//!
+//! ```text
+//! attendance-cli --help
+//! ```
+
+fn main() {
+    if let Err(e) = attendance_lib::run_cli() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,36 @@
+//! 独立运行的考勤服务端，多台桌面端以客户端模式共用同一个数据库
+//!
+//! ```text
+//! attendance-server [--data-dir <目录>] [--address <地址:端口>]
+//! ```
+
+use std::path::PathBuf;
+
+const USAGE: &str = "用法: attendance-server [--data-dir <目录>] [--address <地址:端口>]";
+
+#[tokio::main]
+async fn main() {
+    let mut data_dir = None;
+    let mut address = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--data-dir" => data_dir = args.next().map(PathBuf::from),
+            "--address" => address = args.next(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("未知参数: {}\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Err(e) = attendance_lib::run_server(data_dir, address).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,580 @@
+use crate::database::Database;
+use crate::geo;
+use crate::holiday;
+use crate::models::{
+    AssignmentTarget, AttendanceRecord, AttendanceStatus, CheckInRequest, CheckInResponse,
+    FlagKind, GeoPoint, Location, OfflineCheckIn, PendingCheckIn, PendingStatus, Punctuality,
+    RecordFlag, RecordSource, User, WindowPolicy, DEFAULT_TIMEZONE,
+};
+use crate::overtime;
+use crate::plausibility;
+use crate::schedule;
+use crate::webhook;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use std::collections::HashSet;
+
+/// 离线打卡的采集时间允许比当前时间晚的误差（秒）
+const CLOCK_SKEW: i64 = 300;
+
+/// 候选打卡位置及其所属的位置组
+type CandidateLocation = (Location, Option<String>);
+
+fn add_candidate(
+    candidates: &mut Vec<CandidateLocation>,
+    location: Location,
+    group_id: Option<String>,
+) {
+    if !candidates.iter().any(|(l, _)| l.id == location.id) {
+        candidates.push((location, group_id));
+    }
+}
+
+/// 用户在指定日期可以打卡的所有位置，以及该位置来自哪个位置组
+pub fn resolve_user_locations(
+    db: &Database,
+    user: &User,
+    date: NaiveDate,
+) -> Result<Vec<CandidateLocation>, Box<dyn std::error::Error>> {
+    let mut candidates = Vec::new();
+
+    if let Some(location_id) = &user.location_id {
+        if let Some(location) = db.get_location(location_id)? {
+            add_candidate(&mut candidates, location, None);
+        }
+    }
+
+    for assignment in user
+        .location_assignments
+        .iter()
+        .filter(|a| a.is_active_on(date))
+    {
+        match &assignment.target {
+            AssignmentTarget::Location(id) => {
+                if let Some(location) = db.get_location(id)? {
+                    add_candidate(&mut candidates, location, None);
+                }
+            }
+            AssignmentTarget::Group(id) => {
+                if let Some(group) = db.get_location_group(id)? {
+                    for location_id in &group.location_ids {
+                        if let Some(location) = db.get_location(location_id)? {
+                            add_candidate(&mut candidates, location, Some(group.id.clone()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// 用户所在的时区：取主位置的时区，没有主位置时取第一个可打卡位置的时区
+pub fn user_timezone(db: &Database, user: &User) -> Tz {
+    let today = schedule::local_datetime(chrono::Utc::now().timestamp(), DEFAULT_TIMEZONE).date();
+    resolve_user_locations(db, user, today)
+        .ok()
+        .and_then(|candidates| candidates.into_iter().next())
+        .map(|(location, _)| location.timezone)
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// 用户所在时区的当前日期
+pub fn user_today(db: &Database, user: &User) -> NaiveDate {
+    schedule::local_datetime(chrono::Utc::now().timestamp(), user_timezone(db, user)).date()
+}
+
+/// 优先选择范围内最深入的围栏，都不在范围内时返回边界最近的位置，距离为到围栏边界的带符号距离
+fn select_location(
+    candidates: Vec<CandidateLocation>,
+    point: GeoPoint,
+) -> Option<(Location, Option<String>, f64)> {
+    candidates
+        .into_iter()
+        .map(|(location, group_id)| {
+            let distance = geo::signed_distance(&location.fence(), point);
+            (location, group_id, distance)
+        })
+        .min_by(|a, b| {
+            let a_inside = a.2 <= a.0.buffer;
+            let b_inside = b.2 <= b.0.buffer;
+            b_inside.cmp(&a_inside).then(a.2.total_cmp(&b.2))
+        })
+}
+
+fn failure(message: String) -> CheckInResponse {
+    CheckInResponse {
+        success: false,
+        record: None,
+        message: Some(message),
+    }
+}
+
+/// 保存新的打卡记录并通知订阅了打卡事件的 webhook
+fn save_record(
+    db: &Database,
+    user: &User,
+    record: &AttendanceRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    db.save_record(record)?;
+    webhook::check_in_recorded(db, user, record);
+    Ok(())
+}
+
+/// 打卡校验流程，`timestamp` 为打卡发生的时间，离线补传时为设备采集的时间
+pub fn process_check_in(
+    db: &Database,
+    request: &CheckInRequest,
+    timestamp: i64,
+    source: RecordSource,
+) -> CheckInResponse {
+    let user = match db.get_user(&request.user_id) {
+        Ok(Some(u)) => u,
+        Ok(None) => {
+            return failure("用户不存在".to_string());
+        }
+        Err(e) => {
+            return failure(format!("获取用户失败: {}", e));
+        }
+    };
+
+    if let Some(key) = &request.idempotency_key {
+        match db.get_record_by_idempotency_key(&user.id, key) {
+            Ok(Some(record)) => {
+                return CheckInResponse {
+                    success: record.status == AttendanceStatus::Success,
+                    record: Some(record),
+                    message: Some("重复提交，已返回原打卡记录".to_string()),
+                };
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return failure(format!("获取打卡记录失败: {}", e));
+            }
+        }
+    }
+
+    let settings = match db.get_settings() {
+        Ok(s) => s,
+        Err(e) => {
+            return failure(format!("获取设置失败: {}", e));
+        }
+    };
+
+    let records = db.get_records_by_user(&user.id).unwrap_or_default();
+
+    // 离线补传的打卡可能早于已有记录，因此按时间差绝对值找最近的同类型打卡
+    let nearest_punch = records
+        .iter()
+        .filter(|r| r.status == AttendanceStatus::Success && r.punch_type == request.punch_type)
+        .min_by_key(|r| (timestamp - r.timestamp).abs());
+    if let Some(nearest) = nearest_punch {
+        let elapsed = (timestamp - nearest.timestamp).abs();
+        if elapsed < settings.punch.min_interval_seconds {
+            // 不返回已有的记录，避免调用方把它当作本次打卡的结果
+            return failure(format!(
+                "距上次打卡（记录 {}）仅 {} 秒，请勿重复打卡",
+                nearest.id, elapsed
+            ));
+        }
+    }
+
+    let user_date = schedule::local_datetime(timestamp, user_timezone(db, &user)).date();
+    let candidates = match resolve_user_locations(db, &user, user_date) {
+        Ok(c) => c,
+        Err(e) => {
+            return failure(format!("获取位置失败: {}", e));
+        }
+    };
+
+    let point = GeoPoint {
+        latitude: request.latitude,
+        longitude: request.longitude,
+    };
+    let Some((location, location_group_id, distance)) = select_location(candidates, point) else {
+        return failure("用户未分配打卡位置".to_string());
+    };
+
+    let previous: Vec<AttendanceRecord> = records
+        .into_iter()
+        .filter(|r| r.timestamp <= timestamp)
+        .collect();
+    let risk_reasons = plausibility::assess(
+        &settings.plausibility,
+        &previous,
+        &location,
+        point,
+        request.fix_timestamp,
+        timestamp,
+    );
+    let risk_score = plausibility::risk_score(&risk_reasons);
+
+    let new_record = |status: AttendanceStatus, error_message: Option<String>| {
+        let mut record = AttendanceRecord::new(
+            request.user_id.clone(),
+            location.id.clone(),
+            request.latitude,
+            request.longitude,
+            request.punch_type,
+            status,
+            error_message,
+        );
+        record.timestamp = timestamp;
+        record.source = source;
+        record.synced_at =
+            (source == RecordSource::LateSynced).then(|| chrono::Utc::now().timestamp());
+        record.location_group_id = location_group_id.clone();
+        record.accuracy = request.accuracy;
+        record.altitude = request.altitude;
+        record.fix_timestamp = request.fix_timestamp;
+        record.provider = request.provider.clone();
+        record.risk_score = risk_score;
+        record.risk_reasons = risk_reasons.clone();
+        record.suspicious = risk_score >= settings.plausibility.flag_threshold;
+        record.idempotency_key = request.idempotency_key.clone();
+        record.localize(location.timezone);
+        record
+    };
+    let now = schedule::local_datetime(timestamp, location.timezone);
+
+    let mut flags = Vec::new();
+
+    if !schedule::within_windows(&location.check_in_windows, now) {
+        let reason = format!(
+            "当前时间 {} 不在允许的打卡时间段内（{}）",
+            now.format("%H:%M"),
+            schedule::describe_windows(&location.check_in_windows)
+        );
+
+        if location.window_policy == WindowPolicy::Reject {
+            let record = new_record(AttendanceStatus::OutsideWindow, Some(reason.clone()));
+
+            save_record(db, &user, &record).ok();
+
+            return CheckInResponse {
+                success: false,
+                record: Some(record),
+                message: Some(reason),
+            };
+        }
+
+        flags.push(RecordFlag {
+            kind: FlagKind::OutsideWindow,
+            reason,
+        });
+    }
+
+    if let (Some(accuracy), Some(max_accuracy)) = (request.accuracy, settings.accuracy.max_accuracy)
+    {
+        if accuracy > max_accuracy {
+            let reason = format!(
+                "定位精度 {:.0} 米低于要求（{:.0} 米以内）",
+                accuracy, max_accuracy
+            );
+            let record = new_record(AttendanceStatus::LowAccuracy, Some(reason.clone()));
+
+            save_record(db, &user, &record).ok();
+
+            return CheckInResponse {
+                success: false,
+                record: Some(record),
+                message: Some(reason),
+            };
+        }
+    }
+
+    let within_fence = distance <= location.buffer
+        || request.accuracy.is_some_and(|accuracy| {
+            geo::overlap_ratio(&location.fence(), point, accuracy, location.buffer)
+                >= settings.accuracy.min_overlap
+        });
+
+    if within_fence {
+        let mut record = new_record(AttendanceStatus::Success, None);
+        record.flags = flags;
+
+        if let Some(shift_id) = &user.shift_id {
+            if let Ok(Some(shift)) = db.get_shift(shift_id) {
+                let date =
+                    schedule::shift_date(&shift, record.punch_type, timestamp, location.timezone);
+                let calendar = holiday::user_calendar(db, &user, date).ok().flatten();
+                record.punctuality = schedule::punctuality(
+                    &shift,
+                    calendar.as_ref(),
+                    record.punch_type,
+                    timestamp,
+                    location.timezone,
+                );
+            }
+        }
+
+        let message = match record.punctuality {
+            Some(Punctuality::Late(minutes)) => format!("打卡成功，迟到 {} 分钟", minutes),
+            Some(Punctuality::EarlyLeave(minutes)) => format!("打卡成功，早退 {} 分钟", minutes),
+            _ => "打卡成功".to_string(),
+        };
+
+        match save_record(db, &user, &record) {
+            Ok(_) => {
+                overtime::refresh_after_punch(db, &user, &record).ok();
+                CheckInResponse {
+                    success: true,
+                    record: Some(record),
+                    message: Some(message),
+                }
+            }
+            Err(e) => failure(format!("保存记录失败: {}", e)),
+        }
+    } else {
+        let record = new_record(
+            AttendanceStatus::Failed,
+            Some(format!("距离打卡范围边界 {:.2} 米，超出范围", distance)),
+        );
+
+        save_record(db, &user, &record).ok();
+
+        CheckInResponse {
+            success: false,
+            record: Some(record),
+            message: Some(format!("不在打卡范围内，距离边界 {:.2} 米", distance)),
+        }
+    }
+}
+
+/// 将离线采集的打卡放入待同步队列，同一用户重复的幂等键只保留第一次
+pub fn queue_offline(
+    db: &Database,
+    check_ins: Vec<OfflineCheckIn>,
+) -> Result<Vec<PendingCheckIn>, Box<dyn std::error::Error>> {
+    let mut keys: HashSet<(String, String)> = db
+        .get_all_pending()?
+        .into_iter()
+        .filter_map(|p| p.request.idempotency_key.map(|key| (p.user_id, key)))
+        .collect();
+
+    let mut queued = Vec::new();
+    for check_in in check_ins {
+        if let Some(key) = &check_in.request.idempotency_key {
+            if !keys.insert((check_in.request.user_id.clone(), key.clone())) {
+                continue;
+            }
+        }
+
+        let pending = PendingCheckIn::new(check_in.request, check_in.captured_at);
+        db.save_pending(&pending)?;
+        queued.push(pending);
+    }
+
+    Ok(queued)
+}
+
+/// 按采集时间顺序校验待同步的离线打卡，`user_id` 为空时处理所有用户
+pub fn sync_pending(
+    db: &Database,
+    user_id: Option<&str>,
+    now: i64,
+) -> Result<Vec<PendingCheckIn>, Box<dyn std::error::Error>> {
+    let settings = db.get_settings()?;
+    let max_age = settings.offline.max_age_hours * 3600;
+
+    let mut pending: Vec<PendingCheckIn> = db
+        .get_all_pending()?
+        .into_iter()
+        .filter(|p| p.status == PendingStatus::Pending)
+        .filter(|p| user_id.is_none_or(|id| p.user_id == id))
+        .collect();
+    pending.sort_by_key(|p| p.captured_at);
+
+    for entry in &mut pending {
+        if now - entry.captured_at > max_age {
+            entry.status = PendingStatus::Rejected;
+            entry.message = Some(format!(
+                "离线打卡已超过 {} 小时，无法同步",
+                settings.offline.max_age_hours
+            ));
+        } else if entry.captured_at > now + CLOCK_SKEW {
+            entry.status = PendingStatus::Rejected;
+            entry.message = Some("离线打卡时间晚于当前时间".to_string());
+        } else {
+            let response = process_check_in(
+                db,
+                &entry.request,
+                entry.captured_at,
+                RecordSource::LateSynced,
+            );
+            entry.status = if response.success {
+                PendingStatus::Synced
+            } else {
+                PendingStatus::Rejected
+            };
+            entry.record_id = response.record.map(|r| r.id);
+            entry.message = response.message;
+        }
+
+        db.save_pending(entry)?;
+    }
+
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PunchType;
+    use crate::test_support::{setup, Fixture};
+
+    /// 2025-03-10 09:00 (UTC+8)
+    const MORNING: i64 = 1_741_568_400;
+
+    fn request(user: &User, latitude: f64, punch_type: PunchType) -> CheckInRequest {
+        CheckInRequest {
+            user_id: user.id.clone(),
+            latitude,
+            longitude: 121.0,
+            punch_type,
+            accuracy: None,
+            altitude: None,
+            fix_timestamp: None,
+            provider: None,
+            idempotency_key: None,
+        }
+    }
+
+    #[test]
+    fn punch_inside_the_fence_succeeds() {
+        let Fixture { db, user, .. } = setup();
+        let response = process_check_in(
+            &db,
+            &request(&user, 31.0002, PunchType::In),
+            MORNING,
+            RecordSource::Online,
+        );
+        assert!(response.success);
+        let record = response.record.unwrap();
+        assert_eq!(record.status, AttendanceStatus::Success);
+        assert_eq!(record.local_date, NaiveDate::from_ymd_opt(2025, 3, 10));
+    }
+
+    #[test]
+    fn punch_outside_the_fence_is_saved_as_failed() {
+        let Fixture { db, user, .. } = setup();
+        let response = process_check_in(
+            &db,
+            &request(&user, 31.01, PunchType::In),
+            MORNING,
+            RecordSource::Online,
+        );
+        assert!(!response.success);
+        assert_eq!(response.record.unwrap().status, AttendanceStatus::Failed);
+        assert_eq!(db.get_records_by_user(&user.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn repeated_punch_is_rejected_without_a_record() {
+        let Fixture { db, user, .. } = setup();
+        let first = process_check_in(
+            &db,
+            &request(&user, 31.0, PunchType::In),
+            MORNING,
+            RecordSource::Online,
+        );
+        let second = process_check_in(
+            &db,
+            &request(&user, 31.0, PunchType::In),
+            MORNING + 30,
+            RecordSource::Online,
+        );
+        assert!(!second.success);
+        assert!(second.record.is_none());
+        assert!(second.message.unwrap().contains(&first.record.unwrap().id));
+        assert_eq!(db.get_records_by_user(&user.id).unwrap().len(), 1);
+
+        // 不同类型的打卡不受间隔限制
+        let out = process_check_in(
+            &db,
+            &request(&user, 31.0, PunchType::Out),
+            MORNING + 30,
+            RecordSource::Online,
+        );
+        assert!(out.success);
+    }
+
+    #[test]
+    fn idempotency_key_returns_the_original_record() {
+        let Fixture { db, user, .. } = setup();
+        let mut retry = request(&user, 31.0, PunchType::In);
+        retry.idempotency_key = Some("key-1".to_string());
+        let first = process_check_in(&db, &retry, MORNING, RecordSource::Online);
+        let second = process_check_in(&db, &retry, MORNING + 600, RecordSource::Online);
+        assert!(second.success);
+        assert_eq!(second.record.unwrap().id, first.record.unwrap().id);
+        assert_eq!(db.get_records_by_user(&user.id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejected_offline_punch_does_not_point_at_another_record() {
+        let Fixture { db, user, .. } = setup();
+        process_check_in(
+            &db,
+            &request(&user, 31.0, PunchType::In),
+            MORNING,
+            RecordSource::Online,
+        );
+        let check_in = OfflineCheckIn {
+            request: request(&user, 31.0, PunchType::In),
+            captured_at: MORNING + 10,
+        };
+        queue_offline(&db, vec![check_in]).unwrap();
+
+        let synced = sync_pending(&db, Some(&user.id), MORNING + 100).unwrap();
+        assert_eq!(synced.len(), 1);
+        assert_eq!(synced[0].status, PendingStatus::Rejected);
+        assert_eq!(synced[0].record_id, None);
+    }
+
+    #[test]
+    fn offline_punches_sync_in_capture_order() {
+        let Fixture { db, user, .. } = setup();
+        let mut late = OfflineCheckIn {
+            request: request(&user, 31.0, PunchType::Out),
+            captured_at: MORNING + 9 * 3600,
+        };
+        late.request.idempotency_key = Some("out".to_string());
+        let early = OfflineCheckIn {
+            request: request(&user, 31.0, PunchType::In),
+            captured_at: MORNING,
+        };
+        // 重复提交的幂等键只保留一次
+        let duplicate = OfflineCheckIn {
+            request: late.request.clone(),
+            captured_at: late.captured_at,
+        };
+        assert_eq!(
+            queue_offline(&db, vec![late, early, duplicate])
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let synced = sync_pending(&db, None, MORNING + 10 * 3600).unwrap();
+        assert!(synced.iter().all(|p| p.status == PendingStatus::Synced));
+        assert_eq!(synced[0].request.punch_type, PunchType::In);
+        let records = db.get_records_by_user(&user.id).unwrap();
+        assert!(records.iter().all(|r| r.source == RecordSource::LateSynced));
+    }
+
+    #[test]
+    fn stale_offline_punch_is_rejected() {
+        let Fixture { db, user, .. } = setup();
+        let check_in = OfflineCheckIn {
+            request: request(&user, 31.0, PunchType::In),
+            captured_at: MORNING,
+        };
+        queue_offline(&db, vec![check_in]).unwrap();
+
+        let synced = sync_pending(&db, None, MORNING + 73 * 3600).unwrap();
+        assert_eq!(synced[0].status, PendingStatus::Rejected);
+        assert!(db.get_records_by_user(&user.id).unwrap().is_empty());
+    }
+}
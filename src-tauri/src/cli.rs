@@ -0,0 +1,471 @@
+use crate::database::Database;
+use crate::export;
+use crate::maintenance;
+use crate::models::{
+    CreateUserRequest, DatabaseBackup, GeoJsonImportMode, ImportAction, RecordQuery, User,
+    UserInfo, UserRole,
+};
+use crate::service;
+use crate::user_import::{validate_password, validate_username};
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// 考勤数据库管理工具，运行时桌面端和服务端不能同时打开同一个数据库
+#[derive(Parser)]
+#[command(name = "attendance-cli", version)]
+struct Cli {
+    /// 数据目录，默认与桌面端相同
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 管理用户
+    #[command(subcommand)]
+    Users(UsersCommand),
+    /// 管理打卡位置
+    #[command(subcommand)]
+    Locations(LocationsCommand),
+    /// 查询和导出打卡记录
+    #[command(subcommand)]
+    Records(RecordsCommand),
+    /// 把全部数据备份到 JSON 文件
+    Backup { file: PathBuf },
+    /// 用备份文件替换现有数据
+    Restore {
+        file: PathBuf,
+        /// 确认覆盖现有数据
+        #[arg(long)]
+        yes: bool,
+    },
+    /// 检查数据完整性，有问题时以非零状态退出
+    Verify,
+    /// 把旧版本写入的数据升级为当前结构
+    Migrate,
+}
+
+#[derive(Subcommand)]
+enum UsersCommand {
+    /// 列出用户
+    List {
+        /// 只列出该管理员名下的员工
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// 创建用户
+    Create {
+        username: String,
+        #[arg(long)]
+        password: String,
+        #[arg(long, value_enum, default_value = "user")]
+        role: Role,
+        /// 员工所属的管理员
+        #[arg(long, default_value = "admin")]
+        admin: String,
+    },
+    /// 停用用户，停用后不能登录
+    Disable { username: String },
+    /// 重新启用用户
+    Enable { username: String },
+    /// 重置密码，不指定时随机生成
+    ResetPassword {
+        username: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Role {
+    Admin,
+    User,
+}
+
+#[derive(Subcommand)]
+enum LocationsCommand {
+    /// 列出打卡位置
+    List {
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// 从 GeoJSON 文件导入位置
+    Import {
+        file: PathBuf,
+        #[arg(long, default_value = "admin")]
+        admin: String,
+        /// 同名位置也新建，默认更新同名位置
+        #[arg(long)]
+        create_new: bool,
+        /// 只校验并显示变更，不写入
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 把管理员名下的位置导出为 GeoJSON
+    Export {
+        file: PathBuf,
+        #[arg(long, default_value = "admin")]
+        admin: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum RecordsCommand {
+    /// 在终端显示打卡记录
+    Query {
+        #[command(flatten)]
+        filter: RecordFilter,
+        /// 只显示最近的若干条
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// 把打卡记录导出为 CSV
+    Export {
+        file: PathBuf,
+        #[command(flatten)]
+        filter: RecordFilter,
+    },
+}
+
+#[derive(Args)]
+struct RecordFilter {
+    /// 管理员用户名
+    #[arg(long)]
+    admin: Option<String>,
+    /// 员工用户名
+    #[arg(long)]
+    user: Option<String>,
+    /// 开始日期，如 2024-01-01
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// 结束日期（包含）
+    #[arg(long)]
+    to: Option<NaiveDate>,
+}
+
+fn find_user(db: &Database, username: &str) -> Result<User, String> {
+    db.get_user_by_username(username)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("用户 {} 不存在", username))
+}
+
+fn find_admin(db: &Database, username: &str) -> Result<User, String> {
+    let user = find_user(db, username)?;
+    if user.role != UserRole::Admin {
+        return Err(format!("{} 不是管理员", username));
+    }
+    Ok(user)
+}
+
+fn path_arg(path: &std::path::Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// 按字符数对齐的纯文本表格
+fn print_table(headers: &[String], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = *width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(headers);
+    for row in rows {
+        line(row);
+    }
+}
+
+fn headers(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+fn users_command(db: &Database, command: UsersCommand) -> Result<(), String> {
+    match command {
+        UsersCommand::List { admin } => {
+            let users = match admin {
+                Some(admin) => service::get_users_by_admin(db, find_admin(db, &admin)?.id),
+                None => db
+                    .get_all_users()
+                    .map_err(|e| e.to_string())?
+                    .into_iter()
+                    .map(UserInfo::from)
+                    .collect(),
+            };
+            let names: Vec<(String, String)> = db
+                .get_all_users()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .map(|u| (u.id, u.username))
+                .collect();
+            let rows: Vec<Vec<String>> = users
+                .iter()
+                .map(|u| {
+                    let admin = u
+                        .admin_id
+                        .as_ref()
+                        .and_then(|id| names.iter().find(|(uid, _)| uid == id))
+                        .map(|(_, name)| name.clone())
+                        .unwrap_or_default();
+                    vec![
+                        u.username.clone(),
+                        match u.role {
+                            UserRole::Admin => "管理员".to_string(),
+                            UserRole::User => "员工".to_string(),
+                        },
+                        admin,
+                        u.profile.display_name.clone().unwrap_or_default(),
+                        if u.disabled { "停用" } else { "正常" }.to_string(),
+                        u.id.clone(),
+                    ]
+                })
+                .collect();
+            print_table(
+                &headers(&["用户名", "角色", "管理员", "姓名", "状态", "ID"]),
+                &rows,
+            );
+        }
+        UsersCommand::Create {
+            username,
+            password,
+            role,
+            admin,
+        } => {
+            validate_username(&username)?;
+            validate_password(&password)?;
+            let (role, admin_id) = match role {
+                Role::Admin => (UserRole::Admin, None),
+                Role::User => (UserRole::User, Some(find_admin(db, &admin)?.id)),
+            };
+            let user = service::create_user(
+                db,
+                CreateUserRequest {
+                    username,
+                    password,
+                    role,
+                    admin_id,
+                },
+            )?;
+            println!("已创建用户 {} ({})", user.username, user.id);
+        }
+        UsersCommand::Disable { username } => {
+            let mut user = find_user(db, &username)?;
+            let other_admins = db
+                .get_all_users()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|u| u.role == UserRole::Admin && !u.disabled && u.id != user.id)
+                .count();
+            if user.role == UserRole::Admin && other_admins == 0 {
+                return Err("不能停用最后一个可用的管理员".to_string());
+            }
+            user.disabled = true;
+            db.save_user(&user).map_err(|e| e.to_string())?;
+            println!("已停用用户 {}", username);
+        }
+        UsersCommand::Enable { username } => {
+            let mut user = find_user(db, &username)?;
+            user.disabled = false;
+            db.save_user(&user).map_err(|e| e.to_string())?;
+            println!("已启用用户 {}", username);
+        }
+        UsersCommand::ResetPassword { username, password } => {
+            let mut user = find_user(db, &username)?;
+            let generated = password.is_none();
+            let password =
+                password.unwrap_or_else(|| Uuid::new_v4().simple().to_string()[..12].to_string());
+            validate_password(&password)?;
+            user.password = password.clone();
+            db.save_user(&user).map_err(|e| e.to_string())?;
+            if generated {
+                println!("用户 {} 的新密码: {}", username, password);
+            } else {
+                println!("已重置用户 {} 的密码", username);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn locations_command(db: &Database, command: LocationsCommand) -> Result<(), String> {
+    match command {
+        LocationsCommand::List { admin } => {
+            let locations = match admin {
+                Some(admin) => service::get_locations_by_admin(db, find_admin(db, &admin)?.id),
+                None => db.get_all_locations().map_err(|e| e.to_string())?,
+            };
+            let rows: Vec<Vec<String>> = locations
+                .iter()
+                .map(|l| {
+                    vec![
+                        l.name.clone(),
+                        format!("{:.6}, {:.6}", l.latitude, l.longitude),
+                        format!("{}", l.radius),
+                        if l.geofence.is_some() {
+                            "自定义"
+                        } else {
+                            "圆形"
+                        }
+                        .to_string(),
+                        l.timezone.to_string(),
+                        l.id.clone(),
+                    ]
+                })
+                .collect();
+            print_table(
+                &headers(&["名称", "中心", "半径(米)", "围栏", "时区", "ID"]),
+                &rows,
+            );
+        }
+        LocationsCommand::Import {
+            file,
+            admin,
+            create_new,
+            dry_run,
+        } => {
+            let admin = find_admin(db, &admin)?;
+            let content =
+                std::fs::read_to_string(&file).map_err(|e| format!("读取文件失败: {}", e))?;
+            let mode = if create_new {
+                GeoJsonImportMode::CreateNew
+            } else {
+                GeoJsonImportMode::UpdateByName
+            };
+            let report = service::import_locations_geojson(db, admin.id, content, mode, dry_run)?;
+            for change in &report.changes {
+                let action = match change.action {
+                    ImportAction::Create => "新建",
+                    ImportAction::Update => "更新",
+                    ImportAction::Unchanged => "不变",
+                };
+                println!("[{}] {} {}", change.index, action, change.name);
+                for field in &change.fields {
+                    println!("      {}: {} -> {}", field.field, field.before, field.after);
+                }
+            }
+            for error in &report.errors {
+                println!(
+                    "[{}] 错误 {}: {}",
+                    error.index,
+                    error.name.as_deref().unwrap_or("-"),
+                    error.message
+                );
+            }
+            if report.applied {
+                println!("已导入 {} 个位置", report.changes.len());
+            } else if !report.errors.is_empty() {
+                return Err(format!("{} 个要素有错误，未写入", report.errors.len()));
+            } else {
+                println!("仅校验，未写入");
+            }
+        }
+        LocationsCommand::Export { file, admin } => {
+            let admin = find_admin(db, &admin)?;
+            let count = service::export_locations_geojson(db, admin.id, path_arg(&file))?;
+            println!("已导出 {} 个位置到 {}", count, file.display());
+        }
+    }
+    Ok(())
+}
+
+fn record_query(db: &Database, filter: RecordFilter) -> Result<RecordQuery, String> {
+    Ok(RecordQuery {
+        admin_id: match filter.admin {
+            Some(admin) => Some(find_admin(db, &admin)?.id),
+            None => None,
+        },
+        user_id: match filter.user {
+            Some(user) => Some(find_user(db, &user)?.id),
+            None => None,
+        },
+        start_date: filter.from,
+        end_date: filter.to,
+        ..Default::default()
+    })
+}
+
+fn records_command(db: &Database, command: RecordsCommand) -> Result<(), String> {
+    match command {
+        RecordsCommand::Query { filter, limit } => {
+            let query = record_query(db, filter)?;
+            let mut records = service::query_attendance_records(db, query)?;
+            if let Some(limit) = limit {
+                records.drain(..records.len().saturating_sub(limit));
+            }
+            let table = export::records_table(db, &records, &[])?;
+            print_table(&table.headers, &table.rows);
+            println!("共 {} 条", records.len());
+        }
+        RecordsCommand::Export { file, filter } => {
+            let query = record_query(db, filter)?;
+            let count = service::export_records_csv(db, query, Vec::new(), path_arg(&file))?;
+            println!("已导出 {} 条记录到 {}", count, file.display());
+        }
+    }
+    Ok(())
+}
+
+/// 命令行工具入口，参数取自进程命令行
+pub fn run_cli() -> Result<(), String> {
+    let cli = Cli::parse();
+    let db = match &cli.data_dir {
+        Some(dir) => Database::open(dir.join("attendance_db")),
+        None => Database::new(),
+    }
+    .map_err(|e| format!("无法打开数据库（桌面端或服务端是否正在运行？）: {}", e))?;
+
+    match cli.command {
+        Command::Users(command) => users_command(&db, command),
+        Command::Locations(command) => locations_command(&db, command),
+        Command::Records(command) => records_command(&db, command),
+        Command::Backup { file } => {
+            let backup = db.backup().map_err(|e| e.to_string())?;
+            export::ensure_parent_dir(&file)?;
+            let content = serde_json::to_vec_pretty(&backup).map_err(|e| e.to_string())?;
+            std::fs::write(&file, content).map_err(|e| format!("写入文件失败: {}", e))?;
+            let entries: usize = backup.trees.values().map(Vec::len).sum();
+            println!("已备份 {} 条数据到 {}", entries, file.display());
+            Ok(())
+        }
+        Command::Restore { file, yes } => {
+            if !yes {
+                return Err("恢复会覆盖现有全部数据，确认后请加 --yes 重新执行".to_string());
+            }
+            let content =
+                std::fs::read_to_string(&file).map_err(|e| format!("读取文件失败: {}", e))?;
+            let backup: DatabaseBackup =
+                serde_json::from_str(&content).map_err(|e| format!("备份文件格式错误: {}", e))?;
+            db.restore(&backup).map_err(|e| e.to_string())?;
+            println!("已从 {} 恢复数据", file.display());
+            Ok(())
+        }
+        Command::Verify => {
+            let problems = maintenance::verify(&db)?;
+            if problems.is_empty() {
+                println!("未发现问题");
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}", problem);
+            }
+            Err(format!("发现 {} 个问题", problems.len()))
+        }
+        Command::Migrate => {
+            let migrated = maintenance::migrate(&db)?;
+            println!("已改写 {} 条数据", migrated);
+            Ok(())
+        }
+    }
+}
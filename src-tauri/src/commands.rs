@@ -1,114 +1,75 @@
-use crate::database::Database;
+use crate::events::EventWindows;
 use crate::models::*;
-use std::sync::Mutex;
-use tauri::State;
-
-pub type AppState = Mutex<Database>;
-
-#[tauri::command]
-pub fn login(state: State<AppState>, request: LoginRequest) -> LoginResponse {
-    println!("Login attempt for username: {}", request.username);
-    
-    let db = state.lock().unwrap();
-    
-    match db.get_user_by_username(&request.username) {
-        Ok(Some(user)) => {
-            println!("User found: {}, password match: {}", user.username, user.password == request.password);
-            if user.password == request.password {
-                LoginResponse {
-                    success: true,
-                    user: Some(user),
-                    message: None,
-                }
-            } else {
-                LoginResponse {
-                    success: false,
-                    user: None,
-                    message: Some("密码错误".to_string()),
-                }
-            }
-        }
-        Ok(None) => {
-            println!("User not found: {}", request.username);
-            LoginResponse {
-                success: false,
-                user: None,
-                message: Some("用户不存在".to_string()),
-            }
-        }
-        Err(e) => {
-            println!("Database error: {}", e);
-            LoginResponse {
-                success: false,
-                user: None,
-                message: Some(format!("登录失败: {}", e)),
-            }
+use crate::service;
+use chrono::NaiveDate;
+use tauri::{State, WebviewWindow};
+
+pub use crate::service::AppState;
+
+/// 管理员登录后，当前窗口开始接收其名下的数据变化事件
+#[tauri::command]
+pub fn login(
+    window: WebviewWindow,
+    state: State<AppState>,
+    windows: State<EventWindows>,
+    request: LoginRequest,
+) -> LoginResponse {
+    let response = service::login(&state.lock().unwrap(), request);
+    let admin_id = response
+        .user
+        .as_ref()
+        .filter(|u| u.role == UserRole::Admin)
+        .map(|u| u.id.clone());
+    windows.watch(window.label(), admin_id);
+    response
+}
+
+/// 恢复已保存的登录状态时重新关联窗口，退出登录时传空
+#[tauri::command]
+pub fn watch_data_events(
+    window: WebviewWindow,
+    state: State<AppState>,
+    windows: State<EventWindows>,
+    admin_id: Option<String>,
+) -> Result<(), String> {
+    if let Some(id) = &admin_id {
+        let user = state.lock().unwrap().get_user(id).map_err(|e| e.to_string())?;
+        if !user.is_some_and(|u| u.role == UserRole::Admin) {
+            return Err("管理员不存在".to_string());
         }
     }
+    windows.watch(window.label(), admin_id);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_all_users(state: State<AppState>) -> Vec<User> {
-    let db = state.lock().unwrap();
-    db.get_all_users().unwrap_or_default()
+pub fn get_all_users(state: State<AppState>) -> Vec<UserInfo> {
+    service::get_all_users(&state.lock().unwrap())
 }
 
 #[tauri::command]
-pub fn get_users_by_admin(state: State<AppState>, admin_id: String) -> Vec<User> {
-    let db = state.lock().unwrap();
-    let all_users = db.get_all_users().unwrap_or_default();
-    println!("get_users_by_admin called with admin_id: {}", admin_id);
-    println!("All users count: {}", all_users.len());
-    for user in &all_users {
-        println!("User: {}, role: {:?}, admin_id: {:?}", user.username, user.role, user.admin_id);
-    }
-    let filtered: Vec<User> = all_users
-        .into_iter()
-        .filter(|u| u.admin_id.as_ref() == Some(&admin_id))
-        .collect();
-    println!("Filtered users count: {}", filtered.len());
-    filtered
-}
-
-#[tauri::command]
-pub fn create_user(state: State<AppState>, user: CreateUserRequest) -> Result<User, String> {
-    println!("create_user called with username: {}, role: {:?}, admin_id: {:?}", 
-             user.username, user.role, user.admin_id);
-    
-    let db = state.lock().unwrap();
-    
-    match db.get_user_by_username(&user.username) {
-        Ok(Some(_)) => {
-            println!("Username already exists: {}", user.username);
-            Err("用户名已存在".to_string())
-        }
-        Ok(None) => {
-            let new_user = User::new(
-                user.username.clone(),
-                user.password.clone(),
-                user.role.clone(),
-                user.admin_id.clone(),
-            );
-            println!("Creating user: {}, id: {}, admin_id: {:?}", 
-                     new_user.username, new_user.id, new_user.admin_id);
-            db.save_user(&new_user).map_err(|e| {
-                println!("Failed to save user: {}", e);
-                e.to_string()
-            })?;
-            println!("User created successfully");
-            Ok(new_user)
-        }
-        Err(e) => {
-            println!("Database error: {}", e);
-            Err(e.to_string())
-        }
-    }
+pub fn get_users_by_admin(state: State<AppState>, admin_id: String) -> Vec<UserInfo> {
+    service::get_users_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_user(state: State<AppState>, user: CreateUserRequest) -> Result<UserInfo, String> {
+    service::create_user(&state.lock().unwrap(), user)
+}
+
+#[tauri::command]
+pub fn import_users(
+    state: State<AppState>,
+    admin_id: String,
+    content: String,
+    dry_run: bool,
+) -> Result<UserImportReport, String> {
+    service::import_users(&state.lock().unwrap(), admin_id, content, dry_run)
 }
 
 #[tauri::command]
 pub fn delete_user(state: State<AppState>, user_id: String) -> Result<(), String> {
-    let db = state.lock().unwrap();
-    db.delete_user(&user_id).map_err(|e| e.to_string())
+    service::delete_user(&state.lock().unwrap(), user_id)
 }
 
 #[tauri::command]
@@ -116,63 +77,179 @@ pub fn update_user_location(
     state: State<AppState>,
     user_id: String,
     location_id: String,
-) -> Result<User, String> {
-    let db = state.lock().unwrap();
-    
-    let mut user = db.get_user(&user_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "用户不存在".to_string())?;
-    
-    user.location_id = Some(location_id);
-    
-    db.save_user(&user).map_err(|e| e.to_string())?;
-    Ok(user)
+) -> Result<UserInfo, String> {
+    service::update_user_location(&state.lock().unwrap(), user_id, location_id)
 }
 
 #[tauri::command]
 pub fn get_user_location(state: State<AppState>, user_id: String) -> Option<Location> {
-    let db = state.lock().unwrap();
-    
-    let user = match db.get_user(&user_id) {
-        Ok(Some(u)) => u,
-        _ => return None,
-    };
-    
-    match user.location_id {
-        Some(location_id) => db.get_location(&location_id).ok().flatten(),
-        None => None,
-    }
+    service::get_user_location(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_user_locations(state: State<AppState>, user_id: String) -> Vec<Location> {
+    service::get_user_locations(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn update_user_location_assignments(
+    state: State<AppState>,
+    user_id: String,
+    assignments: Vec<LocationAssignment>,
+) -> Result<UserInfo, String> {
+    service::update_user_location_assignments(&state.lock().unwrap(), user_id, assignments)
+}
+
+#[tauri::command]
+pub fn get_location_groups_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+) -> Vec<LocationGroup> {
+    service::get_location_groups_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_location_group(
+    state: State<AppState>,
+    group: CreateLocationGroupRequest,
+) -> Result<LocationGroup, String> {
+    service::create_location_group(&state.lock().unwrap(), group)
+}
+
+#[tauri::command]
+pub fn update_location_group(
+    state: State<AppState>,
+    group_id: String,
+    group: UpdateLocationGroupRequest,
+) -> Result<LocationGroup, String> {
+    service::update_location_group(&state.lock().unwrap(), group_id, group)
+}
+
+#[tauri::command]
+pub fn delete_location_group(state: State<AppState>, group_id: String) -> Result<(), String> {
+    service::delete_location_group(&state.lock().unwrap(), group_id)
+}
+
+#[tauri::command]
+pub fn get_user_groups_by_admin(state: State<AppState>, admin_id: String) -> Vec<UserGroup> {
+    service::get_user_groups_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_user_group(
+    state: State<AppState>,
+    group: CreateUserGroupRequest,
+) -> Result<UserGroup, String> {
+    service::create_user_group(&state.lock().unwrap(), group)
+}
+
+#[tauri::command]
+pub fn update_user_group(
+    state: State<AppState>,
+    group_id: String,
+    group: UpdateUserGroupRequest,
+) -> Result<UserGroup, String> {
+    service::update_user_group(&state.lock().unwrap(), group_id, group)
+}
+
+#[tauri::command]
+pub fn delete_user_group(state: State<AppState>, group_id: String) -> Result<(), String> {
+    service::delete_user_group(&state.lock().unwrap(), group_id)
+}
+
+#[tauri::command]
+pub fn get_holiday_calendars_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+) -> Vec<HolidayCalendar> {
+    service::get_holiday_calendars_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_holiday_calendar(
+    state: State<AppState>,
+    calendar: CreateHolidayCalendarRequest,
+) -> Result<HolidayCalendar, String> {
+    service::create_holiday_calendar(&state.lock().unwrap(), calendar)
+}
+
+#[tauri::command]
+pub fn update_holiday_calendar(
+    state: State<AppState>,
+    calendar_id: String,
+    calendar: UpdateHolidayCalendarRequest,
+) -> Result<HolidayCalendar, String> {
+    service::update_holiday_calendar(&state.lock().unwrap(), calendar_id, calendar)
+}
+
+#[tauri::command]
+pub fn delete_holiday_calendar(state: State<AppState>, calendar_id: String) -> Result<(), String> {
+    service::delete_holiday_calendar(&state.lock().unwrap(), calendar_id)
+}
+
+#[tauri::command]
+pub fn import_holiday_calendar_ics(
+    state: State<AppState>,
+    calendar_id: String,
+    content: String,
+) -> Result<HolidayCalendar, String> {
+    service::import_holiday_calendar_ics(&state.lock().unwrap(), calendar_id, content)
+}
+
+#[tauri::command]
+pub fn import_cn_statutory_holidays(
+    state: State<AppState>,
+    calendar_id: String,
+    year: i32,
+) -> Result<HolidayCalendar, String> {
+    service::import_cn_statutory_holidays(&state.lock().unwrap(), calendar_id, year)
+}
+
+#[tauri::command]
+pub fn set_holiday_calendar(
+    state: State<AppState>,
+    target: CalendarTarget,
+    calendar_id: Option<String>,
+) -> Result<(), String> {
+    service::set_holiday_calendar(&state.lock().unwrap(), target, calendar_id)
 }
 
 #[tauri::command]
 pub fn get_all_locations(state: State<AppState>) -> Vec<Location> {
-    let db = state.lock().unwrap();
-    db.get_all_locations().unwrap_or_default()
+    service::get_all_locations(&state.lock().unwrap())
 }
 
 #[tauri::command]
 pub fn get_locations_by_admin(state: State<AppState>, admin_id: String) -> Vec<Location> {
-    let db = state.lock().unwrap();
-    db.get_all_locations()
-        .unwrap_or_default()
-        .into_iter()
-        .filter(|l| l.admin_id == admin_id)
-        .collect()
+    service::get_locations_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_location(
+    state: State<AppState>,
+    location: CreateLocationRequest,
+) -> Result<Location, String> {
+    service::create_location(&state.lock().unwrap(), location)
+}
+
+#[tauri::command]
+pub fn export_locations_geojson(
+    state: State<AppState>,
+    admin_id: String,
+    path: String,
+) -> Result<usize, String> {
+    service::export_locations_geojson(&state.lock().unwrap(), admin_id, path)
 }
 
 #[tauri::command]
-pub fn create_location(state: State<AppState>, location: CreateLocationRequest) -> Result<Location, String> {
-    let db = state.lock().unwrap();
-    
-    let new_location = Location::new(
-        location.name.clone(),
-        location.latitude,
-        location.longitude,
-        location.radius,
-        location.admin_id.clone(),
-    );
-    db.save_location(&new_location).map_err(|e| e.to_string())?;
-    Ok(new_location)
+pub fn import_locations_geojson(
+    state: State<AppState>,
+    admin_id: String,
+    content: String,
+    mode: GeoJsonImportMode,
+    dry_run: bool,
+) -> Result<LocationImportReport, String> {
+    service::import_locations_geojson(&state.lock().unwrap(), admin_id, content, mode, dry_run)
 }
 
 #[tauri::command]
@@ -181,176 +258,442 @@ pub fn update_location(
     location_id: String,
     location: UpdateLocationRequest,
 ) -> Result<Location, String> {
-    let db = state.lock().unwrap();
-    
-    let mut existing_location = db.get_location(&location_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "位置不存在".to_string())?;
-    
-    if let Some(name) = location.name {
-        existing_location.name = name;
-    }
-    if let Some(latitude) = location.latitude {
-        existing_location.latitude = latitude;
-    }
-    if let Some(longitude) = location.longitude {
-        existing_location.longitude = longitude;
-    }
-    if let Some(radius) = location.radius {
-        existing_location.radius = radius;
-    }
-    
-    db.save_location(&existing_location).map_err(|e| e.to_string())?;
-    Ok(existing_location)
+    service::update_location(&state.lock().unwrap(), location_id, location)
 }
 
 #[tauri::command]
 pub fn delete_location(state: State<AppState>, location_id: String) -> Result<(), String> {
-    let db = state.lock().unwrap();
-    db.delete_location(&location_id).map_err(|e| e.to_string())
+    service::delete_location(&state.lock().unwrap(), location_id)
 }
 
 #[tauri::command]
-pub fn get_attendance_records(state: State<AppState>, user_id: Option<String>) -> Vec<AttendanceRecord> {
-    let db = state.lock().unwrap();
-    
-    if let Some(uid) = user_id {
-        db.get_records_by_user(&uid).unwrap_or_default()
-    } else {
-        db.get_all_records().unwrap_or_default()
-    }
+pub fn get_attendance_records(
+    state: State<AppState>,
+    user_id: Option<String>,
+) -> Vec<AttendanceRecord> {
+    service::get_attendance_records(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_attendance_records_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+) -> Vec<AttendanceRecord> {
+    service::get_attendance_records_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn query_attendance_records(
+    state: State<AppState>,
+    query: RecordQuery,
+) -> Result<Vec<AttendanceRecord>, String> {
+    service::query_attendance_records(&state.lock().unwrap(), query)
+}
+
+#[tauri::command]
+pub fn export_records_csv(
+    state: State<AppState>,
+    query: RecordQuery,
+    columns: Vec<RecordColumn>,
+    path: String,
+) -> Result<usize, String> {
+    service::export_records_csv(&state.lock().unwrap(), query, columns, path)
 }
 
 #[tauri::command]
-pub fn get_attendance_records_by_admin(state: State<AppState>, admin_id: String) -> Vec<AttendanceRecord> {
-    let db = state.lock().unwrap();
-    
-    let users: Vec<String> = db
-        .get_all_users()
-        .unwrap_or_default()
-        .into_iter()
-        .filter(|u| u.admin_id.as_ref() == Some(&admin_id))
-        .map(|u| u.id)
-        .collect();
-    
-    db.get_all_records()
-        .unwrap_or_default()
-        .into_iter()
-        .filter(|r| users.contains(&r.user_id))
-        .collect()
+pub fn export_monthly_report_csv(
+    state: State<AppState>,
+    admin_id: String,
+    month: String,
+    columns: Vec<ReportColumn>,
+    path: String,
+) -> Result<usize, String> {
+    service::export_monthly_report_csv(&state.lock().unwrap(), admin_id, month, columns, path)
 }
 
-fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    const R: f64 = 6371000.0;
-    let φ1 = lat1.to_radians();
-    let φ2 = lat2.to_radians();
-    let Δφ = (lat2 - lat1).to_radians();
-    let Δλ = (lon2 - lon1).to_radians();
+#[tauri::command]
+pub fn export_attendance_track(
+    state: State<AppState>,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    format: TrackFormat,
+    path: String,
+) -> Result<usize, String> {
+    service::export_attendance_track(&state.lock().unwrap(), user_id, start_date, end_date, format, path)
+}
 
-    let a = (Δφ / 2.0).sin().powi(2) + φ1.cos() * φ2.cos() * (Δλ / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+#[tauri::command]
+pub fn export_monthly_report_xlsx(
+    state: State<AppState>,
+    admin_id: String,
+    month: String,
+    columns: Vec<ReportColumn>,
+    path: String,
+) -> Result<usize, String> {
+    service::export_monthly_report_xlsx(&state.lock().unwrap(), admin_id, month, columns, path)
+}
 
-    R * c
+#[tauri::command]
+pub fn get_suspicious_records(
+    state: State<AppState>,
+    admin_id: String,
+    min_score: Option<u32>,
+) -> Vec<AttendanceRecord> {
+    service::get_suspicious_records(&state.lock().unwrap(), admin_id, min_score)
 }
 
 #[tauri::command]
 pub fn check_in(state: State<AppState>, request: CheckInRequest) -> CheckInResponse {
-    let db = state.lock().unwrap();
-    
-    let user = match db.get_user(&request.user_id) {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return CheckInResponse {
-                success: false,
-                record: None,
-                message: Some("用户不存在".to_string()),
-            };
-        }
-        Err(e) => {
-            return CheckInResponse {
-                success: false,
-                record: None,
-                message: Some(format!("获取用户失败: {}", e)),
-            };
-        }
-    };
-    
-    let location_id = match &user.location_id {
-        Some(id) => id.clone(),
-        None => {
-            return CheckInResponse {
-                success: false,
-                record: None,
-                message: Some("用户未分配打卡位置".to_string()),
-            };
-        }
-    };
-    
-    let location = match db.get_location(&location_id) {
-        Ok(Some(l)) => l,
-        Ok(None) => {
-            return CheckInResponse {
-                success: false,
-                record: None,
-                message: Some("打卡位置不存在".to_string()),
-            };
-        }
-        Err(e) => {
-            return CheckInResponse {
-                success: false,
-                record: None,
-                message: Some(format!("获取位置失败: {}", e)),
-            };
-        }
-    };
-    
-    let distance = calculate_distance(
-        request.latitude,
-        request.longitude,
-        location.latitude,
-        location.longitude,
-    );
-    
-    if distance <= location.radius {
-        let record = AttendanceRecord::new(
-            request.user_id.clone(),
-            location.id.clone(),
-            request.latitude,
-            request.longitude,
-            AttendanceStatus::Success,
-            None,
-        );
-        
-        match db.save_record(&record) {
-            Ok(_) => CheckInResponse {
-                success: true,
-                record: Some(record),
-                message: Some("打卡成功".to_string()),
-            },
-            Err(e) => CheckInResponse {
-                success: false,
-                record: None,
-                message: Some(format!("保存记录失败: {}", e)),
-            },
-        }
-    } else {
-        let record = AttendanceRecord::new(
-            request.user_id.clone(),
-            location.id.clone(),
-            request.latitude,
-            request.longitude,
-            AttendanceStatus::Failed,
-            Some(format!("距离打卡位置 {:.2} 米，超出范围", distance)),
-        );
-        
-        db.save_record(&record).ok();
-        
-        CheckInResponse {
-            success: false,
-            record: Some(record),
-            message: Some(format!("不在打卡范围内，距离 {:.2} 米", distance)),
-        }
-    }
+    service::check_in(&state.lock().unwrap(), request)
+}
+
+#[tauri::command]
+pub fn queue_offline_check_ins(
+    state: State<AppState>,
+    check_ins: Vec<OfflineCheckIn>,
+) -> Result<Vec<PendingCheckIn>, String> {
+    service::queue_offline_check_ins(&state.lock().unwrap(), check_ins)
+}
+
+#[tauri::command]
+pub fn sync_pending_check_ins(
+    state: State<AppState>,
+    user_id: Option<String>,
+) -> Result<Vec<PendingCheckIn>, String> {
+    service::sync_pending_check_ins(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_pending_check_ins(state: State<AppState>, user_id: String) -> Vec<PendingCheckIn> {
+    service::get_pending_check_ins(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn submit_correction_request(
+    state: State<AppState>,
+    request: CreateCorrectionRequest,
+) -> Result<CorrectionRequest, String> {
+    service::submit_correction_request(&state.lock().unwrap(), request)
+}
+
+#[tauri::command]
+pub fn get_correction_requests_by_user(
+    state: State<AppState>,
+    user_id: String,
+) -> Vec<CorrectionRequest> {
+    service::get_correction_requests_by_user(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_correction_requests_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<CorrectionRequest> {
+    service::get_correction_requests_by_admin(&state.lock().unwrap(), admin_id, status)
+}
+
+#[tauri::command]
+pub fn approve_correction_request(
+    state: State<AppState>,
+    correction_id: String,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    service::approve_correction_request(&state.lock().unwrap(), correction_id, review)
+}
+
+#[tauri::command]
+pub fn reject_correction_request(
+    state: State<AppState>,
+    correction_id: String,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    service::reject_correction_request(&state.lock().unwrap(), correction_id, review)
+}
+
+#[tauri::command]
+pub fn get_leave_types_by_admin(state: State<AppState>, admin_id: String) -> Vec<LeaveType> {
+    service::get_leave_types_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_leave_type(
+    state: State<AppState>,
+    leave_type: CreateLeaveTypeRequest,
+) -> Result<LeaveType, String> {
+    service::create_leave_type(&state.lock().unwrap(), leave_type)
+}
+
+#[tauri::command]
+pub fn update_leave_type(
+    state: State<AppState>,
+    leave_type_id: String,
+    leave_type: UpdateLeaveTypeRequest,
+) -> Result<LeaveType, String> {
+    service::update_leave_type(&state.lock().unwrap(), leave_type_id, leave_type)
+}
+
+#[tauri::command]
+pub fn delete_leave_type(state: State<AppState>, leave_type_id: String) -> Result<(), String> {
+    service::delete_leave_type(&state.lock().unwrap(), leave_type_id)
+}
+
+#[tauri::command]
+pub fn submit_leave_request(
+    state: State<AppState>,
+    request: CreateLeaveRequest,
+) -> Result<LeaveRequest, String> {
+    service::submit_leave_request(&state.lock().unwrap(), request)
+}
+
+#[tauri::command]
+pub fn get_leave_requests_by_user(state: State<AppState>, user_id: String) -> Vec<LeaveRequest> {
+    service::get_leave_requests_by_user(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_leave_requests_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<LeaveRequest> {
+    service::get_leave_requests_by_admin(&state.lock().unwrap(), admin_id, status)
+}
+
+#[tauri::command]
+pub fn approve_leave_request(
+    state: State<AppState>,
+    leave_id: String,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    service::approve_leave_request(&state.lock().unwrap(), leave_id, review)
+}
+
+#[tauri::command]
+pub fn reject_leave_request(
+    state: State<AppState>,
+    leave_id: String,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    service::reject_leave_request(&state.lock().unwrap(), leave_id, review)
+}
+
+#[tauri::command]
+pub fn get_leave_balances(
+    state: State<AppState>,
+    user_id: String,
+    year: i32,
+) -> Result<Vec<LeaveBalance>, String> {
+    service::get_leave_balances(&state.lock().unwrap(), user_id, year)
+}
+
+#[tauri::command]
+pub fn set_leave_adjustment(
+    state: State<AppState>,
+    user_id: String,
+    leave_type_id: String,
+    year: i32,
+    days: f64,
+) -> Result<(), String> {
+    service::set_leave_adjustment(&state.lock().unwrap(), user_id, leave_type_id, year, days)
+}
+
+#[tauri::command]
+pub fn get_overtime_by_user(state: State<AppState>, user_id: String) -> Vec<OvertimeRecord> {
+    service::get_overtime_by_user(&state.lock().unwrap(), user_id)
+}
+
+#[tauri::command]
+pub fn get_overtime_by_admin(
+    state: State<AppState>,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<OvertimeRecord> {
+    service::get_overtime_by_admin(&state.lock().unwrap(), admin_id, status)
+}
+
+#[tauri::command]
+pub fn recalculate_overtime(
+    state: State<AppState>,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<OvertimeRecord>, String> {
+    service::recalculate_overtime(&state.lock().unwrap(), user_id, start_date, end_date)
+}
+
+#[tauri::command]
+pub fn approve_overtime(
+    state: State<AppState>,
+    overtime_id: String,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    service::approve_overtime(&state.lock().unwrap(), overtime_id, review)
+}
+
+#[tauri::command]
+pub fn reject_overtime(
+    state: State<AppState>,
+    overtime_id: String,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    service::reject_overtime(&state.lock().unwrap(), overtime_id, review)
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<AppState>) -> Result<Settings, String> {
+    service::get_settings(&state.lock().unwrap())
+}
+
+#[tauri::command]
+pub fn update_settings(state: State<AppState>, settings: Settings) -> Result<Settings, String> {
+    service::update_settings(&state.lock().unwrap(), settings)
+}
+
+#[tauri::command]
+pub fn get_api_keys_by_admin(state: State<AppState>, admin_id: String) -> Vec<ApiKey> {
+    service::get_api_keys_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_api_key(
+    state: State<AppState>,
+    admin_id: String,
+    name: String,
+) -> Result<CreatedApiKey, String> {
+    service::create_api_key(&state.lock().unwrap(), admin_id, name)
+}
+
+#[tauri::command]
+pub fn delete_api_key(
+    state: State<AppState>,
+    admin_id: String,
+    key_id: String,
+) -> Result<(), String> {
+    service::delete_api_key(&state.lock().unwrap(), admin_id, key_id)
+}
+
+#[tauri::command]
+pub fn get_webhooks_by_admin(state: State<AppState>, admin_id: String) -> Vec<Webhook> {
+    service::get_webhooks_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_webhook(
+    state: State<AppState>,
+    webhook: CreateWebhookRequest,
+) -> Result<Webhook, String> {
+    service::create_webhook(&state.lock().unwrap(), webhook)
+}
+
+#[tauri::command]
+pub fn update_webhook(
+    state: State<AppState>,
+    admin_id: String,
+    webhook_id: String,
+    webhook: UpdateWebhookRequest,
+) -> Result<Webhook, String> {
+    service::update_webhook(&state.lock().unwrap(), admin_id, webhook_id, webhook)
+}
+
+#[tauri::command]
+pub fn delete_webhook(
+    state: State<AppState>,
+    admin_id: String,
+    webhook_id: String,
+) -> Result<(), String> {
+    service::delete_webhook(&state.lock().unwrap(), admin_id, webhook_id)
+}
+
+#[tauri::command]
+pub fn test_webhook(
+    state: State<AppState>,
+    admin_id: String,
+    webhook_id: String,
+) -> Result<(), String> {
+    service::test_webhook(&state.lock().unwrap(), admin_id, webhook_id)
+}
+
+#[tauri::command]
+pub fn get_webhook_deliveries(
+    state: State<AppState>,
+    admin_id: String,
+    webhook_id: String,
+) -> Result<Vec<WebhookDeliveryLog>, String> {
+    service::get_webhook_deliveries(&state.lock().unwrap(), admin_id, webhook_id)
+}
+
+#[tauri::command]
+pub fn get_shifts_by_admin(state: State<AppState>, admin_id: String) -> Vec<Shift> {
+    service::get_shifts_by_admin(&state.lock().unwrap(), admin_id)
+}
+
+#[tauri::command]
+pub fn create_shift(state: State<AppState>, shift: CreateShiftRequest) -> Result<Shift, String> {
+    service::create_shift(&state.lock().unwrap(), shift)
+}
+
+#[tauri::command]
+pub fn update_shift(
+    state: State<AppState>,
+    shift_id: String,
+    shift: UpdateShiftRequest,
+) -> Result<Shift, String> {
+    service::update_shift(&state.lock().unwrap(), shift_id, shift)
+}
+
+#[tauri::command]
+pub fn delete_shift(state: State<AppState>, shift_id: String) -> Result<(), String> {
+    service::delete_shift(&state.lock().unwrap(), shift_id)
+}
+
+#[tauri::command]
+pub fn update_user_shift(
+    state: State<AppState>,
+    user_id: String,
+    shift_id: Option<String>,
+) -> Result<UserInfo, String> {
+    service::update_user_shift(&state.lock().unwrap(), user_id, shift_id)
+}
+
+#[tauri::command]
+pub fn get_daily_attendance(
+    state: State<AppState>,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailyAttendance>, String> {
+    service::get_daily_attendance(&state.lock().unwrap(), user_id, start_date, end_date)
+}
+
+#[tauri::command]
+pub fn get_daily_summaries(
+    state: State<AppState>,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    service::get_daily_summaries(&state.lock().unwrap(), user_id, start_date, end_date)
+}
+
+#[tauri::command]
+pub fn recompute_daily_summaries(
+    state: State<AppState>,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    service::recompute_daily_summaries(&state.lock().unwrap(), user_id, start_date, end_date)
+}
+
+#[tauri::command]
+pub fn generate_monthly_report(
+    state: State<AppState>,
+    admin_id: String,
+    month: String,
+) -> Result<MonthlyReport, String> {
+    service::generate_monthly_report(&state.lock().unwrap(), admin_id, month)
 }
 
 #[tauri::command]
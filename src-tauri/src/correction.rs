@@ -0,0 +1,258 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::holiday;
+use crate::models::{
+    ApprovalStatus, AttendanceRecord, AttendanceStatus, CorrectionRequest, CreateCorrectionRequest,
+    RecordSource, ReviewRequest, User,
+};
+use crate::overtime;
+use crate::schedule;
+use crate::webhook;
+use chrono::Datelike;
+
+/// 同一自然月（按用户所在时区）内未被驳回的补卡申请数量
+fn used_quota(db: &Database, user: &User, requested_time: i64) -> Result<u32, String> {
+    let tz = checkin::user_timezone(db, user);
+    let month = schedule::local_datetime(requested_time, tz).date();
+    let count = db
+        .get_all_corrections()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|c| c.user_id == user.id && c.status != ApprovalStatus::Rejected)
+        .filter(|c| {
+            let date = schedule::local_datetime(c.requested_time, tz).date();
+            date.year() == month.year() && date.month() == month.month()
+        })
+        .count();
+    Ok(count as u32)
+}
+
+pub fn submit(
+    db: &Database,
+    request: CreateCorrectionRequest,
+) -> Result<CorrectionRequest, String> {
+    if request.reason.trim().is_empty() {
+        return Err("请填写补卡原因".to_string());
+    }
+    if request.requested_time > chrono::Utc::now().timestamp() {
+        return Err("不能为未来的时间补卡".to_string());
+    }
+
+    let user = db
+        .get_user(&request.user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+
+    if let Some(location_id) = &request.location_id {
+        db.get_location(location_id)
+            .map_err(|e| e.to_string())?
+            .filter(|l| user.admin_id.as_ref() == Some(&l.admin_id))
+            .ok_or_else(|| "位置不存在".to_string())?;
+    }
+
+    let quota = db
+        .get_settings()
+        .map_err(|e| e.to_string())?
+        .correction
+        .monthly_quota;
+    if used_quota(db, &user, request.requested_time)? >= quota {
+        return Err(format!("本月补卡次数已用完（每月 {} 次）", quota));
+    }
+
+    let correction = CorrectionRequest::new(request, user.admin_id);
+    db.save_correction(&correction).map_err(|e| e.to_string())?;
+    Ok(correction)
+}
+
+fn pending_for_review(
+    db: &Database,
+    correction_id: &str,
+    review: &ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    let correction = db
+        .get_correction(correction_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "补卡申请不存在".to_string())?;
+
+    if correction.status != ApprovalStatus::Pending {
+        return Err("该申请已处理".to_string());
+    }
+    if correction.admin_id.as_ref() != Some(&review.admin_id) {
+        return Err("无权审批该申请".to_string());
+    }
+
+    Ok(correction)
+}
+
+/// 审批通过后生成一条手动记录，坐标取打卡位置的中心点
+pub fn approve(
+    db: &Database,
+    correction_id: &str,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    let mut correction = pending_for_review(db, correction_id, &review)?;
+
+    let user = db
+        .get_user(&correction.user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    let location_id = correction
+        .location_id
+        .clone()
+        .or(user.location_id.clone())
+        .ok_or_else(|| "无法确定补卡的打卡位置".to_string())?;
+    let location = db
+        .get_location(&location_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "打卡位置不存在".to_string())?;
+
+    let mut record = AttendanceRecord::new(
+        user.id.clone(),
+        location.id.clone(),
+        location.latitude,
+        location.longitude,
+        correction.punch_type,
+        AttendanceStatus::Success,
+        None,
+    );
+    record.timestamp = correction.requested_time;
+    record.source = RecordSource::Manual;
+    record.correction_id = Some(correction.id.clone());
+    record.localize(location.timezone);
+
+    if let Some(shift_id) = &user.shift_id {
+        if let Some(shift) = db.get_shift(shift_id).map_err(|e| e.to_string())? {
+            let date = schedule::work_date(&record, Some(&shift));
+            let calendar = holiday::user_calendar(db, &user, date).map_err(|e| e.to_string())?;
+            record.punctuality = schedule::punctuality(
+                &shift,
+                calendar.as_ref(),
+                record.punch_type,
+                record.timestamp,
+                location.timezone,
+            );
+        }
+    }
+
+    db.save_record(&record).map_err(|e| e.to_string())?;
+    overtime::refresh_after_punch(db, &user, &record)?;
+
+    correction.status = ApprovalStatus::Approved;
+    correction.reviewed_by = Some(review.admin_id);
+    correction.reviewed_at = Some(chrono::Utc::now().timestamp());
+    correction.review_comment = review.comment;
+    correction.record_id = Some(record.id.clone());
+    db.save_correction(&correction).map_err(|e| e.to_string())?;
+    webhook::correction_approved(db, &user, &correction, &record);
+    Ok(correction)
+}
+
+pub fn reject(
+    db: &Database,
+    correction_id: &str,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    let mut correction = pending_for_review(db, correction_id, &review)?;
+
+    correction.status = ApprovalStatus::Rejected;
+    correction.reviewed_by = Some(review.admin_id);
+    correction.reviewed_at = Some(chrono::Utc::now().timestamp());
+    correction.review_comment = review.comment;
+    db.save_correction(&correction).map_err(|e| e.to_string())?;
+    Ok(correction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PunchType;
+    use crate::test_support::{self, at, setup, Fixture};
+
+    fn request(fixture: &Fixture, day: u32) -> CreateCorrectionRequest {
+        CreateCorrectionRequest {
+            user_id: fixture.user.id.clone(),
+            punch_type: PunchType::In,
+            requested_time: schedule::local_timestamp(test_support::TZ, at(day, 9, 0)),
+            location_id: None,
+            reason: "忘记打卡".to_string(),
+        }
+    }
+
+    fn review(admin: &User) -> ReviewRequest {
+        ReviewRequest {
+            admin_id: admin.id.clone(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn rejected_requests_do_not_count_against_the_monthly_quota() {
+        let fixture = setup();
+        let first = submit(&fixture.db, request(&fixture, 10)).unwrap();
+        submit(&fixture.db, request(&fixture, 11)).unwrap();
+        submit(&fixture.db, request(&fixture, 12)).unwrap();
+        let err = submit(&fixture.db, request(&fixture, 13)).unwrap_err();
+        assert_eq!(err, "本月补卡次数已用完（每月 3 次）");
+
+        let mut april = request(&fixture, 13);
+        april.requested_time += 31 * 24 * 3600;
+        submit(&fixture.db, april).unwrap();
+
+        reject(&fixture.db, &first.id, review(&fixture.admin)).unwrap();
+        submit(&fixture.db, request(&fixture, 13)).unwrap();
+    }
+
+    #[test]
+    fn approval_creates_a_linked_manual_record() {
+        let fixture = setup();
+        let correction = submit(&fixture.db, request(&fixture, 14)).unwrap();
+        assert_eq!(correction.status, ApprovalStatus::Pending);
+        assert_eq!(correction.admin_id, Some(fixture.admin.id.clone()));
+
+        let stranger = test_support::admin(&fixture.db, "other");
+        let denied = approve(&fixture.db, &correction.id, review(&stranger)).unwrap_err();
+        assert_eq!(denied, "无权审批该申请");
+
+        let approved = approve(&fixture.db, &correction.id, review(&fixture.admin)).unwrap();
+        assert_eq!(approved.status, ApprovalStatus::Approved);
+        assert_eq!(approved.reviewed_by, Some(fixture.admin.id.clone()));
+
+        let record = fixture
+            .db
+            .get_record(approved.record_id.as_ref().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.source, RecordSource::Manual);
+        assert_eq!(record.correction_id, Some(correction.id.clone()));
+        assert_eq!(record.location_id, fixture.location.id);
+        assert_eq!(record.timestamp, correction.requested_time);
+        assert_eq!(record.status, AttendanceStatus::Success);
+
+        let again = reject(&fixture.db, &correction.id, review(&fixture.admin)).unwrap_err();
+        assert_eq!(again, "该申请已处理");
+    }
+
+    #[test]
+    fn rejection_creates_no_record() {
+        let fixture = setup();
+        let correction = submit(&fixture.db, request(&fixture, 14)).unwrap();
+        let rejected = reject(&fixture.db, &correction.id, review(&fixture.admin)).unwrap();
+        assert_eq!(rejected.status, ApprovalStatus::Rejected);
+        assert_eq!(rejected.record_id, None);
+        assert!(fixture
+            .db
+            .get_records_by_user(&fixture.user.id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn location_of_another_admin_is_rejected() {
+        let fixture = setup();
+        let other_admin = test_support::admin(&fixture.db, "other");
+        let foreign = test_support::location(&fixture.db, "他人位置", &other_admin);
+        let mut request = request(&fixture, 14);
+        request.location_id = Some(foreign.id);
+        assert_eq!(submit(&fixture.db, request).unwrap_err(), "位置不存在");
+    }
+}
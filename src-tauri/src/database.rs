@@ -1,14 +1,87 @@
-use crate::models::{User, Location, AttendanceRecord};
-use sled::{Db, Tree};
+use crate::events::{ChangeKind, DataEvent, EventListener, LocationChangedEvent, RecordSavedEvent, UserChangedEvent};
+use crate::models::{AssignmentTarget, User, UserRole, Location, LocationGroup, UserGroup, AttendanceRecord, Shift, Settings, PendingCheckIn, CorrectionRequest, LeaveType, LeaveRequest, HolidayCalendar, OvertimeRecord, DailySummary, ApiKey, Webhook, WebhookDelivery, WebhookDeliveryLog, DatabaseBackup, BACKUP_FORMAT_VERSION};
+use chrono::{DateTime, Duration, NaiveDate};
+use sled::transaction::ConflictableTransactionResult;
+use sled::{Db, Tree, Transactional};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use dirs;
-use serde_json;
+use serde::de::DeserializeOwned;
 
 pub struct Database {
     db: Arc<Db>,
     users: Arc<Tree>,
     locations: Arc<Tree>,
     records: Arc<Tree>,
+    shifts: Arc<Tree>,
+    location_groups: Arc<Tree>,
+    user_groups: Arc<Tree>,
+    settings: Arc<Tree>,
+    idempotency: Arc<Tree>,
+    pending: Arc<Tree>,
+    corrections: Arc<Tree>,
+    leave_types: Arc<Tree>,
+    leave_requests: Arc<Tree>,
+    leave_adjustments: Arc<Tree>,
+    calendars: Arc<Tree>,
+    overtime: Arc<Tree>,
+    summaries: Arc<Tree>,
+    api_keys: Arc<Tree>,
+    webhooks: Arc<Tree>,
+    webhook_outbox: Arc<Tree>,
+    webhook_log: Arc<Tree>,
+    listener: Option<EventListener>,
+}
+
+const SETTINGS_KEY: &[u8] = b"app";
+
+fn leave_adjustment_key(user_id: &str, leave_type_id: &str, year: i32) -> String {
+    format!("{}:{}:{}", user_id, leave_type_id, year)
+}
+
+/// 日期使用 `YYYY-MM-DD` 格式，同一用户的汇总按日期顺序存放
+fn summary_key(user_id: &str, date: NaiveDate) -> String {
+    format!("{}:{}", user_id, date)
+}
+
+/// 按端点和时间排序，便于按端点查询最近的投递记录
+fn webhook_log_key(log: &WebhookDeliveryLog) -> String {
+    format!("{}:{:020}:{}", log.webhook_id, log.timestamp, log.id)
+}
+
+/// 每个端点保留的投递记录条数
+const WEBHOOK_LOG_LIMIT: usize = 500;
+
+/// 按表名检查条目能否解析为当前数据结构；幂等索引的值是记录 ID，不需要解析
+fn decode_error(tree: &str, value: &[u8]) -> Option<String> {
+    fn check<T: DeserializeOwned>(value: &[u8]) -> Option<String> {
+        serde_json::from_slice::<T>(value).err().map(|e| e.to_string())
+    }
+    
+    match tree {
+        "users" => check::<User>(value),
+        "locations" => check::<Location>(value),
+        "records" => check::<AttendanceRecord>(value),
+        "shifts" => check::<Shift>(value),
+        "location_groups" => check::<LocationGroup>(value),
+        "user_groups" => check::<UserGroup>(value),
+        "settings" => check::<Settings>(value),
+        "pending" => check::<PendingCheckIn>(value),
+        "corrections" => check::<CorrectionRequest>(value),
+        "leave_types" => check::<LeaveType>(value),
+        "leave_requests" => check::<LeaveRequest>(value),
+        "leave_adjustments" => check::<f64>(value),
+        "calendars" => check::<HolidayCalendar>(value),
+        "overtime" => check::<OvertimeRecord>(value),
+        "api_keys" => check::<ApiKey>(value),
+        "webhooks" => check::<Webhook>(value),
+        "webhook_outbox" => check::<WebhookDelivery>(value),
+        "webhook_log" => check::<WebhookDeliveryLog>(value),
+        _ => None,
+    }
+}
+
+fn idempotency_index(user_id: &str, key: &str) -> String {
+    format!("{}:{}", user_id, key)
 }
 
 impl Database {
@@ -19,14 +92,116 @@ impl Database {
         let app_data_dir = data_dir.join("attendance");
         std::fs::create_dir_all(&app_data_dir)?;
         
-        let db_path = app_data_dir.join("attendance_db");
-        let db = Arc::new(sled::open(db_path)?);
+        Self::open(app_data_dir.join("attendance_db"))
+    }
+    
+    /// 打开指定目录下的数据库，服务端和命令行工具用它访问共享的数据目录
+    pub fn open(db_path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_db(sled::open(db_path)?)
+    }
+    
+    /// 关闭后自动删除的数据库，供测试使用
+    #[cfg(test)]
+    pub fn temporary() -> Self {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .expect("Failed to open temporary database");
+        Self::from_db(db).expect("Failed to open temporary database")
+    }
+    
+    fn from_db(db: Db) -> Result<Self, Box<dyn std::error::Error>> {
+        let db = Arc::new(db);
         
         let users = Arc::new(db.open_tree("users")?);
         let locations = Arc::new(db.open_tree("locations")?);
         let records = Arc::new(db.open_tree("records")?);
+        let shifts = Arc::new(db.open_tree("shifts")?);
+        let location_groups = Arc::new(db.open_tree("location_groups")?);
+        let user_groups = Arc::new(db.open_tree("user_groups")?);
+        let settings = Arc::new(db.open_tree("settings")?);
+        let idempotency = Arc::new(db.open_tree("idempotency")?);
+        let pending = Arc::new(db.open_tree("pending")?);
+        let corrections = Arc::new(db.open_tree("corrections")?);
+        let leave_types = Arc::new(db.open_tree("leave_types")?);
+        let leave_requests = Arc::new(db.open_tree("leave_requests")?);
+        let leave_adjustments = Arc::new(db.open_tree("leave_adjustments")?);
+        let calendars = Arc::new(db.open_tree("calendars")?);
+        let overtime = Arc::new(db.open_tree("overtime")?);
+        let summaries = Arc::new(db.open_tree("daily_summaries")?);
+        let api_keys = Arc::new(db.open_tree("api_keys")?);
+        let webhooks = Arc::new(db.open_tree("webhooks")?);
+        let webhook_outbox = Arc::new(db.open_tree("webhook_outbox")?);
+        let webhook_log = Arc::new(db.open_tree("webhook_log")?);
         
-        Ok(Self { db, users, locations, records })
+        Ok(Self {
+            db,
+            users,
+            locations,
+            records,
+            shifts,
+            location_groups,
+            user_groups,
+            settings,
+            idempotency,
+            pending,
+            corrections,
+            leave_types,
+            leave_requests,
+            leave_adjustments,
+            calendars,
+            overtime,
+            summaries,
+            api_keys,
+            webhooks,
+            webhook_outbox,
+            webhook_log,
+            listener: None,
+        })
+    }
+    
+    /// 设置后用户、位置和打卡记录写入成功时都会通知监听者
+    pub fn set_event_listener(&mut self, listener: EventListener) {
+        self.listener = Some(listener);
+    }
+    
+    /// 员工归属其管理员，管理员本人的数据归属自己
+    fn owning_admin(user: &User) -> Option<String> {
+        match user.role {
+            UserRole::Admin => Some(user.id.clone()),
+            UserRole::User => user.admin_id.clone(),
+        }
+    }
+    
+    fn notify_user(&self, user: &User, change: ChangeKind) {
+        let Some(listener) = &self.listener else { return };
+        if let Some(admin_id) = Self::owning_admin(user) {
+            listener(&DataEvent::UserChanged(UserChangedEvent {
+                admin_id,
+                change,
+                user: user.clone().into(),
+            }));
+        }
+    }
+    
+    fn notify_location(&self, location: &Location, change: ChangeKind) {
+        let Some(listener) = &self.listener else { return };
+        listener(&DataEvent::LocationChanged(LocationChangedEvent {
+            admin_id: location.admin_id.clone(),
+            change,
+            location: location.clone(),
+        }));
+    }
+    
+    fn notify_record(&self, record: &AttendanceRecord) {
+        let Some(listener) = &self.listener else { return };
+        let admin_id = self.get_user(&record.user_id).ok().flatten().and_then(|u| Self::owning_admin(&u));
+        if let Some(admin_id) = admin_id {
+            listener(&DataEvent::RecordSaved(RecordSavedEvent {
+                admin_id,
+                record: record.clone(),
+            }));
+        }
     }
     
     pub fn init_default_admin(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -78,6 +253,28 @@ impl Database {
         let key = user.id.as_bytes();
         let value = serde_json::to_vec(user)?;
         self.users.insert(key, value)?;
+        self.invalidate_user_summaries(&user.id)?;
+        self.notify_user(user, ChangeKind::Saved);
+        Ok(())
+    }
+    
+    /// 在同一事务中写入多个用户，任一失败则全部不写入
+    pub fn save_users(&self, users: &[User]) -> Result<(), Box<dyn std::error::Error>> {
+        let entries = users
+            .iter()
+            .map(|u| Ok((u.id.clone(), serde_json::to_vec(u)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        
+        self.users.transaction(|tree| -> ConflictableTransactionResult<(), sled::Error> {
+            for (key, value) in &entries {
+                tree.insert(key.as_bytes(), value.as_slice())?;
+            }
+            Ok(())
+        })?;
+        for user in users {
+            self.invalidate_user_summaries(&user.id)?;
+            self.notify_user(user, ChangeKind::Saved);
+        }
         Ok(())
     }
     
@@ -112,7 +309,11 @@ impl Database {
     }
     
     pub fn delete_user(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.users.remove(id.as_bytes())?;
+        let removed = self.users.remove(id.as_bytes())?;
+        self.invalidate_user_summaries(id)?;
+        if let Some(value) = removed {
+            self.notify_user(&serde_json::from_slice(&value)?, ChangeKind::Deleted);
+        }
         Ok(())
     }
     
@@ -120,6 +321,29 @@ impl Database {
         let key = location.id.as_bytes();
         let value = serde_json::to_vec(location)?;
         self.locations.insert(key, value)?;
+        self.invalidate_location_summaries(std::slice::from_ref(&location.id))?;
+        self.notify_location(location, ChangeKind::Saved);
+        Ok(())
+    }
+    
+    /// 在同一事务中写入多个位置
+    pub fn save_locations(&self, locations: &[Location]) -> Result<(), Box<dyn std::error::Error>> {
+        let entries = locations
+            .iter()
+            .map(|l| Ok((l.id.clone(), serde_json::to_vec(l)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        
+        self.locations.transaction(|tree| -> ConflictableTransactionResult<(), sled::Error> {
+            for (key, value) in &entries {
+                tree.insert(key.as_bytes(), value.as_slice())?;
+            }
+            Ok(())
+        })?;
+        let ids: Vec<String> = locations.iter().map(|l| l.id.clone()).collect();
+        self.invalidate_location_summaries(&ids)?;
+        for location in locations {
+            self.notify_location(location, ChangeKind::Saved);
+        }
         Ok(())
     }
     
@@ -143,17 +367,133 @@ impl Database {
     }
     
     pub fn delete_location(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
-        self.locations.remove(id.as_bytes())?;
+        let removed = self.locations.remove(id.as_bytes())?;
+        self.invalidate_location_summaries(&[id.to_string()])?;
+        if let Some(value) = removed {
+            self.notify_location(&serde_json::from_slice(&value)?, ChangeKind::Deleted);
+        }
+        Ok(())
+    }
+    
+    pub fn save_location_group(&self, group: &LocationGroup) -> Result<(), Box<dyn std::error::Error>> {
+        let key = group.id.as_bytes();
+        let value = serde_json::to_vec(group)?;
+        self.location_groups.insert(key, value)?;
+        self.invalidate_assigned_summaries(&[], std::slice::from_ref(&group.id))?;
+        Ok(())
+    }
+    
+    pub fn get_location_group(&self, id: &str) -> Result<Option<LocationGroup>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.location_groups.get(id.as_bytes())? {
+            let group: LocationGroup = serde_json::from_slice(&value)?;
+            Ok(Some(group))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_location_groups(&self) -> Result<Vec<LocationGroup>, Box<dyn std::error::Error>> {
+        let mut groups = Vec::new();
+        for item in self.location_groups.iter() {
+            let (_, value) = item?;
+            let group: LocationGroup = serde_json::from_slice(&value)?;
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+    
+    pub fn delete_location_group(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.location_groups.remove(id.as_bytes())?;
+        self.invalidate_assigned_summaries(&[], &[id.to_string()])?;
         Ok(())
     }
     
+    pub fn save_user_group(&self, group: &UserGroup) -> Result<(), Box<dyn std::error::Error>> {
+        let key = group.id.as_bytes();
+        let value = serde_json::to_vec(group)?;
+        let previous = self.user_groups.insert(key, value)?;
+        // 移出分组的成员同样受影响
+        if let Some(value) = previous {
+            self.invalidate_member_summaries(&serde_json::from_slice(&value)?)?;
+        }
+        self.invalidate_member_summaries(group)?;
+        Ok(())
+    }
+    
+    pub fn get_user_group(&self, id: &str) -> Result<Option<UserGroup>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.user_groups.get(id.as_bytes())? {
+            let group: UserGroup = serde_json::from_slice(&value)?;
+            Ok(Some(group))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_user_groups(&self) -> Result<Vec<UserGroup>, Box<dyn std::error::Error>> {
+        let mut groups = Vec::new();
+        for item in self.user_groups.iter() {
+            let (_, value) = item?;
+            let group: UserGroup = serde_json::from_slice(&value)?;
+            groups.push(group);
+        }
+        Ok(groups)
+    }
+    
+    pub fn delete_user_group(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(value) = self.user_groups.remove(id.as_bytes())? {
+            self.invalidate_member_summaries(&serde_json::from_slice(&value)?)?;
+        }
+        Ok(())
+    }
+    
+    /// 带幂等键的记录会同时写入幂等索引
     pub fn save_record(&self, record: &AttendanceRecord) -> Result<(), Box<dyn std::error::Error>> {
         let key = record.id.as_bytes();
         let value = serde_json::to_vec(record)?;
-        self.records.insert(key, value)?;
+        let idempotency_key = record
+            .idempotency_key
+            .as_ref()
+            .map(|k| idempotency_index(&record.user_id, k));
+        
+        (&*self.records, &*self.idempotency).transaction(|(records, keys)| -> ConflictableTransactionResult<(), sled::Error> {
+            records.insert(key, value.as_slice())?;
+            if let Some(idempotency_key) = &idempotency_key {
+                keys.insert(idempotency_key.as_bytes(), key)?;
+            }
+            Ok(())
+        })?;
+        
+        // 跨夜班次的打卡可能属于前一天或后一天
+        let date = record.local_date.unwrap_or_else(|| {
+            DateTime::from_timestamp(record.timestamp, 0)
+                .unwrap_or_default()
+                .date_naive()
+        });
+        self.invalidate_summaries(&record.user_id, date - Duration::days(1), date + Duration::days(1))?;
+        self.notify_record(record);
         Ok(())
     }
     
+    pub fn get_record(&self, id: &str) -> Result<Option<AttendanceRecord>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.records.get(id.as_bytes())? {
+            let record: AttendanceRecord = serde_json::from_slice(&value)?;
+            Ok(Some(record))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_record_by_idempotency_key(
+        &self,
+        user_id: &str,
+        key: &str,
+    ) -> Result<Option<AttendanceRecord>, Box<dyn std::error::Error>> {
+        match self.idempotency.get(idempotency_index(user_id, key).as_bytes())? {
+            Some(record_id) => self.get_record(&String::from_utf8(record_id.to_vec())?),
+            None => Ok(None),
+        }
+    }
+    
     pub fn get_all_records(&self) -> Result<Vec<AttendanceRecord>, Box<dyn std::error::Error>> {
         let mut records = Vec::new();
         for item in self.records.iter() {
@@ -175,4 +515,708 @@ impl Database {
         }
         Ok(records)
     }
+    
+    pub fn save_shift(&self, shift: &Shift) -> Result<(), Box<dyn std::error::Error>> {
+        let key = shift.id.as_bytes();
+        let value = serde_json::to_vec(shift)?;
+        self.shifts.insert(key, value)?;
+        self.invalidate_shift_summaries(&shift.id)?;
+        Ok(())
+    }
+    
+    pub fn get_shift(&self, id: &str) -> Result<Option<Shift>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.shifts.get(id.as_bytes())? {
+            let shift: Shift = serde_json::from_slice(&value)?;
+            Ok(Some(shift))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_shifts(&self) -> Result<Vec<Shift>, Box<dyn std::error::Error>> {
+        let mut shifts = Vec::new();
+        for item in self.shifts.iter() {
+            let (_, value) = item?;
+            let shift: Shift = serde_json::from_slice(&value)?;
+            shifts.push(shift);
+        }
+        Ok(shifts)
+    }
+    
+    pub fn delete_shift(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.shifts.remove(id.as_bytes())?;
+        self.invalidate_shift_summaries(id)?;
+        Ok(())
+    }
+    
+    pub fn get_settings(&self) -> Result<Settings, Box<dyn std::error::Error>> {
+        match self.settings.get(SETTINGS_KEY)? {
+            Some(value) => Ok(serde_json::from_slice(&value)?),
+            None => Ok(Settings::default()),
+        }
+    }
+    
+    pub fn save_settings(&self, settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+        let value = serde_json::to_vec(settings)?;
+        self.settings.insert(SETTINGS_KEY, value)?;
+        Ok(())
+    }
+    
+    pub fn save_pending(&self, pending: &PendingCheckIn) -> Result<(), Box<dyn std::error::Error>> {
+        let key = pending.id.as_bytes();
+        let value = serde_json::to_vec(pending)?;
+        self.pending.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_all_pending(&self) -> Result<Vec<PendingCheckIn>, Box<dyn std::error::Error>> {
+        let mut pending = Vec::new();
+        for item in self.pending.iter() {
+            let (_, value) = item?;
+            let entry: PendingCheckIn = serde_json::from_slice(&value)?;
+            pending.push(entry);
+        }
+        Ok(pending)
+    }
+    
+    pub fn save_correction(&self, correction: &CorrectionRequest) -> Result<(), Box<dyn std::error::Error>> {
+        let key = correction.id.as_bytes();
+        let value = serde_json::to_vec(correction)?;
+        self.corrections.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_correction(&self, id: &str) -> Result<Option<CorrectionRequest>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.corrections.get(id.as_bytes())? {
+            let correction: CorrectionRequest = serde_json::from_slice(&value)?;
+            Ok(Some(correction))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_corrections(&self) -> Result<Vec<CorrectionRequest>, Box<dyn std::error::Error>> {
+        let mut corrections = Vec::new();
+        for item in self.corrections.iter() {
+            let (_, value) = item?;
+            let correction: CorrectionRequest = serde_json::from_slice(&value)?;
+            corrections.push(correction);
+        }
+        Ok(corrections)
+    }
+    
+    pub fn save_leave_type(&self, leave_type: &LeaveType) -> Result<(), Box<dyn std::error::Error>> {
+        let key = leave_type.id.as_bytes();
+        let value = serde_json::to_vec(leave_type)?;
+        self.leave_types.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_leave_type(&self, id: &str) -> Result<Option<LeaveType>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.leave_types.get(id.as_bytes())? {
+            let leave_type: LeaveType = serde_json::from_slice(&value)?;
+            Ok(Some(leave_type))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_leave_types(&self) -> Result<Vec<LeaveType>, Box<dyn std::error::Error>> {
+        let mut leave_types = Vec::new();
+        for item in self.leave_types.iter() {
+            let (_, value) = item?;
+            let leave_type: LeaveType = serde_json::from_slice(&value)?;
+            leave_types.push(leave_type);
+        }
+        Ok(leave_types)
+    }
+    
+    pub fn delete_leave_type(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.leave_types.remove(id.as_bytes())?;
+        Ok(())
+    }
+    
+    pub fn save_leave_request(&self, request: &LeaveRequest) -> Result<(), Box<dyn std::error::Error>> {
+        let key = request.id.as_bytes();
+        let value = serde_json::to_vec(request)?;
+        self.leave_requests.insert(key, value)?;
+        self.invalidate_summaries(&request.user_id, request.start_date, request.end_date)?;
+        Ok(())
+    }
+    
+    pub fn get_leave_request(&self, id: &str) -> Result<Option<LeaveRequest>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.leave_requests.get(id.as_bytes())? {
+            let request: LeaveRequest = serde_json::from_slice(&value)?;
+            Ok(Some(request))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_leave_requests(&self) -> Result<Vec<LeaveRequest>, Box<dyn std::error::Error>> {
+        let mut requests = Vec::new();
+        for item in self.leave_requests.iter() {
+            let (_, value) = item?;
+            let request: LeaveRequest = serde_json::from_slice(&value)?;
+            requests.push(request);
+        }
+        Ok(requests)
+    }
+    
+    pub fn get_leave_requests_by_user(&self, user_id: &str) -> Result<Vec<LeaveRequest>, Box<dyn std::error::Error>> {
+        Ok(self
+            .get_all_leave_requests()?
+            .into_iter()
+            .filter(|r| r.user_id == user_id)
+            .collect())
+    }
+    
+    /// 管理员手动调整的额度（如上年结转），单位为天
+    pub fn get_leave_adjustment(
+        &self,
+        user_id: &str,
+        leave_type_id: &str,
+        year: i32,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        match self.leave_adjustments.get(leave_adjustment_key(user_id, leave_type_id, year).as_bytes())? {
+            Some(value) => Ok(serde_json::from_slice(&value)?),
+            None => Ok(0.0),
+        }
+    }
+    
+    pub fn save_leave_adjustment(
+        &self,
+        user_id: &str,
+        leave_type_id: &str,
+        year: i32,
+        days: f64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let value = serde_json::to_vec(&days)?;
+        self.leave_adjustments.insert(leave_adjustment_key(user_id, leave_type_id, year).as_bytes(), value)?;
+        Ok(())
+    }
+    
+    pub fn save_calendar(&self, calendar: &HolidayCalendar) -> Result<(), Box<dyn std::error::Error>> {
+        let key = calendar.id.as_bytes();
+        let value = serde_json::to_vec(calendar)?;
+        self.calendars.insert(key, value)?;
+        self.invalidate_calendar_summaries(&calendar.id)?;
+        Ok(())
+    }
+    
+    pub fn get_calendar(&self, id: &str) -> Result<Option<HolidayCalendar>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.calendars.get(id.as_bytes())? {
+            let calendar: HolidayCalendar = serde_json::from_slice(&value)?;
+            Ok(Some(calendar))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_calendars(&self) -> Result<Vec<HolidayCalendar>, Box<dyn std::error::Error>> {
+        let mut calendars = Vec::new();
+        for item in self.calendars.iter() {
+            let (_, value) = item?;
+            let calendar: HolidayCalendar = serde_json::from_slice(&value)?;
+            calendars.push(calendar);
+        }
+        Ok(calendars)
+    }
+    
+    pub fn delete_calendar(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.calendars.remove(id.as_bytes())?;
+        self.invalidate_calendar_summaries(id)?;
+        Ok(())
+    }
+    
+    pub fn save_overtime(&self, overtime: &OvertimeRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let key = overtime.id.as_bytes();
+        let value = serde_json::to_vec(overtime)?;
+        self.overtime.insert(key, value)?;
+        self.invalidate_summaries(&overtime.user_id, overtime.date, overtime.date)?;
+        Ok(())
+    }
+    
+    pub fn get_overtime(&self, id: &str) -> Result<Option<OvertimeRecord>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.overtime.get(id.as_bytes())? {
+            let overtime: OvertimeRecord = serde_json::from_slice(&value)?;
+            Ok(Some(overtime))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_overtime(&self) -> Result<Vec<OvertimeRecord>, Box<dyn std::error::Error>> {
+        let mut overtime = Vec::new();
+        for item in self.overtime.iter() {
+            let (_, value) = item?;
+            let record: OvertimeRecord = serde_json::from_slice(&value)?;
+            overtime.push(record);
+        }
+        Ok(overtime)
+    }
+    
+    pub fn get_overtime_by_user(&self, user_id: &str) -> Result<Vec<OvertimeRecord>, Box<dyn std::error::Error>> {
+        Ok(self
+            .get_all_overtime()?
+            .into_iter()
+            .filter(|o| o.user_id == user_id)
+            .collect())
+    }
+    
+    pub fn delete_overtime(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(value) = self.overtime.remove(id.as_bytes())? {
+            let overtime: OvertimeRecord = serde_json::from_slice(&value)?;
+            self.invalidate_summaries(&overtime.user_id, overtime.date, overtime.date)?;
+        }
+        Ok(())
+    }
+    
+    pub fn save_api_key(&self, api_key: &ApiKey) -> Result<(), Box<dyn std::error::Error>> {
+        let key = api_key.id.as_bytes();
+        let value = serde_json::to_vec(api_key)?;
+        self.api_keys.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.api_keys.get(id.as_bytes())? {
+            let api_key: ApiKey = serde_json::from_slice(&value)?;
+            Ok(Some(api_key))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_api_keys(&self) -> Result<Vec<ApiKey>, Box<dyn std::error::Error>> {
+        let mut api_keys = Vec::new();
+        for item in self.api_keys.iter() {
+            let (_, value) = item?;
+            let api_key: ApiKey = serde_json::from_slice(&value)?;
+            api_keys.push(api_key);
+        }
+        Ok(api_keys)
+    }
+    
+    pub fn delete_api_key(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.api_keys.remove(id.as_bytes())?;
+        Ok(())
+    }
+    
+    pub fn save_webhook(&self, webhook: &Webhook) -> Result<(), Box<dyn std::error::Error>> {
+        let key = webhook.id.as_bytes();
+        let value = serde_json::to_vec(webhook)?;
+        self.webhooks.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_webhook(&self, id: &str) -> Result<Option<Webhook>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.webhooks.get(id.as_bytes())? {
+            let webhook: Webhook = serde_json::from_slice(&value)?;
+            Ok(Some(webhook))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    pub fn get_all_webhooks(&self) -> Result<Vec<Webhook>, Box<dyn std::error::Error>> {
+        let mut webhooks = Vec::new();
+        for item in self.webhooks.iter() {
+            let (_, value) = item?;
+            let webhook: Webhook = serde_json::from_slice(&value)?;
+            webhooks.push(webhook);
+        }
+        Ok(webhooks)
+    }
+    
+    /// 同时删除该端点未投递的事件和投递记录
+    pub fn delete_webhook(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.webhooks.remove(id.as_bytes())?;
+        for delivery in self.get_all_deliveries()? {
+            if delivery.webhook_id == id {
+                self.webhook_outbox.remove(delivery.id.as_bytes())?;
+            }
+        }
+        for item in self.webhook_log.scan_prefix(format!("{}:", id).as_bytes()) {
+            let (key, _) = item?;
+            self.webhook_log.remove(key)?;
+        }
+        Ok(())
+    }
+    
+    pub fn save_delivery(&self, delivery: &WebhookDelivery) -> Result<(), Box<dyn std::error::Error>> {
+        let key = delivery.id.as_bytes();
+        let value = serde_json::to_vec(delivery)?;
+        self.webhook_outbox.insert(key, value)?;
+        Ok(())
+    }
+    
+    pub fn get_all_deliveries(&self) -> Result<Vec<WebhookDelivery>, Box<dyn std::error::Error>> {
+        let mut deliveries = Vec::new();
+        for item in self.webhook_outbox.iter() {
+            let (_, value) = item?;
+            let delivery: WebhookDelivery = serde_json::from_slice(&value)?;
+            deliveries.push(delivery);
+        }
+        Ok(deliveries)
+    }
+    
+    pub fn delete_delivery(&self, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.webhook_outbox.remove(id.as_bytes())?;
+        Ok(())
+    }
+    
+    /// 超过保留条数时删除该端点最早的记录
+    pub fn save_delivery_log(&self, log: &WebhookDeliveryLog) -> Result<(), Box<dyn std::error::Error>> {
+        let value = serde_json::to_vec(log)?;
+        self.webhook_log.insert(webhook_log_key(log).as_bytes(), value)?;
+        
+        let prefix = format!("{}:", log.webhook_id);
+        let count = self.webhook_log.scan_prefix(prefix.as_bytes()).count();
+        for item in self.webhook_log.scan_prefix(prefix.as_bytes()).take(count.saturating_sub(WEBHOOK_LOG_LIMIT)) {
+            let (key, _) = item?;
+            self.webhook_log.remove(key)?;
+        }
+        Ok(())
+    }
+    
+    /// 按时间倒序返回端点的投递记录
+    pub fn get_delivery_logs(&self, webhook_id: &str) -> Result<Vec<WebhookDeliveryLog>, Box<dyn std::error::Error>> {
+        let mut logs = Vec::new();
+        for item in self.webhook_log.scan_prefix(format!("{}:", webhook_id).as_bytes()).rev() {
+            let (_, value) = item?;
+            let log: WebhookDeliveryLog = serde_json::from_slice(&value)?;
+            logs.push(log);
+        }
+        Ok(logs)
+    }
+    
+    pub fn save_summary(&self, summary: &DailySummary) -> Result<(), Box<dyn std::error::Error>> {
+        let key = summary_key(&summary.user_id, summary.date);
+        let value = serde_json::to_vec(summary)?;
+        self.summaries.insert(key.as_bytes(), value)?;
+        Ok(())
+    }
+    
+    pub fn get_summaries(
+        &self,
+        user_id: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<DailySummary>, Box<dyn std::error::Error>> {
+        let start = summary_key(user_id, start_date);
+        let end = summary_key(user_id, end_date);
+        let mut summaries = Vec::new();
+        for item in self.summaries.range(start.as_bytes()..=end.as_bytes()) {
+            let (_, value) = item?;
+            let summary: DailySummary = serde_json::from_slice(&value)?;
+            summaries.push(summary);
+        }
+        Ok(summaries)
+    }
+    
+    pub fn invalidate_summaries(
+        &self,
+        user_id: &str,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let start = summary_key(user_id, start_date);
+        let end = summary_key(user_id, end_date);
+        for item in self.summaries.range(start.as_bytes()..=end.as_bytes()) {
+            let (key, _) = item?;
+            self.summaries.remove(key)?;
+        }
+        Ok(())
+    }
+    
+    pub fn invalidate_user_summaries(&self, user_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        for item in self.summaries.scan_prefix(format!("{}:", user_id).as_bytes()) {
+            let (key, _) = item?;
+            self.summaries.remove(key)?;
+        }
+        Ok(())
+    }
+    
+    /// 清除满足条件的用户的全部每日汇总
+    fn invalidate_summaries_where(&self, affected: impl Fn(&User) -> bool) -> Result<(), Box<dyn std::error::Error>> {
+        for user in self.get_all_users()? {
+            if affected(&user) {
+                self.invalidate_user_summaries(&user.id)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 主位置或额外分配的位置、位置组属于给定范围的用户
+    fn invalidate_assigned_summaries(
+        &self,
+        location_ids: &[String],
+        group_ids: &[String],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.invalidate_summaries_where(|user| {
+            user.location_id.as_ref().is_some_and(|id| location_ids.contains(id))
+                || user.location_assignments.iter().any(|a| match &a.target {
+                    AssignmentTarget::Location(id) => location_ids.contains(id),
+                    AssignmentTarget::Group(id) => group_ids.contains(id),
+                })
+        })
+    }
+    
+    /// 位置变化影响分配到这些位置或包含它们的位置组的用户
+    fn invalidate_location_summaries(&self, location_ids: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let group_ids: Vec<String> = self
+            .get_all_location_groups()?
+            .into_iter()
+            .filter(|g| g.location_ids.iter().any(|id| location_ids.contains(id)))
+            .map(|g| g.id)
+            .collect();
+        self.invalidate_assigned_summaries(location_ids, &group_ids)
+    }
+    
+    fn invalidate_member_summaries(&self, group: &UserGroup) -> Result<(), Box<dyn std::error::Error>> {
+        for user_id in &group.user_ids {
+            self.invalidate_user_summaries(user_id)?;
+        }
+        Ok(())
+    }
+    
+    fn invalidate_shift_summaries(&self, shift_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.invalidate_summaries_where(|user| user.shift_id.as_deref() == Some(shift_id))
+    }
+    
+    /// 日历变化影响所在员工分组、位置组或位置使用该日历的用户
+    fn invalidate_calendar_summaries(&self, calendar_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let uses = |id: &Option<String>| id.as_deref() == Some(calendar_id);
+        let location_ids: Vec<String> = self
+            .get_all_locations()?
+            .into_iter()
+            .filter(|l| uses(&l.calendar_id))
+            .map(|l| l.id)
+            .collect();
+        let group_ids: Vec<String> = self
+            .get_all_location_groups()?
+            .into_iter()
+            .filter(|g| uses(&g.calendar_id))
+            .map(|g| g.id)
+            .collect();
+        self.invalidate_assigned_summaries(&location_ids, &group_ids)?;
+        for group in self.get_all_user_groups()? {
+            if uses(&group.calendar_id) {
+                self.invalidate_member_summaries(&group)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 重新本地化全部记录等需要重算所有人汇总时调用
+    pub fn clear_summaries(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.summaries.clear()?;
+        Ok(())
+    }
+    
+    /// 需要备份的数据，每日汇总可以重新计算因此不包含在内
+    fn backup_trees(&self) -> [(&'static str, &Tree); 19] {
+        [
+            ("users", &self.users),
+            ("locations", &self.locations),
+            ("records", &self.records),
+            ("shifts", &self.shifts),
+            ("location_groups", &self.location_groups),
+            ("user_groups", &self.user_groups),
+            ("settings", &self.settings),
+            ("idempotency", &self.idempotency),
+            ("pending", &self.pending),
+            ("corrections", &self.corrections),
+            ("leave_types", &self.leave_types),
+            ("leave_requests", &self.leave_requests),
+            ("leave_adjustments", &self.leave_adjustments),
+            ("calendars", &self.calendars),
+            ("overtime", &self.overtime),
+            ("api_keys", &self.api_keys),
+            ("webhooks", &self.webhooks),
+            ("webhook_outbox", &self.webhook_outbox),
+            ("webhook_log", &self.webhook_log),
+        ]
+    }
+    
+    pub fn backup(&self) -> Result<DatabaseBackup, Box<dyn std::error::Error>> {
+        let mut trees = BTreeMap::new();
+        for (name, tree) in self.backup_trees() {
+            let mut entries = Vec::new();
+            for item in tree.iter() {
+                let (key, value) = item?;
+                entries.push((
+                    String::from_utf8(key.to_vec())?,
+                    String::from_utf8(value.to_vec())?,
+                ));
+            }
+            trees.insert(name.to_string(), entries);
+        }
+        
+        Ok(DatabaseBackup {
+            version: BACKUP_FORMAT_VERSION,
+            created_at: chrono::Utc::now().timestamp(),
+            trees,
+        })
+    }
+    
+    /// 无法按当前数据结构解析的条目，格式为 `表名/键: 错误`
+    pub fn undecodable_entries(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut problems = Vec::new();
+        for (name, tree) in self.backup_trees() {
+            for item in tree.iter() {
+                let (key, value) = item?;
+                if let Some(e) = decode_error(name, &value) {
+                    problems.push(format!("{}/{}: {}", name, String::from_utf8_lossy(&key), e));
+                }
+            }
+        }
+        
+        // 幂等索引的值是打卡记录的 ID
+        for item in self.idempotency.iter() {
+            let (key, record_id) = item?;
+            if !self.records.contains_key(&record_id)? {
+                problems.push(format!(
+                    "idempotency/{}: 对应的打卡记录 {} 不存在",
+                    String::from_utf8_lossy(&key),
+                    String::from_utf8_lossy(&record_id)
+                ));
+            }
+        }
+        Ok(problems)
+    }
+    
+    /// 用备份替换现有数据，备份中没有的数据会被清空。
+    /// 先校验备份中的所有条目，再在同一事务中替换所有数据表，任一步失败时现有数据保持不变
+    pub fn restore(&self, backup: &DatabaseBackup) -> Result<(), Box<dyn std::error::Error>> {
+        if backup.version != BACKUP_FORMAT_VERSION {
+            return Err(format!("不支持的备份版本: {}", backup.version).into());
+        }
+        let trees = self.backup_trees();
+        if let Some(name) = backup.trees.keys().find(|name| !trees.iter().any(|(n, _)| n == name)) {
+            return Err(format!("备份中包含未知的数据表: {}", name).into());
+        }
+        
+        let mut problems = Vec::new();
+        for (name, entries) in &backup.trees {
+            for (key, value) in entries {
+                if let Some(e) = decode_error(name, value.as_bytes()) {
+                    problems.push(format!("{}/{}: {}", name, key, e));
+                }
+            }
+        }
+        if let Some(first) = problems.first() {
+            return Err(format!("备份中有 {} 条数据无法解析，例如 {}", problems.len(), first).into());
+        }
+        
+        let existing = trees
+            .iter()
+            .map(|(_, tree)| tree.iter().keys().collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let handles: Vec<&Tree> = trees.iter().map(|(_, tree)| *tree).collect();
+        handles.as_slice().transaction(|views| -> ConflictableTransactionResult<(), sled::Error> {
+            for (((name, _), view), keys) in trees.iter().zip(views).zip(&existing) {
+                for key in keys {
+                    view.remove(key)?;
+                }
+                for (key, value) in backup.trees.get(*name).into_iter().flatten() {
+                    view.insert(key.as_bytes(), value.as_bytes())?;
+                }
+            }
+            Ok(())
+        })?;
+        self.summaries.clear()?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{setup, Fixture};
+
+    #[test]
+    fn user_events_do_not_carry_passwords() {
+        let Fixture {
+            mut db,
+            admin,
+            user,
+            ..
+        } = setup();
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = events.clone();
+        db.set_event_listener(Arc::new(move |event: &DataEvent| {
+            if let DataEvent::UserChanged(e) = event {
+                captured
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::to_value(e).unwrap());
+            }
+        }));
+
+        db.save_user(&user).unwrap();
+        db.save_users(&[user]).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        for event in events.iter() {
+            assert_eq!(event["adminId"], admin.id);
+            assert_eq!(event["user"]["username"], "alice");
+            assert!(event["user"].get("password").is_none());
+        }
+    }
+
+    #[test]
+    fn restore_replaces_all_data_with_the_backup() {
+        let Fixture {
+            db,
+            admin,
+            location,
+            ..
+        } = setup();
+        let backup = db.backup().unwrap();
+
+        let added = Location::new("分部".to_string(), 30.0, 120.0, 100.0, admin.id.clone());
+        db.save_location(&added).unwrap();
+        db.delete_location(&location.id).unwrap();
+
+        db.restore(&backup).unwrap();
+        let locations = db.get_all_locations().unwrap();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].id, location.id);
+        assert!(db.get_user(&admin.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn invalid_backups_leave_existing_data_untouched() {
+        let Fixture {
+            db,
+            admin,
+            location,
+            ..
+        } = setup();
+        let mut backup = db.backup().unwrap();
+        backup.trees.get_mut("locations").unwrap().clear();
+        // 排在最后的数据表中有无法解析的条目
+        backup
+            .trees
+            .entry("webhook_log".to_string())
+            .or_default()
+            .push(("broken".to_string(), "not json".to_string()));
+
+        let error = db.restore(&backup).unwrap_err().to_string();
+        assert!(error.contains("webhook_log/broken"), "{}", error);
+        assert_eq!(db.get_all_locations().unwrap().len(), 1);
+        assert!(db.get_location(&location.id).unwrap().is_some());
+        assert!(db.get_user(&admin.id).unwrap().is_some());
+
+        let mut unknown = db.backup().unwrap();
+        unknown.trees.insert("sessions".to_string(), Vec::new());
+        assert_eq!(
+            db.restore(&unknown).unwrap_err().to_string(),
+            "备份中包含未知的数据表: sessions"
+        );
+    }
 }
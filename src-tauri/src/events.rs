@@ -0,0 +1,120 @@
+//! 数据变化事件。事件名带有所属管理员的 ID（如 `record-saved:<adminId>`），
+//! 桌面端只把事件发送给登录了该管理员的窗口，其他窗口即使监听同名事件也收不到。
+
+use crate::models::{AttendanceRecord, Location, UserInfo};
+use serde::Serialize;
+
+pub const RECORD_SAVED: &str = "record-saved";
+pub const USER_CHANGED: &str = "user-changed";
+pub const LOCATION_CHANGED: &str = "location-changed";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Saved,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSavedEvent {
+    pub admin_id: String,
+    pub record: AttendanceRecord,
+}
+
+/// 发送给管理员窗口的用户信息不包含密码
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserChangedEvent {
+    pub admin_id: String,
+    pub change: ChangeKind,
+    pub user: UserInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationChangedEvent {
+    pub admin_id: String,
+    pub change: ChangeKind,
+    pub location: Location,
+}
+
+pub enum DataEvent {
+    RecordSaved(RecordSavedEvent),
+    UserChanged(UserChangedEvent),
+    LocationChanged(LocationChangedEvent),
+}
+
+impl DataEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataEvent::RecordSaved(_) => RECORD_SAVED,
+            DataEvent::UserChanged(_) => USER_CHANGED,
+            DataEvent::LocationChanged(_) => LOCATION_CHANGED,
+        }
+    }
+
+    pub fn admin_id(&self) -> &str {
+        match self {
+            DataEvent::RecordSaved(e) => &e.admin_id,
+            DataEvent::UserChanged(e) => &e.admin_id,
+            DataEvent::LocationChanged(e) => &e.admin_id,
+        }
+    }
+
+    /// 按所属管理员区分的事件名
+    pub fn scoped_name(&self) -> String {
+        format!("{}:{}", self.name(), self.admin_id())
+    }
+}
+
+/// 数据库写入成功后调用，由桌面端转发为 Tauri 事件
+pub type EventListener = std::sync::Arc<dyn Fn(&DataEvent) + Send + Sync>;
+
+/// 桌面端各窗口当前登录的管理员，键为窗口标签
+#[cfg(feature = "desktop")]
+#[derive(Default)]
+pub struct EventWindows(std::sync::Mutex<std::collections::HashMap<String, String>>);
+
+#[cfg(feature = "desktop")]
+impl EventWindows {
+    /// 窗口开始接收该管理员名下的事件，`None` 表示不再接收任何事件
+    pub fn watch(&self, label: &str, admin_id: Option<String>) {
+        let mut windows = self.0.lock().unwrap();
+        match admin_id {
+            Some(admin_id) => windows.insert(label.to_string(), admin_id),
+            None => windows.remove(label),
+        };
+    }
+
+    fn owns(&self, target: &tauri::EventTarget, admin_id: &str) -> bool {
+        use tauri::EventTarget;
+
+        let label = match target {
+            EventTarget::Window { label }
+            | EventTarget::Webview { label }
+            | EventTarget::WebviewWindow { label }
+            | EventTarget::AnyLabel { label } => label,
+            _ => return false,
+        };
+        self.0.lock().unwrap().get(label).map(String::as_str) == Some(admin_id)
+    }
+}
+
+/// 只有按窗口监听（而不是全局监听）的事件才能被筛选，前端需通过当前窗口订阅
+#[cfg(feature = "desktop")]
+pub fn emit(app: &tauri::AppHandle, event: &DataEvent) {
+    use tauri::{Emitter, Manager};
+
+    let windows = app.state::<EventWindows>();
+    let owned = |target: &tauri::EventTarget| windows.owns(target, event.admin_id());
+    let name = event.scoped_name();
+    let result = match event {
+        DataEvent::RecordSaved(payload) => app.emit_filter(&name, payload, owned),
+        DataEvent::UserChanged(payload) => app.emit_filter(&name, payload, owned),
+        DataEvent::LocationChanged(payload) => app.emit_filter(&name, payload, owned),
+    };
+    if let Err(e) = result {
+        println!("Failed to emit {}: {}", name, e);
+    }
+}
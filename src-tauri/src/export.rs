@@ -0,0 +1,305 @@
+use crate::database::Database;
+use crate::models::{
+    AttendanceRecord, AttendanceStatus, DayStatus, Location, MonthlyReport, PunchType, Punctuality,
+    RecordColumn, RecordQuery, RecordSource, ReportColumn, ReportFigures, DEFAULT_TIMEZONE,
+};
+use crate::schedule;
+use std::collections::HashMap;
+use std::path::Path;
+
+const DEFAULT_RECORD_COLUMNS: &[RecordColumn] = &[
+    RecordColumn::Username,
+    RecordColumn::LocationName,
+    RecordColumn::Date,
+    RecordColumn::Time,
+    RecordColumn::PunchType,
+    RecordColumn::Status,
+    RecordColumn::Punctuality,
+];
+
+pub const DEFAULT_REPORT_COLUMNS: &[ReportColumn] = &[
+    ReportColumn::Username,
+    ReportColumn::WorkingDays,
+    ReportColumn::PresentDays,
+    ReportColumn::LateCount,
+    ReportColumn::LateMinutes,
+    ReportColumn::EarlyLeaveCount,
+    ReportColumn::EarlyLeaveMinutes,
+    ReportColumn::AbsentDays,
+    ReportColumn::IncompleteDays,
+    ReportColumn::LeaveDays,
+    ReportColumn::OvertimeMinutes,
+    ReportColumn::FailedAttempts,
+];
+
+/// 与导出格式无关的表格，CSV 和其他格式共用
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl RecordColumn {
+    fn header(self) -> &'static str {
+        match self {
+            RecordColumn::Username => "用户名",
+            RecordColumn::LocationName => "打卡位置",
+            RecordColumn::Date => "日期",
+            RecordColumn::Time => "时间",
+            RecordColumn::Timezone => "时区",
+            RecordColumn::PunchType => "打卡类型",
+            RecordColumn::Status => "状态",
+            RecordColumn::Punctuality => "准点情况",
+            RecordColumn::Latitude => "纬度",
+            RecordColumn::Longitude => "经度",
+            RecordColumn::Accuracy => "定位精度(米)",
+            RecordColumn::RiskScore => "风险分",
+            RecordColumn::Source => "来源",
+            RecordColumn::ErrorMessage => "失败原因",
+        }
+    }
+}
+
+impl ReportColumn {
+    pub fn header(self) -> &'static str {
+        match self {
+            ReportColumn::Username => "用户名",
+            ReportColumn::WorkingDays => "应出勤天数",
+            ReportColumn::PresentDays => "出勤天数",
+            ReportColumn::LateCount => "迟到次数",
+            ReportColumn::LateMinutes => "迟到分钟",
+            ReportColumn::EarlyLeaveCount => "早退次数",
+            ReportColumn::EarlyLeaveMinutes => "早退分钟",
+            ReportColumn::AbsentDays => "缺勤天数",
+            ReportColumn::IncompleteDays => "缺卡天数",
+            ReportColumn::LeaveDays => "请假天数",
+            ReportColumn::OvertimeMinutes => "加班分钟",
+            ReportColumn::FailedAttempts => "失败打卡次数",
+        }
+    }
+
+    /// 用户名列没有数值，返回 0
+    pub fn number(self, figures: &ReportFigures) -> f64 {
+        match self {
+            ReportColumn::Username => 0.0,
+            ReportColumn::WorkingDays => figures.working_days as f64,
+            ReportColumn::PresentDays => figures.present_days as f64,
+            ReportColumn::LateCount => figures.late_count as f64,
+            ReportColumn::LateMinutes => figures.late_minutes as f64,
+            ReportColumn::EarlyLeaveCount => figures.early_leave_count as f64,
+            ReportColumn::EarlyLeaveMinutes => figures.early_leave_minutes as f64,
+            ReportColumn::AbsentDays => figures.absent_days as f64,
+            ReportColumn::IncompleteDays => figures.incomplete_days as f64,
+            ReportColumn::LeaveDays => figures.leave_days,
+            ReportColumn::OvertimeMinutes => figures.overtime_minutes as f64,
+            ReportColumn::FailedAttempts => figures.failed_attempts as f64,
+        }
+    }
+}
+
+pub fn status_label(status: &AttendanceStatus) -> &'static str {
+    match status {
+        AttendanceStatus::Success => "成功",
+        AttendanceStatus::Failed => "失败",
+        AttendanceStatus::OutsideWindow => "不在打卡时段",
+        AttendanceStatus::LowAccuracy => "定位精度不足",
+    }
+}
+
+pub fn punch_type_label(punch_type: PunchType) -> &'static str {
+    match punch_type {
+        PunchType::In => "上班",
+        PunchType::Out => "下班",
+    }
+}
+
+fn punctuality_label(punctuality: Option<Punctuality>) -> String {
+    match punctuality {
+        Some(Punctuality::OnTime) => "正常".to_string(),
+        Some(Punctuality::Late(minutes)) => format!("迟到 {} 分钟", minutes),
+        Some(Punctuality::EarlyLeave(minutes)) => format!("早退 {} 分钟", minutes),
+        None => String::new(),
+    }
+}
+
+pub fn day_status_label(status: DayStatus) -> &'static str {
+    match status {
+        DayStatus::Present => "出勤",
+        DayStatus::Absent => "缺勤",
+        DayStatus::Incomplete => "缺卡",
+        DayStatus::Rest => "休息",
+        DayStatus::Leave => "请假",
+    }
+}
+
+fn source_label(source: RecordSource) -> &'static str {
+    match source {
+        RecordSource::Online => "在线",
+        RecordSource::LateSynced => "离线补传",
+        RecordSource::Manual => "补卡",
+    }
+}
+
+/// 按条件筛选打卡记录，结果按时间排序
+pub fn query_records(db: &Database, query: &RecordQuery) -> Result<Vec<AttendanceRecord>, String> {
+    let users: Option<Vec<String>> = match &query.admin_id {
+        Some(admin_id) => Some(
+            db.get_all_users()
+                .map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|u| u.admin_id.as_ref() == Some(admin_id))
+                .map(|u| u.id)
+                .collect(),
+        ),
+        None => None,
+    };
+
+    let mut records: Vec<AttendanceRecord> = db
+        .get_all_records()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| users.as_ref().is_none_or(|u| u.contains(&r.user_id)))
+        .filter(|r| query.user_id.as_ref().is_none_or(|id| &r.user_id == id))
+        .filter(|r| {
+            query
+                .location_id
+                .as_ref()
+                .is_none_or(|id| &r.location_id == id)
+        })
+        .filter(|r| query.status.as_ref().is_none_or(|s| &r.status == s))
+        .filter(|r| query.punch_type.is_none_or(|p| r.punch_type == p))
+        .filter(|r| {
+            let date = r.local_date.unwrap_or_else(|| schedule::work_date(r, None));
+            query.start_date.is_none_or(|start| date >= start)
+                && query.end_date.is_none_or(|end| date <= end)
+        })
+        .collect();
+    records.sort_by_key(|r| r.timestamp);
+    Ok(records)
+}
+
+/// 用户名和位置名代替 ID，时间按记录所在位置的时区显示；`columns` 为空时使用默认列
+pub fn records_table(
+    db: &Database,
+    records: &[AttendanceRecord],
+    columns: &[RecordColumn],
+) -> Result<Table, String> {
+    let columns = if columns.is_empty() {
+        DEFAULT_RECORD_COLUMNS
+    } else {
+        columns
+    };
+
+    let usernames: HashMap<String, String> = db
+        .get_all_users()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|u| (u.id, u.username))
+        .collect();
+    let locations: HashMap<String, Location> = db
+        .get_all_locations()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|l| (l.id.clone(), l))
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            let location = locations.get(&record.location_id);
+            let tz = record
+                .timezone
+                .or(location.map(|l| l.timezone))
+                .unwrap_or(DEFAULT_TIMEZONE);
+            let at = schedule::local_datetime(record.timestamp, tz);
+
+            columns
+                .iter()
+                .map(|column| match column {
+                    RecordColumn::Username => usernames
+                        .get(&record.user_id)
+                        .cloned()
+                        .unwrap_or_else(|| record.user_id.clone()),
+                    RecordColumn::LocationName => location
+                        .map(|l| l.name.clone())
+                        .unwrap_or_else(|| record.location_id.clone()),
+                    RecordColumn::Date => at.format("%Y-%m-%d").to_string(),
+                    RecordColumn::Time => at.format("%H:%M:%S").to_string(),
+                    RecordColumn::Timezone => tz.name().to_string(),
+                    RecordColumn::PunchType => punch_type_label(record.punch_type).to_string(),
+                    RecordColumn::Status => status_label(&record.status).to_string(),
+                    RecordColumn::Punctuality => punctuality_label(record.punctuality),
+                    RecordColumn::Latitude => record.latitude.to_string(),
+                    RecordColumn::Longitude => record.longitude.to_string(),
+                    RecordColumn::Accuracy => {
+                        record.accuracy.map(|a| a.to_string()).unwrap_or_default()
+                    }
+                    RecordColumn::RiskScore => record.risk_score.to_string(),
+                    RecordColumn::Source => source_label(record.source).to_string(),
+                    RecordColumn::ErrorMessage => record.error_message.clone().unwrap_or_default(),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(Table {
+        headers: columns.iter().map(|c| c.header().to_string()).collect(),
+        rows,
+    })
+}
+
+/// 每个员工一行，最后一行为合计；`columns` 为空时使用默认列
+pub fn report_table(report: &MonthlyReport, columns: &[ReportColumn]) -> Table {
+    let columns = if columns.is_empty() {
+        DEFAULT_REPORT_COLUMNS
+    } else {
+        columns
+    };
+    let row = |name: &str, figures: &ReportFigures| -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| match column {
+                ReportColumn::Username => name.to_string(),
+                _ => column.number(figures).to_string(),
+            })
+            .collect()
+    };
+
+    let mut rows: Vec<Vec<String>> = report
+        .rows
+        .iter()
+        .map(|r| row(&r.username, &r.figures))
+        .collect();
+    rows.push(row("合计", &report.totals));
+
+    Table {
+        headers: columns.iter().map(|c| c.header().to_string()).collect(),
+        rows,
+    }
+}
+
+pub fn ensure_parent_dir(path: &Path) -> Result<(), String> {
+    if path
+        .parent()
+        .is_some_and(|p| !p.as_os_str().is_empty() && !p.is_dir())
+    {
+        return Err("导出目录不存在".to_string());
+    }
+    Ok(())
+}
+
+/// 写入带 UTF-8 BOM 的 CSV，便于 Excel 正确识别中文
+pub fn write_csv(table: &Table, path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    ensure_parent_dir(path)?;
+
+    let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    writer
+        .write_record(&table.headers)
+        .map_err(|e| e.to_string())?;
+    for row in &table.rows {
+        writer.write_record(row).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+
+    std::fs::write(path, bytes).map_err(|e| format!("写入文件失败: {}", e))
+}
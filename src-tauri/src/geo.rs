@@ -0,0 +1,219 @@
+use crate::models::{GeoPoint, Geofence};
+
+const EARTH_RADIUS: f64 = 6371000.0;
+
+pub fn calculate_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let lat1_rad = lat1.to_radians();
+    let lat2_rad = lat2.to_radians();
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a =
+        (d_lat / 2.0).sin().powi(2) + lat1_rad.cos() * lat2_rad.cos() * (d_lon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    EARTH_RADIUS * c
+}
+
+/// 以 `origin` 为原点的局部平面坐标（米），围栏尺度下误差可以忽略
+fn project(origin: GeoPoint, point: GeoPoint) -> (f64, f64) {
+    let x = (point.longitude - origin.longitude).to_radians()
+        * origin.latitude.to_radians().cos()
+        * EARTH_RADIUS;
+    let y = (point.latitude - origin.latitude).to_radians() * EARTH_RADIUS;
+    (x, y)
+}
+
+/// 原点到线段 ab 的距离
+fn distance_to_segment(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (-(a.0 * dx + a.1 * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    (a.0 + t * dx).hypot(a.1 + t * dy)
+}
+
+fn polygon_signed_distance(vertices: &[GeoPoint], point: GeoPoint) -> f64 {
+    if vertices.len() < 3 {
+        return f64::INFINITY;
+    }
+
+    let projected: Vec<(f64, f64)> = vertices.iter().map(|v| project(point, *v)).collect();
+    let mut inside = false;
+    let mut nearest = f64::INFINITY;
+
+    for i in 0..projected.len() {
+        let a = projected[i];
+        let b = projected[(i + 1) % projected.len()];
+
+        if (a.1 > 0.0) != (b.1 > 0.0) && 0.0 < a.0 - a.1 * (b.0 - a.0) / (b.1 - a.1) {
+            inside = !inside;
+        }
+        nearest = nearest.min(distance_to_segment(a, b));
+    }
+
+    if inside {
+        -nearest
+    } else {
+        nearest
+    }
+}
+
+/// 点到围栏边界的带符号距离（米），在围栏内为负数
+pub fn signed_distance(fence: &Geofence, point: GeoPoint) -> f64 {
+    match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            radius,
+        } => calculate_distance(point.latitude, point.longitude, *latitude, *longitude) - radius,
+        Geofence::Polygon { vertices } => polygon_signed_distance(vertices, point),
+        Geofence::Union { shapes } => shapes
+            .iter()
+            .map(|shape| signed_distance(shape, point))
+            .fold(f64::INFINITY, f64::min),
+    }
+}
+
+/// 精度圆落在围栏（含缓冲距离）内的面积比例，用均匀分布的采样点估算
+pub fn overlap_ratio(fence: &Geofence, center: GeoPoint, accuracy: f64, buffer: f64) -> f64 {
+    const SAMPLES: usize = 256;
+    let golden_angle = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+
+    if accuracy <= 0.0 {
+        return if signed_distance(fence, center) <= buffer {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    let inside = (0..SAMPLES)
+        .filter(|&i| {
+            let r = accuracy * ((i as f64 + 0.5) / SAMPLES as f64).sqrt();
+            let theta = i as f64 * golden_angle;
+            let point = GeoPoint {
+                latitude: center.latitude + (r * theta.sin() / EARTH_RADIUS).to_degrees(),
+                longitude: center.longitude
+                    + (r * theta.cos() / (EARTH_RADIUS * center.latitude.to_radians().cos()))
+                        .to_degrees(),
+            };
+            signed_distance(fence, point) <= buffer
+        })
+        .count();
+
+    inside as f64 / SAMPLES as f64
+}
+
+/// 用多边形近似圆形围栏的边界，用于在地图上绘制
+pub fn circle_outline(center: GeoPoint, radius: f64, segments: usize) -> Vec<GeoPoint> {
+    (0..segments)
+        .map(|i| {
+            let theta = i as f64 * std::f64::consts::TAU / segments as f64;
+            GeoPoint {
+                latitude: center.latitude + (radius * theta.sin() / EARTH_RADIUS).to_degrees(),
+                longitude: center.longitude
+                    + (radius * theta.cos() / (EARTH_RADIUS * center.latitude.to_radians().cos()))
+                        .to_degrees(),
+            }
+        })
+        .collect()
+}
+
+fn valid_point(point: &GeoPoint) -> bool {
+    (-90.0..=90.0).contains(&point.latitude) && (-180.0..=180.0).contains(&point.longitude)
+}
+
+pub fn validate_geofence(fence: &Geofence) -> Result<(), String> {
+    match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            radius,
+        } => {
+            if !valid_point(&GeoPoint {
+                latitude: *latitude,
+                longitude: *longitude,
+            }) {
+                return Err("圆形围栏的中心坐标无效".to_string());
+            }
+            if *radius <= 0.0 {
+                return Err("圆形围栏的半径必须大于 0".to_string());
+            }
+        }
+        Geofence::Polygon { vertices } => {
+            if vertices.len() < 3 {
+                return Err("多边形围栏至少需要 3 个顶点".to_string());
+            }
+            if !vertices.iter().all(valid_point) {
+                return Err("多边形围栏的顶点坐标无效".to_string());
+            }
+        }
+        Geofence::Union { shapes } => {
+            if shapes.is_empty() {
+                return Err("组合围栏至少需要一个形状".to_string());
+            }
+            for shape in shapes {
+                validate_geofence(shape)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> GeoPoint {
+        GeoPoint {
+            latitude,
+            longitude,
+        }
+    }
+
+    fn square() -> Geofence {
+        Geofence::Polygon {
+            vertices: vec![
+                point(31.0, 121.0),
+                point(31.0, 121.001),
+                point(31.001, 121.001),
+                point(31.001, 121.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn distance_matches_known_values() {
+        assert_eq!(calculate_distance(31.0, 121.0, 31.0, 121.0), 0.0);
+        // 纬度相差 0.001 度约 111 米
+        let d = calculate_distance(31.0, 121.0, 31.001, 121.0);
+        assert!((d - 111.19).abs() < 0.1, "{}", d);
+    }
+
+    #[test]
+    fn polygon_distance_is_negative_inside() {
+        assert!(signed_distance(&square(), point(31.0005, 121.0005)) < 0.0);
+        let outside = signed_distance(&square(), point(31.0015, 121.0005));
+        assert!((outside - 55.6).abs() < 1.0, "{}", outside);
+    }
+
+    #[test]
+    fn union_uses_the_nearest_shape() {
+        let fence = Geofence::Union {
+            shapes: vec![
+                square(),
+                Geofence::Circle {
+                    latitude: 31.01,
+                    longitude: 121.0,
+                    radius: 50.0,
+                },
+            ],
+        };
+        assert!(signed_distance(&fence, point(31.01, 121.0)) < 0.0);
+        assert!(signed_distance(&fence, point(31.005, 121.0)) > 0.0);
+    }
+}
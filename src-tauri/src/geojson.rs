@@ -0,0 +1,657 @@
+use crate::database::Database;
+use crate::export;
+use crate::geo;
+use crate::models::{
+    FeatureError, FieldChange, GeoJsonImportMode, GeoPoint, Geofence, ImportAction, Location,
+    LocationChange, LocationImportReport, UserRole,
+};
+use chrono_tz::Tz;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+fn position(point: GeoPoint) -> Value {
+    json!([point.longitude, point.latitude])
+}
+
+/// 圆形围栏导出为带 `radius` 扩展成员的 Point，多边形的外环首尾闭合
+fn fence_geometry(fence: &Geofence) -> Value {
+    match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            radius,
+        } => json!({
+            "type": "Point",
+            "coordinates": [longitude, latitude],
+            "radius": radius,
+        }),
+        Geofence::Polygon { vertices } => {
+            let mut ring: Vec<Value> = vertices.iter().copied().map(position).collect();
+            if let Some(first) = vertices.first() {
+                ring.push(position(*first));
+            }
+            json!({ "type": "Polygon", "coordinates": [ring] })
+        }
+        Geofence::Union { shapes } => json!({
+            "type": "GeometryCollection",
+            "geometries": shapes.iter().map(fence_geometry).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn feature(location: &Location, admin: Option<&str>) -> Value {
+    let geometry = match &location.geofence {
+        Some(fence) => fence_geometry(fence),
+        None => json!({
+            "type": "Point",
+            "coordinates": [location.longitude, location.latitude],
+        }),
+    };
+    json!({
+        "type": "Feature",
+        "id": location.id,
+        "geometry": geometry,
+        "properties": {
+            "name": location.name,
+            "radius": location.radius,
+            "center": [location.longitude, location.latitude],
+            "adminId": location.admin_id,
+            "admin": admin,
+            "buffer": location.buffer,
+            "timezone": location.timezone.name(),
+        },
+    })
+}
+
+/// 导出管理员名下的位置，返回导出的数量
+pub fn export_locations(db: &Database, admin_id: &str, path: &str) -> Result<usize, String> {
+    let path = Path::new(path);
+    export::ensure_parent_dir(path)?;
+
+    let admins: HashMap<String, String> = db
+        .get_all_users()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|u| u.role == UserRole::Admin)
+        .map(|u| (u.id, u.username))
+        .collect();
+    let mut locations: Vec<Location> = db
+        .get_all_locations()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|l| l.admin_id == admin_id)
+        .collect();
+    locations.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let collection = json!({
+        "type": "FeatureCollection",
+        "features": locations
+            .iter()
+            .map(|l| feature(l, admins.get(&l.admin_id).map(String::as_str)))
+            .collect::<Vec<_>>(),
+    });
+    let content = serde_json::to_string_pretty(&collection).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    Ok(locations.len())
+}
+
+fn parse_position(value: &Value) -> Result<GeoPoint, String> {
+    match value.as_array().map(Vec::as_slice) {
+        Some([longitude, latitude, ..]) => match (longitude.as_f64(), latitude.as_f64()) {
+            (Some(longitude), Some(latitude)) => Ok(GeoPoint {
+                latitude,
+                longitude,
+            }),
+            _ => Err("坐标必须是数字".to_string()),
+        },
+        _ => Err("坐标格式应为 [经度, 纬度]".to_string()),
+    }
+}
+
+fn parse_ring(value: &Value) -> Result<Vec<GeoPoint>, String> {
+    let mut vertices = value
+        .as_array()
+        .ok_or("多边形坐标格式不正确")?
+        .iter()
+        .map(parse_position)
+        .collect::<Result<Vec<_>, _>>()?;
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+    Ok(vertices)
+}
+
+/// 多边形只使用外环，内环（洞）会被忽略
+fn parse_polygon(coordinates: &Value) -> Result<Geofence, String> {
+    let outer = coordinates
+        .as_array()
+        .and_then(|rings| rings.first())
+        .ok_or("多边形缺少外环")?;
+    Ok(Geofence::Polygon {
+        vertices: parse_ring(outer)?,
+    })
+}
+
+/// 解析几何对象；不带 `radius` 的 Point 返回 None，表示使用位置本身的圆形范围
+fn parse_geometry(geometry: &Value) -> Result<Option<Geofence>, String> {
+    let kind = geometry
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let coordinates = geometry.get("coordinates").unwrap_or(&Value::Null);
+    let fence = match kind {
+        "Point" => {
+            let center = parse_position(coordinates)?;
+            match geometry.get("radius").and_then(Value::as_f64) {
+                Some(radius) => Geofence::Circle {
+                    latitude: center.latitude,
+                    longitude: center.longitude,
+                    radius,
+                },
+                None => return Ok(None),
+            }
+        }
+        "Polygon" => parse_polygon(coordinates)?,
+        "MultiPolygon" => Geofence::Union {
+            shapes: coordinates
+                .as_array()
+                .ok_or("多边形坐标格式不正确")?
+                .iter()
+                .map(parse_polygon)
+                .collect::<Result<_, _>>()?,
+        },
+        "GeometryCollection" => Geofence::Union {
+            shapes: geometry
+                .get("geometries")
+                .and_then(Value::as_array)
+                .ok_or("几何集合缺少 geometries")?
+                .iter()
+                .map(|g| {
+                    parse_geometry(g)?.ok_or_else(|| "几何集合中的点必须带有 radius".to_string())
+                })
+                .collect::<Result<_, _>>()?,
+        },
+        other => return Err(format!("不支持的几何类型: {}", other)),
+    };
+    Ok(Some(fence))
+}
+
+/// 围栏上的各点及其覆盖半径，多边形顶点的半径为 0
+fn fence_points(fence: &Geofence, points: &mut Vec<(GeoPoint, f64)>) {
+    match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            radius,
+        } => points.push((
+            GeoPoint {
+                latitude: *latitude,
+                longitude: *longitude,
+            },
+            *radius,
+        )),
+        Geofence::Polygon { vertices } => points.extend(vertices.iter().map(|v| (*v, 0.0))),
+        Geofence::Union { shapes } => shapes.iter().for_each(|s| fence_points(s, points)),
+    }
+}
+
+/// 没有给出中心点时取围栏各点的平均值，半径取能覆盖整个围栏的距离
+fn derived_center(fence: &Geofence) -> (GeoPoint, f64) {
+    let mut points = Vec::new();
+    fence_points(fence, &mut points);
+    let count = points.len().max(1) as f64;
+    let center = GeoPoint {
+        latitude: points.iter().map(|(p, _)| p.latitude).sum::<f64>() / count,
+        longitude: points.iter().map(|(p, _)| p.longitude).sum::<f64>() / count,
+    };
+    let radius = points
+        .iter()
+        .map(|(p, radius)| {
+            geo::calculate_distance(center.latitude, center.longitude, p.latitude, p.longitude)
+                + radius
+        })
+        .fold(0.0, f64::max);
+    (center, radius)
+}
+
+fn fence_label(fence: Option<&Geofence>) -> String {
+    match fence {
+        None => "位置圆形范围".to_string(),
+        Some(Geofence::Circle { radius, .. }) => format!("圆形 (半径 {} 米)", radius),
+        Some(Geofence::Polygon { vertices }) => format!("多边形 ({} 个顶点)", vertices.len()),
+        Some(Geofence::Union { shapes }) => format!("组合 ({} 个形状)", shapes.len()),
+    }
+}
+
+/// 从 Feature 中读取的位置字段，`buffer` 和 `timezone` 缺省时保留原值
+struct ParsedFeature {
+    name: String,
+    admin_id: String,
+    center: GeoPoint,
+    radius: f64,
+    geofence: Option<Geofence>,
+    buffer: Option<f64>,
+    timezone: Option<Tz>,
+}
+
+/// `adminId` 只能是当前管理员，缺省时归属当前管理员
+fn parse_feature(feature: &Value, admin_id: &str) -> Result<ParsedFeature, String> {
+    let empty = Map::new();
+    let properties = feature
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    let name = properties
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .ok_or("缺少位置名称 (properties.name)")?
+        .to_string();
+    if let Some(id) = properties.get("adminId").and_then(Value::as_str) {
+        if id != admin_id {
+            return Err(format!("不能导入其他管理员的位置: {}", id));
+        }
+    }
+    let admin_id = admin_id.to_string();
+
+    let geometry = feature.get("geometry").ok_or("缺少 geometry")?;
+    let geofence = parse_geometry(geometry)?;
+    if let Some(fence) = &geofence {
+        geo::validate_geofence(fence)?;
+    }
+
+    let (center, derived_radius) = match (&geofence, properties.get("center")) {
+        (_, Some(center)) => (parse_position(center)?, None),
+        (None, None) => (parse_position(&geometry["coordinates"])?, None),
+        (Some(fence), None) => {
+            let (center, radius) = derived_center(fence);
+            (center, Some(radius))
+        }
+    };
+    let radius = properties
+        .get("radius")
+        .and_then(Value::as_f64)
+        .or(derived_radius)
+        .ok_or("缺少半径 (properties.radius)")?;
+    geo::validate_geofence(&Geofence::Circle {
+        latitude: center.latitude,
+        longitude: center.longitude,
+        radius,
+    })?;
+
+    let buffer = properties.get("buffer").and_then(Value::as_f64);
+    if buffer.is_some_and(|b| b < 0.0) {
+        return Err("缓冲距离不能为负数".to_string());
+    }
+    let timezone = match properties.get("timezone").and_then(Value::as_str) {
+        Some(name) => Some(
+            name.parse::<Tz>()
+                .map_err(|_| format!("未知时区: {}", name))?,
+        ),
+        None => None,
+    };
+
+    Ok(ParsedFeature {
+        name,
+        admin_id,
+        center,
+        radius,
+        geofence,
+        buffer,
+        timezone,
+    })
+}
+
+/// 把解析结果写入位置，返回有变化的字段
+fn apply(location: &mut Location, parsed: ParsedFeature) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut change = |field: &str, before: String, after: String| {
+        if before != after {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                before,
+                after,
+            });
+        }
+    };
+
+    change(
+        "center",
+        format!("{}, {}", location.latitude, location.longitude),
+        format!("{}, {}", parsed.center.latitude, parsed.center.longitude),
+    );
+    change(
+        "radius",
+        location.radius.to_string(),
+        parsed.radius.to_string(),
+    );
+    if location.geofence != parsed.geofence {
+        change(
+            "geofence",
+            fence_label(location.geofence.as_ref()),
+            fence_label(parsed.geofence.as_ref()),
+        );
+    }
+    let buffer = parsed.buffer.unwrap_or(location.buffer);
+    change("buffer", location.buffer.to_string(), buffer.to_string());
+    let timezone = parsed.timezone.unwrap_or(location.timezone);
+    change(
+        "timezone",
+        location.timezone.name().to_string(),
+        timezone.name().to_string(),
+    );
+
+    location.latitude = parsed.center.latitude;
+    location.longitude = parsed.center.longitude;
+    location.radius = parsed.radius;
+    location.geofence = parsed.geofence;
+    location.buffer = buffer;
+    location.timezone = timezone;
+    changes
+}
+
+/// 导入 GeoJSON FeatureCollection；按名称更新时在所属管理员的位置中查找同名位置，`dry_run` 时只返回差异
+pub fn import_locations(
+    db: &Database,
+    admin_id: &str,
+    content: &str,
+    mode: GeoJsonImportMode,
+    dry_run: bool,
+) -> Result<LocationImportReport, String> {
+    let collection: Value =
+        serde_json::from_str(content).map_err(|e| format!("GeoJSON 格式错误: {}", e))?;
+    if collection.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+        return Err("文件必须是 GeoJSON FeatureCollection".to_string());
+    }
+    let features = collection
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or("FeatureCollection 缺少 features")?;
+
+    db.get_user(admin_id)
+        .map_err(|e| e.to_string())?
+        .filter(|u| u.role == UserRole::Admin)
+        .ok_or_else(|| "管理员不存在".to_string())?;
+    let existing = db.get_all_locations().map_err(|e| e.to_string())?;
+
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+    let mut changes = Vec::new();
+    let mut errors = Vec::new();
+    let mut pending = Vec::new();
+
+    for (index, feature) in features.iter().enumerate() {
+        let name = feature
+            .pointer("/properties/name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let result = (|| -> Result<(LocationChange, Option<Location>), String> {
+            let parsed = parse_feature(feature, admin_id)?;
+            let key = (parsed.admin_id.clone(), parsed.name.clone());
+            if let Some(first) = seen.get(&key) {
+                return Err(format!("与第 {} 个要素名称重复", first + 1));
+            }
+            seen.insert(key, index);
+
+            let matches: Vec<&Location> = match mode {
+                GeoJsonImportMode::UpdateByName => existing
+                    .iter()
+                    .filter(|l| l.admin_id == parsed.admin_id && l.name == parsed.name)
+                    .collect(),
+                GeoJsonImportMode::CreateNew => Vec::new(),
+            };
+            let name = parsed.name.clone();
+            match matches.as_slice() {
+                [] => {
+                    let mut location = Location::new(
+                        parsed.name.clone(),
+                        parsed.center.latitude,
+                        parsed.center.longitude,
+                        parsed.radius,
+                        parsed.admin_id.clone(),
+                    );
+                    apply(&mut location, parsed);
+                    // 仅校验时新位置不会保存，不返回临时 ID
+                    let change = LocationChange {
+                        index,
+                        name,
+                        action: ImportAction::Create,
+                        location_id: (!dry_run).then(|| location.id.clone()),
+                        fields: Vec::new(),
+                    };
+                    Ok((change, Some(location)))
+                }
+                [current] => {
+                    let mut location = (*current).clone();
+                    let fields = apply(&mut location, parsed);
+                    let action = if fields.is_empty() {
+                        ImportAction::Unchanged
+                    } else {
+                        ImportAction::Update
+                    };
+                    let change = LocationChange {
+                        index,
+                        name,
+                        action,
+                        location_id: Some(location.id.clone()),
+                        fields,
+                    };
+                    Ok((change, (action == ImportAction::Update).then_some(location)))
+                }
+                _ => Err(format!("存在多个名为 {} 的位置，无法按名称更新", name)),
+            }
+        })();
+
+        match result {
+            Ok((change, location)) => {
+                changes.push(change);
+                pending.extend(location);
+            }
+            Err(message) => errors.push(FeatureError {
+                index,
+                name,
+                message,
+            }),
+        }
+    }
+
+    let applied = !dry_run && !pending.is_empty();
+    if applied {
+        db.save_locations(&pending).map_err(|e| e.to_string())?;
+    }
+
+    Ok(LocationImportReport {
+        changes,
+        errors,
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::User;
+    use crate::test_support;
+
+    fn setup() -> (Database, User) {
+        let db = Database::temporary();
+        let admin = test_support::admin(&db, "admin");
+        (db, admin)
+    }
+
+    fn import(
+        db: &Database,
+        admin: &User,
+        features: Value,
+        mode: GeoJsonImportMode,
+        dry_run: bool,
+    ) -> LocationImportReport {
+        let content = json!({ "type": "FeatureCollection", "features": features }).to_string();
+        import_locations(db, &admin.id, &content, mode, dry_run).unwrap()
+    }
+
+    fn office() -> Value {
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [[
+                    [121.0, 31.0], [121.002, 31.0], [121.002, 31.002], [121.0, 31.002], [121.0, 31.0]
+                ]],
+            },
+            "properties": { "name": "办公楼", "timezone": "Asia/Tokyo" },
+        })
+    }
+
+    #[test]
+    fn polygons_are_imported_with_a_derived_center() {
+        let (db, admin) = setup();
+        let report = import(
+            &db,
+            &admin,
+            json!([office()]),
+            GeoJsonImportMode::UpdateByName,
+            false,
+        );
+        assert!(report.errors.is_empty());
+        assert!(report.applied);
+        assert_eq!(report.changes[0].action, ImportAction::Create);
+
+        let location = db.get_all_locations().unwrap().remove(0);
+        assert_eq!(location.admin_id, admin.id);
+        assert!((location.latitude - 31.001).abs() < 1e-9);
+        assert!((location.longitude - 121.001).abs() < 1e-9);
+        assert!(location.radius > 100.0 && location.radius < 200.0);
+        assert_eq!(location.timezone, chrono_tz::Asia::Tokyo);
+        match location.geofence {
+            Some(Geofence::Polygon { vertices }) => assert_eq!(vertices.len(), 4),
+            other => panic!("unexpected geofence: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn export_then_import_is_unchanged() {
+        let (db, admin) = setup();
+        let mut location = Location::new("仓库".to_string(), 30.0, 120.0, 80.0, admin.id.clone());
+        location.geofence = Some(Geofence::Union {
+            shapes: vec![
+                Geofence::Circle {
+                    latitude: 30.0,
+                    longitude: 120.0,
+                    radius: 50.0,
+                },
+                Geofence::Circle {
+                    latitude: 30.001,
+                    longitude: 120.001,
+                    radius: 30.0,
+                },
+            ],
+        });
+        location.buffer = 10.0;
+        db.save_location(&location).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locations.geojson");
+        let path = path.to_str().unwrap();
+        assert_eq!(export_locations(&db, &admin.id, path).unwrap(), 1);
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let report = import_locations(
+            &db,
+            &admin.id,
+            &content,
+            GeoJsonImportMode::UpdateByName,
+            false,
+        )
+        .unwrap();
+        assert!(report.errors.is_empty());
+        assert_eq!(report.changes[0].action, ImportAction::Unchanged);
+        assert_eq!(
+            report.changes[0].location_id.as_deref(),
+            Some(location.id.as_str())
+        );
+        assert!(!report.applied);
+    }
+
+    #[test]
+    fn dry_run_reports_field_changes_without_saving() {
+        let (db, admin) = setup();
+        let location = Location::new("门店".to_string(), 31.0, 121.0, 100.0, admin.id.clone());
+        db.save_location(&location).unwrap();
+
+        let feature = json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": [121.0, 31.0] },
+            "properties": { "name": "门店", "radius": 150.0 },
+        });
+        let report = import(
+            &db,
+            &admin,
+            json!([feature]),
+            GeoJsonImportMode::UpdateByName,
+            true,
+        );
+        let change = &report.changes[0];
+        assert_eq!(change.action, ImportAction::Update);
+        assert_eq!(change.fields.len(), 1);
+        assert_eq!(change.fields[0].field, "radius");
+        assert_eq!(change.fields[0].before, "100");
+        assert_eq!(change.fields[0].after, "150");
+        assert!(!report.applied);
+        assert_eq!(
+            db.get_location(&location.id).unwrap().unwrap().radius,
+            100.0
+        );
+    }
+
+    #[test]
+    fn invalid_features_are_reported_by_index() {
+        let (db, admin) = setup();
+        let other = test_support::admin(&db, "other");
+
+        let mut foreign = office();
+        foreign["properties"]["name"] = json!("他人位置");
+        foreign["properties"]["adminId"] = json!(other.id);
+        let features = json!([
+            office(),
+            office(),
+            { "type": "Feature", "geometry": { "type": "LineString", "coordinates": [] },
+              "properties": { "name": "线路" } },
+            { "type": "Feature", "geometry": { "type": "Point", "coordinates": [121.0, 31.0] },
+              "properties": { "name": "无半径" } },
+            foreign,
+        ]);
+        let report = import(&db, &admin, features, GeoJsonImportMode::CreateNew, false);
+        let errors: Vec<(usize, &str)> = report
+            .errors
+            .iter()
+            .map(|e| (e.index, e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (1, "与第 1 个要素名称重复"),
+                (2, "不支持的几何类型: LineString"),
+                (3, "缺少半径 (properties.radius)"),
+                (4, &*format!("不能导入其他管理员的位置: {}", other.id)),
+            ]
+        );
+        assert_eq!(db.get_all_locations().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn non_collections_are_rejected() {
+        let (db, admin) = setup();
+        let error = import_locations(
+            &db,
+            &admin.id,
+            r#"{"type": "Feature"}"#,
+            GeoJsonImportMode::CreateNew,
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(error, "文件必须是 GeoJSON FeatureCollection");
+    }
+}
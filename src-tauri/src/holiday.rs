@@ -0,0 +1,317 @@
+use crate::database::Database;
+use crate::models::{AssignmentTarget, CalendarDay, DayKind, HolidayCalendar, User};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashSet;
+
+/// (名称, 放假开始, 放假结束, 调休上班日)，日期均为 (月, 日)
+type Festival = (&'static str, (u32, u32), (u32, u32), &'static [(u32, u32)]);
+
+const CN_2025: &[Festival] = &[
+    ("元旦", (1, 1), (1, 1), &[]),
+    ("春节", (1, 28), (2, 4), &[(1, 26), (2, 8)]),
+    ("清明节", (4, 4), (4, 6), &[]),
+    ("劳动节", (5, 1), (5, 5), &[(4, 27)]),
+    ("端午节", (5, 31), (6, 2), &[]),
+    ("国庆节、中秋节", (10, 1), (10, 8), &[(9, 28), (10, 11)]),
+];
+
+const CN_2026: &[Festival] = &[
+    ("元旦", (1, 1), (1, 3), &[(1, 4)]),
+    ("春节", (2, 15), (2, 23), &[(2, 14), (2, 28)]),
+    ("清明节", (4, 4), (4, 6), &[]),
+    ("劳动节", (5, 1), (5, 5), &[(5, 9)]),
+    ("端午节", (6, 19), (6, 21), &[]),
+    ("中秋节", (9, 25), (9, 27), &[]),
+    ("国庆节", (10, 1), (10, 7), &[(9, 20), (10, 10)]),
+];
+
+/// 国务院办公厅公布的法定节假日及调休安排，未收录的年份返回错误
+pub fn cn_statutory(year: i32) -> Result<Vec<CalendarDay>, String> {
+    let festivals = match year {
+        2025 => CN_2025,
+        2026 => CN_2026,
+        _ => return Err(format!("暂未收录 {} 年的法定节假日", year)),
+    };
+    let date = |(month, day): (u32, u32)| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+    let mut days = Vec::new();
+    for (name, start, end, workdays) in festivals {
+        days.extend(
+            date(*start)
+                .iter_days()
+                .take_while(|d| *d <= date(*end))
+                .map(|d| CalendarDay {
+                    date: d,
+                    kind: DayKind::Holiday,
+                    name: name.to_string(),
+                }),
+        );
+        days.extend(workdays.iter().map(|w| CalendarDay {
+            date: date(*w),
+            kind: DayKind::Workday,
+            name: format!("{}调休上班", name),
+        }));
+    }
+    days.sort_by_key(|d| d.date);
+    Ok(days)
+}
+
+#[derive(Default)]
+struct IcsEvent {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    summary: String,
+}
+
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|v| NaiveDate::parse_from_str(v, "%Y%m%d").ok())
+        .ok_or_else(|| format!("无法识别的日期: {}", value))
+}
+
+/// 事件标题中带“班”字（如“补班”“调休上班”）的视为调休上班日，其余视为假日
+fn event_days(event: IcsEvent) -> Result<Vec<CalendarDay>, String> {
+    let start = event
+        .start
+        .ok_or_else(|| "日历事件缺少开始日期".to_string())?;
+    // DTEND 不包含在事件内
+    let end = event
+        .end
+        .filter(|end| *end > start)
+        .unwrap_or(start + Duration::days(1));
+    if (end - start).num_days() > 366 {
+        return Err(format!("日历事件“{}”跨度过长", event.summary));
+    }
+
+    let kind = if event.summary.contains('班') {
+        DayKind::Workday
+    } else {
+        DayKind::Holiday
+    };
+    Ok(start
+        .iter_days()
+        .take_while(|d| *d < end)
+        .map(|date| CalendarDay {
+            date,
+            kind,
+            name: event.summary.clone(),
+        })
+        .collect())
+}
+
+/// 解析 iCalendar 文件中的全天事件
+pub fn parse_ics(content: &str) -> Result<Vec<CalendarDay>, String> {
+    // 以空格或制表符开头的行是上一行的折行
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut days = Vec::new();
+    let mut event: Option<IcsEvent> = None;
+    for line in &lines {
+        let Some((head, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = head
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(IcsEvent::default());
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                days.extend(event_days(event.take().unwrap())?);
+            }
+            ("DTSTART", Some(e)) => e.start = Some(parse_ics_date(value)?),
+            ("DTEND", Some(e)) => e.end = Some(parse_ics_date(value)?),
+            ("SUMMARY", Some(e)) => {
+                e.summary = value
+                    .replace("\\,", ",")
+                    .replace("\\;", ";")
+                    .replace("\\n", " ")
+                    .replace("\\\\", "\\");
+            }
+            _ => {}
+        }
+    }
+
+    if days.is_empty() {
+        return Err("日历文件中没有找到事件".to_string());
+    }
+    Ok(days)
+}
+
+pub fn validate_calendar(calendar: &HolidayCalendar) -> Result<(), String> {
+    if calendar.name.trim().is_empty() {
+        return Err("日历名称不能为空".to_string());
+    }
+    let mut seen = HashSet::new();
+    if let Some(day) = calendar.days.iter().find(|d| !seen.insert(d.date)) {
+        return Err(format!("日期 {} 重复", day.date));
+    }
+    Ok(())
+}
+
+fn attached_calendar(
+    db: &Database,
+    target: &AssignmentTarget,
+) -> Result<Option<HolidayCalendar>, Box<dyn std::error::Error>> {
+    let calendar_id = match target {
+        AssignmentTarget::Location(id) => db.get_location(id)?.and_then(|l| l.calendar_id),
+        AssignmentTarget::Group(id) => db.get_location_group(id)?.and_then(|g| g.calendar_id),
+    };
+    match calendar_id {
+        Some(id) => db.get_calendar(&id),
+        None => Ok(None),
+    }
+}
+
+/// 用户在指定日期适用的日历：优先取所在员工分组的日历，其次是位置组，最后是主位置和额外分配的位置
+pub fn user_calendar(
+    db: &Database,
+    user: &User,
+    date: NaiveDate,
+) -> Result<Option<HolidayCalendar>, Box<dyn std::error::Error>> {
+    for group in db.get_all_user_groups()? {
+        if !group.user_ids.contains(&user.id) {
+            continue;
+        }
+        if let Some(id) = &group.calendar_id {
+            if let Some(calendar) = db.get_calendar(id)? {
+                return Ok(Some(calendar));
+            }
+        }
+    }
+
+    let active: Vec<&AssignmentTarget> = user
+        .location_assignments
+        .iter()
+        .filter(|a| a.is_active_on(date))
+        .map(|a| &a.target)
+        .collect();
+    let primary = user.location_id.clone().map(AssignmentTarget::Location);
+
+    let groups = active
+        .iter()
+        .filter(|t| matches!(t, AssignmentTarget::Group(_)));
+    let locations = primary.iter().chain(
+        active
+            .iter()
+            .copied()
+            .filter(|t| matches!(t, AssignmentTarget::Location(_))),
+    );
+
+    for target in groups.copied().chain(locations) {
+        if let Some(calendar) = attached_calendar(db, target)? {
+            return Ok(Some(calendar));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Location, LocationAssignment, LocationGroup, UserGroup, UserRole};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    fn calendar(db: &Database, name: &str) -> HolidayCalendar {
+        let calendar = HolidayCalendar::new(name.to_string(), Vec::new(), "admin".to_string());
+        db.save_calendar(&calendar).unwrap();
+        calendar
+    }
+
+    #[test]
+    fn statutory_holidays_include_make_up_workdays() {
+        let days = cn_statutory(2025).unwrap();
+        let kind = |d: NaiveDate| days.iter().find(|c| c.date == d).map(|c| c.kind);
+        assert_eq!(kind(date(1, 28)), Some(DayKind::Holiday));
+        assert_eq!(kind(date(2, 4)), Some(DayKind::Holiday));
+        assert_eq!(kind(date(1, 26)), Some(DayKind::Workday));
+        assert_eq!(kind(date(3, 3)), None);
+        assert!(cn_statutory(2019).is_err());
+    }
+
+    #[test]
+    fn ics_events_expand_to_days() {
+        let content = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20251001\r\n\
+            DTEND;VALUE=DATE:20251004\r\n\
+            SUMMARY:国庆\r\n \
+            节\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20250928\r\n\
+            SUMMARY:补班\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let days = parse_ics(content).unwrap();
+        assert_eq!(days.len(), 4);
+        assert_eq!(days[0].name, "国庆节");
+        assert_eq!(days[2].date, date(10, 3));
+        assert_eq!(days[3].kind, DayKind::Workday);
+        assert!(parse_ics("BEGIN:VCALENDAR\nEND:VCALENDAR").is_err());
+    }
+
+    #[test]
+    fn user_group_calendar_takes_precedence() {
+        let db = Database::temporary();
+        let location_calendar = calendar(&db, "位置");
+        let group_calendar = calendar(&db, "位置组");
+        let user_group_calendar = calendar(&db, "员工分组");
+
+        let mut location =
+            Location::new("总部".to_string(), 31.0, 121.0, 100.0, "admin".to_string());
+        location.calendar_id = Some(location_calendar.id.clone());
+        db.save_location(&location).unwrap();
+        let mut group = LocationGroup::new(
+            "全部".to_string(),
+            vec![location.id.clone()],
+            "admin".to_string(),
+        );
+        group.calendar_id = Some(group_calendar.id.clone());
+        db.save_location_group(&group).unwrap();
+
+        let mut user = User::new(
+            "alice".to_string(),
+            "secret1".to_string(),
+            UserRole::User,
+            Some("admin".to_string()),
+        );
+        user.location_id = Some(location.id);
+        let resolve = |user: &User| {
+            user_calendar(&db, user, date(3, 10))
+                .unwrap()
+                .map(|c| c.name)
+        };
+        assert_eq!(resolve(&user).as_deref(), Some("位置"));
+
+        user.location_assignments.push(LocationAssignment {
+            target: AssignmentTarget::Group(group.id),
+            valid_from: None,
+            valid_until: None,
+        });
+        assert_eq!(resolve(&user).as_deref(), Some("位置组"));
+
+        let mut user_group = UserGroup::new(
+            "夜班".to_string(),
+            vec![user.id.clone()],
+            "admin".to_string(),
+        );
+        user_group.calendar_id = Some(user_group_calendar.id);
+        db.save_user_group(&user_group).unwrap();
+        assert_eq!(resolve(&user).as_deref(), Some("员工分组"));
+    }
+}
@@ -0,0 +1,407 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::holiday;
+use crate::models::{
+    AccrualMethod, ApprovalStatus, CreateLeaveRequest, DayPart, LeaveBalance, LeaveRequest,
+    LeaveType, ReviewRequest, Shift, User,
+};
+use crate::schedule;
+use chrono::{Datelike, NaiveDate};
+
+pub fn validate_leave_type(leave_type: &LeaveType) -> Result<(), String> {
+    if leave_type.name.trim().is_empty() {
+        return Err("假期类型名称不能为空".to_string());
+    }
+    if leave_type.annual_days < 0.0 {
+        return Err("年度额度不能为负数".to_string());
+    }
+    Ok(())
+}
+
+/// 请假覆盖的工作日天数，半天按 0.5 计算，节假日不计入；每天按当天适用的节假日日历判断
+pub fn requested_days(
+    db: &Database,
+    user: &User,
+    leave: &LeaveRequest,
+    shift: Option<&Shift>,
+) -> Result<f64, String> {
+    let mut days = 0.0;
+    for date in leave
+        .start_date
+        .iter_days()
+        .take_while(|date| *date <= leave.end_date)
+    {
+        let calendar = holiday::user_calendar(db, user, date).map_err(|e| e.to_string())?;
+        if schedule::is_working_day(shift, calendar.as_ref(), date) {
+            days += leave.portion_on(date);
+        }
+    }
+    Ok(days)
+}
+
+fn validate_range(request: &CreateLeaveRequest) -> Result<(), String> {
+    if request.end_date < request.start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (request.end_date - request.start_date).num_days() > 366 {
+        return Err("请假时间不能超过一年".to_string());
+    }
+    if request.start_date != request.end_date
+        && (request.start_part == DayPart::Morning || request.end_part == DayPart::Afternoon)
+    {
+        return Err("跨天请假只能从下午开始、在上午结束".to_string());
+    }
+    if request.reason.trim().is_empty() {
+        return Err("请填写请假原因".to_string());
+    }
+    Ok(())
+}
+
+/// 按发放方式计算截至 `today` 已获得的额度，按月累积时向下取整到半天
+fn accrued_days(leave_type: &LeaveType, year: i32, today: NaiveDate) -> f64 {
+    match leave_type.accrual {
+        AccrualMethod::Annual => leave_type.annual_days,
+        AccrualMethod::Monthly => {
+            let months = if year < today.year() {
+                12
+            } else if year > today.year() {
+                0
+            } else {
+                today.month()
+            };
+            (leave_type.annual_days * months as f64 / 12.0 * 2.0).floor() / 2.0
+        }
+    }
+}
+
+/// 请假按开始日期所在年份计入额度
+pub fn balance(
+    db: &Database,
+    user_id: &str,
+    leave_type: &LeaveType,
+    year: i32,
+    today: NaiveDate,
+) -> Result<LeaveBalance, String> {
+    let requests: Vec<LeaveRequest> = db
+        .get_leave_requests_by_user(user_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| r.leave_type_id == leave_type.id && r.start_date.year() == year)
+        .collect();
+    let total = |status: ApprovalStatus| -> f64 {
+        requests
+            .iter()
+            .filter(|r| r.status == status)
+            .map(|r| r.days)
+            .sum()
+    };
+
+    let accrued = accrued_days(leave_type, year, today);
+    let adjustment = db
+        .get_leave_adjustment(user_id, &leave_type.id, year)
+        .map_err(|e| e.to_string())?;
+    let used = total(ApprovalStatus::Approved);
+    let pending = total(ApprovalStatus::Pending);
+
+    Ok(LeaveBalance {
+        user_id: user_id.to_string(),
+        leave_type_id: leave_type.id.clone(),
+        year,
+        entitled: leave_type.annual_days,
+        accrued,
+        adjustment,
+        used,
+        pending,
+        available: accrued + adjustment - used - pending,
+    })
+}
+
+pub fn submit(db: &Database, request: CreateLeaveRequest) -> Result<LeaveRequest, String> {
+    validate_range(&request)?;
+
+    let user = db
+        .get_user(&request.user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    // 只能使用所属管理员设置的假期类型
+    let leave_type = db
+        .get_leave_type(&request.leave_type_id)
+        .map_err(|e| e.to_string())?
+        .filter(|t| user.admin_id.as_ref() == Some(&t.admin_id))
+        .ok_or_else(|| "假期类型不存在".to_string())?;
+    let shift = match &user.shift_id {
+        Some(id) => db.get_shift(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+
+    let overlapping = db
+        .get_leave_requests_by_user(&user.id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|r| r.status != ApprovalStatus::Rejected)
+        .any(|r| r.start_date <= request.end_date && request.start_date <= r.end_date);
+    if overlapping {
+        return Err("与已有的请假时间重叠".to_string());
+    }
+
+    let mut leave = LeaveRequest::new(request, user.admin_id.clone(), 0.0);
+    leave.days = requested_days(db, &user, &leave, shift.as_ref())?;
+    if leave.days <= 0.0 {
+        return Err("所选日期内没有需要请假的工作日".to_string());
+    }
+
+    let balance = balance(
+        db,
+        &user.id,
+        &leave_type,
+        leave.start_date.year(),
+        checkin::user_today(db, &user),
+    )?;
+    if balance.available < leave.days {
+        return Err(format!(
+            "{}余额不足，可用 {} 天，申请 {} 天",
+            leave_type.name, balance.available, leave.days
+        ));
+    }
+
+    db.save_leave_request(&leave).map_err(|e| e.to_string())?;
+    Ok(leave)
+}
+
+fn pending_for_review(
+    db: &Database,
+    leave_id: &str,
+    review: &ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    let leave = db
+        .get_leave_request(leave_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "请假申请不存在".to_string())?;
+
+    if leave.status != ApprovalStatus::Pending {
+        return Err("该申请已处理".to_string());
+    }
+    if leave.admin_id.as_ref() != Some(&review.admin_id) {
+        return Err("无权审批该申请".to_string());
+    }
+
+    Ok(leave)
+}
+
+pub fn approve(
+    db: &Database,
+    leave_id: &str,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    let mut leave = pending_for_review(db, leave_id, &review)?;
+
+    let leave_type = db
+        .get_leave_type(&leave.leave_type_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "假期类型不存在".to_string())?;
+    let user = db
+        .get_user(&leave.user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    let balance = balance(
+        db,
+        &user.id,
+        &leave_type,
+        leave.start_date.year(),
+        checkin::user_today(db, &user),
+    )?;
+    // 待审批的额度尚未真正扣除，只需已获得额度扣除已使用部分后足够即可
+    if balance.available + balance.pending < leave.days {
+        return Err(format!("{}余额不足", leave_type.name));
+    }
+
+    leave.status = ApprovalStatus::Approved;
+    leave.reviewed_by = Some(review.admin_id);
+    leave.reviewed_at = Some(chrono::Utc::now().timestamp());
+    leave.review_comment = review.comment;
+    db.save_leave_request(&leave).map_err(|e| e.to_string())?;
+    Ok(leave)
+}
+
+pub fn reject(
+    db: &Database,
+    leave_id: &str,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    let mut leave = pending_for_review(db, leave_id, &review)?;
+
+    leave.status = ApprovalStatus::Rejected;
+    leave.reviewed_by = Some(review.admin_id);
+    leave.reviewed_at = Some(chrono::Utc::now().timestamp());
+    leave.review_comment = review.comment;
+    db.save_leave_request(&leave).map_err(|e| e.to_string())?;
+    Ok(leave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        AssignmentTarget, CalendarDay, DayKind, HolidayCalendar, Location, LocationAssignment,
+        UserRole,
+    };
+    use crate::test_support::{self, date};
+
+    struct Fixture {
+        db: Database,
+        admin: User,
+        user: User,
+        leave_type: LeaveType,
+    }
+
+    fn setup() -> Fixture {
+        let test_support::Fixture { db, admin, user, .. } = test_support::setup();
+        let leave_type = LeaveType::new(
+            "年假".to_string(),
+            5.0,
+            AccrualMethod::Annual,
+            true,
+            admin.id.clone(),
+        );
+        db.save_leave_type(&leave_type).unwrap();
+        Fixture {
+            db,
+            admin,
+            user,
+            leave_type,
+        }
+    }
+
+    fn request(fixture: &Fixture, start: u32, end: u32) -> CreateLeaveRequest {
+        CreateLeaveRequest {
+            user_id: fixture.user.id.clone(),
+            leave_type_id: fixture.leave_type.id.clone(),
+            start_date: date(start),
+            end_date: date(end),
+            start_part: DayPart::Full,
+            end_part: DayPart::Full,
+            reason: "休息".to_string(),
+        }
+    }
+
+    fn review(admin: &User) -> ReviewRequest {
+        ReviewRequest {
+            admin_id: admin.id.clone(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn weekends_and_half_days_are_counted() {
+        let fixture = setup();
+        // 周五下午到下周一上午
+        let mut request = request(&fixture, 14, 17);
+        request.start_part = DayPart::Afternoon;
+        request.end_part = DayPart::Morning;
+        let leave = submit(&fixture.db, request).unwrap();
+        assert_eq!(leave.days, 1.0);
+        assert_eq!(leave.status, ApprovalStatus::Pending);
+    }
+
+    #[test]
+    fn each_day_uses_its_own_calendar() {
+        let fixture = setup();
+        let mut branch = Location::new(
+            "分部".to_string(),
+            30.0,
+            120.0,
+            100.0,
+            fixture.admin.id.clone(),
+        );
+        let calendar = HolidayCalendar::new(
+            "分部日历".to_string(),
+            vec![CalendarDay {
+                date: date(13),
+                kind: DayKind::Holiday,
+                name: "假日".to_string(),
+            }],
+            fixture.admin.id.clone(),
+        );
+        fixture.db.save_calendar(&calendar).unwrap();
+        branch.calendar_id = Some(calendar.id);
+        fixture.db.save_location(&branch).unwrap();
+        let mut user = fixture.user.clone();
+        user.location_id = None;
+        user.location_assignments = vec![
+            LocationAssignment {
+                target: AssignmentTarget::Location(fixture.user.location_id.clone().unwrap()),
+                valid_from: None,
+                valid_until: Some(date(11)),
+            },
+            LocationAssignment {
+                target: AssignmentTarget::Location(branch.id),
+                valid_from: Some(date(12)),
+                valid_until: None,
+            },
+        ];
+        fixture.db.save_user(&user).unwrap();
+
+        // 3 月 10 日至 14 日，13 日是调往分部后的假日
+        let leave = submit(&fixture.db, request(&fixture, 10, 14)).unwrap();
+        assert_eq!(leave.days, 4.0);
+    }
+
+    #[test]
+    fn leave_type_of_another_admin_is_rejected() {
+        let fixture = setup();
+        let other = LeaveType::new(
+            "其他".to_string(),
+            10.0,
+            AccrualMethod::Annual,
+            true,
+            "other-admin".to_string(),
+        );
+        fixture.db.save_leave_type(&other).unwrap();
+        let mut request = request(&fixture, 10, 10);
+        request.leave_type_id = other.id;
+        assert_eq!(submit(&fixture.db, request).unwrap_err(), "假期类型不存在");
+    }
+
+    #[test]
+    fn balance_limits_requests_and_tracks_approvals() {
+        let fixture = setup();
+        let err = submit(&fixture.db, request(&fixture, 10, 18)).unwrap_err();
+        assert!(err.contains("余额不足"), "{}", err);
+
+        let leave = submit(&fixture.db, request(&fixture, 10, 12)).unwrap();
+        let overlap = submit(&fixture.db, request(&fixture, 12, 12)).unwrap_err();
+        assert_eq!(overlap, "与已有的请假时间重叠");
+
+        let stranger = User::new(
+            "other".to_string(),
+            "other123".to_string(),
+            UserRole::Admin,
+            None,
+        );
+        let denied = approve(&fixture.db, &leave.id, review(&stranger)).unwrap_err();
+        assert_eq!(denied, "无权审批该申请");
+
+        approve(&fixture.db, &leave.id, review(&fixture.admin)).unwrap();
+        let balance = balance(
+            &fixture.db,
+            &fixture.user.id,
+            &fixture.leave_type,
+            2025,
+            date(20),
+        )
+        .unwrap();
+        assert_eq!(balance.used, 3.0);
+        assert_eq!(balance.pending, 0.0);
+        assert_eq!(balance.available, 2.0);
+    }
+
+    #[test]
+    fn monthly_accrual_rounds_down_to_half_days() {
+        let mut leave_type = setup().leave_type;
+        leave_type.annual_days = 10.0;
+        leave_type.accrual = AccrualMethod::Monthly;
+        assert_eq!(accrued_days(&leave_type, 2025, date(20)), 2.5);
+        assert_eq!(accrued_days(&leave_type, 2024, date(20)), 10.0);
+        assert_eq!(accrued_days(&leave_type, 2026, date(20)), 0.0);
+    }
+}
@@ -1,36 +1,219 @@
+// 导出到本机文件的接口只有桌面端使用
+#![cfg_attr(not(feature = "desktop"), allow(dead_code))]
+
 mod models;
 mod database;
+mod events;
+#[cfg(feature = "desktop")]
 mod commands;
+mod auth;
+mod checkin;
+mod cli;
+mod correction;
+mod export;
+mod geo;
+mod geojson;
+mod holiday;
+mod leave;
+mod maintenance;
+mod overtime;
+mod plausibility;
+mod report;
+mod schedule;
+mod server;
+mod service;
+mod summary;
+#[cfg(test)]
+mod test_support;
+mod track;
+mod user_import;
+mod webhook;
+mod xlsx;
 
+#[cfg(feature = "desktop")]
 use commands::*;
 use database::Database;
-use std::sync::Mutex;
+pub use cli::run_cli;
+use service::AppState;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 后台汇总任务的执行间隔及补齐的天数
+const SUMMARY_REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const SUMMARY_REFRESH_DAYS: i64 = 31;
+/// 检查 webhook 发件箱的间隔
+const WEBHOOK_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
+/// 桌面端和服务端共用的启动流程：补同步离线打卡并启动后台汇总任务
+fn start(db: Database) -> AppState {
+    // 应用启动时处理上次未同步的离线打卡
+    match checkin::sync_pending(&db, None, chrono::Utc::now().timestamp()) {
+        Ok(synced) if !synced.is_empty() => println!("Synced {} offline check-ins", synced.len()),
+        Ok(_) => {}
+        Err(e) => println!("Failed to sync offline check-ins: {}", e),
+    }
+    
+    let state: AppState = Arc::new(Mutex::new(db));
+    
+    let job_state = state.clone();
+    std::thread::spawn(move || loop {
+        let result = summary::refresh_recent(&job_state, SUMMARY_REFRESH_DAYS);
+        match result {
+            Ok(computed) if computed > 0 => println!("Computed {} daily summaries", computed),
+            Ok(_) => {}
+            Err(e) => println!("Failed to compute daily summaries: {}", e),
+        }
+        std::thread::sleep(SUMMARY_REFRESH_INTERVAL);
+    });
+    
+    let webhook_state = state.clone();
+    std::thread::spawn(move || loop {
+        if let Err(e) = webhook::deliver_due(&webhook_state) {
+            println!("Failed to deliver webhooks: {}", e);
+        }
+        std::thread::sleep(WEBHOOK_POLL_INTERVAL);
+    });
+    
+    state
+}
+
+/// 独立服务端入口，未指定时使用桌面端相同的数据目录和设置中的监听地址
+pub async fn run_server(data_dir: Option<PathBuf>, address: Option<String>) -> Result<(), String> {
+    let db = match data_dir {
+        Some(dir) => Database::open(dir.join("attendance_db")),
+        None => Database::new(),
+    }
+    .map_err(|e| format!("无法打开数据库: {}", e))?;
+    db.init_default_admin().map_err(|e| e.to_string())?;
+    
+    let address = match address {
+        Some(address) => address,
+        None => db.get_settings().map_err(|e| e.to_string())?.server.address,
+    };
+    server::serve(start(db), &address).await
+}
+
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let db = Database::new().expect("Failed to initialize database");
     db.init_default_admin().expect("Failed to initialize default admin");
     
+    let server_settings = db.get_settings().map(|s| s.server).unwrap_or_default();
+    let state = start(db);
+    
+    if server_settings.enabled {
+        let server_state = state.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = server::serve(server_state, &server_settings.address).await {
+                println!("HTTP API stopped: {}", e);
+            }
+        });
+    }
+    
+    let event_state = state.clone();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .manage(Mutex::new(db))
+        .setup(move |app| {
+            // 包括 HTTP 接口和后台任务在内的写入都会推送到管理员窗口
+            let handle = app.handle().clone();
+            event_state
+                .lock()
+                .unwrap()
+                .set_event_listener(Arc::new(move |event| events::emit(&handle, event)));
+            Ok(())
+        })
+        .manage(state)
+        .manage(events::EventWindows::default())
         .invoke_handler(tauri::generate_handler![
             login,
+            watch_data_events,
             get_all_users,
             get_users_by_admin,
             create_user,
+            import_users,
             delete_user,
             update_user_location,
             get_user_location,
+            get_user_locations,
+            update_user_location_assignments,
+            get_location_groups_by_admin,
+            create_location_group,
+            update_location_group,
+            delete_location_group,
+            get_user_groups_by_admin,
+            create_user_group,
+            update_user_group,
+            delete_user_group,
+            get_holiday_calendars_by_admin,
+            create_holiday_calendar,
+            update_holiday_calendar,
+            delete_holiday_calendar,
+            import_holiday_calendar_ics,
+            import_cn_statutory_holidays,
+            set_holiday_calendar,
             get_all_locations,
             get_locations_by_admin,
             create_location,
             update_location,
+            export_locations_geojson,
+            import_locations_geojson,
             delete_location,
             get_attendance_records,
             get_attendance_records_by_admin,
+            query_attendance_records,
+            export_records_csv,
+            export_monthly_report_csv,
+            export_monthly_report_xlsx,
+            export_attendance_track,
+            get_suspicious_records,
             check_in,
+            queue_offline_check_ins,
+            sync_pending_check_ins,
+            get_pending_check_ins,
+            submit_correction_request,
+            get_correction_requests_by_user,
+            get_correction_requests_by_admin,
+            approve_correction_request,
+            reject_correction_request,
+            get_leave_types_by_admin,
+            create_leave_type,
+            update_leave_type,
+            delete_leave_type,
+            submit_leave_request,
+            get_leave_requests_by_user,
+            get_leave_requests_by_admin,
+            approve_leave_request,
+            reject_leave_request,
+            get_leave_balances,
+            set_leave_adjustment,
+            get_overtime_by_user,
+            get_overtime_by_admin,
+            recalculate_overtime,
+            approve_overtime,
+            reject_overtime,
             get_current_location,
+            get_shifts_by_admin,
+            create_shift,
+            update_shift,
+            delete_shift,
+            update_user_shift,
+            get_daily_attendance,
+            get_daily_summaries,
+            recompute_daily_summaries,
+            generate_monthly_report,
+            get_settings,
+            update_settings,
+            get_api_keys_by_admin,
+            create_api_key,
+            delete_api_key,
+            get_webhooks_by_admin,
+            create_webhook,
+            update_webhook,
+            delete_webhook,
+            test_webhook,
+            get_webhook_deliveries,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,239 @@
+use crate::database::Database;
+use crate::models::{AssignmentTarget, UserRole, DEFAULT_TIMEZONE};
+use std::collections::{HashMap, HashSet};
+
+/// 检查数据完整性：无法解析的条目以及指向不存在对象的引用，返回发现的问题
+pub fn verify(db: &Database) -> Result<Vec<String>, String> {
+    let mut problems = db.undecodable_entries().map_err(|e| e.to_string())?;
+    if !problems.is_empty() {
+        // 存在无法解析的条目时，后续的整表读取都会失败
+        return Ok(problems);
+    }
+
+    let users = db.get_all_users().map_err(|e| e.to_string())?;
+    let locations = db.get_all_locations().map_err(|e| e.to_string())?;
+    let groups = db.get_all_location_groups().map_err(|e| e.to_string())?;
+    let user_groups = db.get_all_user_groups().map_err(|e| e.to_string())?;
+    let shifts = db.get_all_shifts().map_err(|e| e.to_string())?;
+    let calendars = db.get_all_calendars().map_err(|e| e.to_string())?;
+    let leave_types = db.get_all_leave_types().map_err(|e| e.to_string())?;
+
+    let admin_ids: HashSet<&str> = users
+        .iter()
+        .filter(|u| u.role == UserRole::Admin)
+        .map(|u| u.id.as_str())
+        .collect();
+    let user_ids: HashSet<&str> = users.iter().map(|u| u.id.as_str()).collect();
+    let location_ids: HashSet<&str> = locations.iter().map(|l| l.id.as_str()).collect();
+    let group_ids: HashSet<&str> = groups.iter().map(|g| g.id.as_str()).collect();
+    let shift_ids: HashSet<&str> = shifts.iter().map(|s| s.id.as_str()).collect();
+    let calendar_ids: HashSet<&str> = calendars.iter().map(|c| c.id.as_str()).collect();
+    let leave_type_ids: HashSet<&str> = leave_types.iter().map(|t| t.id.as_str()).collect();
+
+    if !users
+        .iter()
+        .any(|u| u.role == UserRole::Admin && !u.disabled)
+    {
+        problems.push("没有可用的管理员账号".to_string());
+    }
+
+    let mut usernames: HashMap<&str, usize> = HashMap::new();
+    for user in &users {
+        *usernames.entry(user.username.as_str()).or_default() += 1;
+        let name = &user.username;
+        if user.role == UserRole::User {
+            match &user.admin_id {
+                Some(admin_id) if admin_ids.contains(admin_id.as_str()) => {}
+                Some(admin_id) => {
+                    problems.push(format!("用户 {} 的管理员 {} 不存在", name, admin_id))
+                }
+                None => problems.push(format!("用户 {} 没有所属管理员", name)),
+            }
+        }
+        if let Some(location_id) = &user.location_id {
+            if !location_ids.contains(location_id.as_str()) {
+                problems.push(format!("用户 {} 的打卡位置 {} 不存在", name, location_id));
+            }
+        }
+        if let Some(shift_id) = &user.shift_id {
+            if !shift_ids.contains(shift_id.as_str()) {
+                problems.push(format!("用户 {} 的班次 {} 不存在", name, shift_id));
+            }
+        }
+        for assignment in &user.location_assignments {
+            match &assignment.target {
+                AssignmentTarget::Location(id) if !location_ids.contains(id.as_str()) => {
+                    problems.push(format!("用户 {} 分配的位置 {} 不存在", name, id))
+                }
+                AssignmentTarget::Group(id) if !group_ids.contains(id.as_str()) => {
+                    problems.push(format!("用户 {} 分配的位置组 {} 不存在", name, id))
+                }
+                _ => {}
+            }
+        }
+    }
+    for (username, count) in usernames {
+        if count > 1 {
+            problems.push(format!("用户名 {} 重复 {} 次", username, count));
+        }
+    }
+
+    let mut check_admin = |kind: &str, name: &str, admin_id: &str| {
+        if !admin_ids.contains(admin_id) {
+            problems.push(format!("{} {} 的管理员 {} 不存在", kind, name, admin_id));
+        }
+    };
+    for location in &locations {
+        check_admin("位置", &location.name, &location.admin_id);
+    }
+    for group in &groups {
+        check_admin("位置组", &group.name, &group.admin_id);
+    }
+    for group in &user_groups {
+        check_admin("员工分组", &group.name, &group.admin_id);
+    }
+    for shift in &shifts {
+        check_admin("班次", &shift.name, &shift.admin_id);
+    }
+    for calendar in &calendars {
+        check_admin("节假日日历", &calendar.name, &calendar.admin_id);
+    }
+    for leave_type in &leave_types {
+        check_admin("假期类型", &leave_type.name, &leave_type.admin_id);
+    }
+    for key in db.get_all_api_keys().map_err(|e| e.to_string())? {
+        check_admin("API 密钥", &key.name, &key.admin_id);
+    }
+    for webhook in db.get_all_webhooks().map_err(|e| e.to_string())? {
+        check_admin("Webhook", &webhook.name, &webhook.admin_id);
+    }
+
+    for location in &locations {
+        if let Some(calendar_id) = &location.calendar_id {
+            if !calendar_ids.contains(calendar_id.as_str()) {
+                problems.push(format!(
+                    "位置 {} 的节假日日历 {} 不存在",
+                    location.name, calendar_id
+                ));
+            }
+        }
+    }
+    for group in &user_groups {
+        for user_id in &group.user_ids {
+            if !user_ids.contains(user_id.as_str()) {
+                problems.push(format!(
+                    "员工分组 {} 包含的用户 {} 不存在",
+                    group.name, user_id
+                ));
+            }
+        }
+        if let Some(calendar_id) = &group.calendar_id {
+            if !calendar_ids.contains(calendar_id.as_str()) {
+                problems.push(format!(
+                    "员工分组 {} 的节假日日历 {} 不存在",
+                    group.name, calendar_id
+                ));
+            }
+        }
+    }
+    for group in &groups {
+        for location_id in &group.location_ids {
+            if !location_ids.contains(location_id.as_str()) {
+                problems.push(format!(
+                    "位置组 {} 包含的位置 {} 不存在",
+                    group.name, location_id
+                ));
+            }
+        }
+    }
+
+    for record in db.get_all_records().map_err(|e| e.to_string())? {
+        if !user_ids.contains(record.user_id.as_str()) {
+            problems.push(format!(
+                "打卡记录 {} 的用户 {} 不存在",
+                record.id, record.user_id
+            ));
+        }
+        if !location_ids.contains(record.location_id.as_str()) {
+            problems.push(format!(
+                "打卡记录 {} 的位置 {} 不存在",
+                record.id, record.location_id
+            ));
+        }
+    }
+    for correction in db.get_all_corrections().map_err(|e| e.to_string())? {
+        if !user_ids.contains(correction.user_id.as_str()) {
+            problems.push(format!(
+                "补卡申请 {} 的用户 {} 不存在",
+                correction.id, correction.user_id
+            ));
+        }
+    }
+    for request in db.get_all_leave_requests().map_err(|e| e.to_string())? {
+        if !user_ids.contains(request.user_id.as_str()) {
+            problems.push(format!(
+                "请假申请 {} 的用户 {} 不存在",
+                request.id, request.user_id
+            ));
+        }
+        if !leave_type_ids.contains(request.leave_type_id.as_str()) {
+            problems.push(format!(
+                "请假申请 {} 的假期类型 {} 不存在",
+                request.id, request.leave_type_id
+            ));
+        }
+    }
+    for overtime in db.get_all_overtime().map_err(|e| e.to_string())? {
+        if !user_ids.contains(overtime.user_id.as_str()) {
+            problems.push(format!(
+                "加班记录 {} 的用户 {} 不存在",
+                overtime.id, overtime.user_id
+            ));
+        }
+    }
+    for pending in db.get_all_pending().map_err(|e| e.to_string())? {
+        if !user_ids.contains(pending.user_id.as_str()) {
+            problems.push(format!(
+                "离线打卡 {} 的用户 {} 不存在",
+                pending.id, pending.user_id
+            ));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// 把旧版本写入的数据升级为当前结构，返回改写的条目数
+pub fn migrate(db: &Database) -> Result<usize, String> {
+    db.migrate_user_data().map_err(|e| e.to_string())?;
+    let mut migrated = 0;
+
+    // 按当前结构重新写入，补齐旧数据缺少的默认字段
+    for user in db.get_all_users().map_err(|e| e.to_string())? {
+        db.save_user(&user).map_err(|e| e.to_string())?;
+        migrated += 1;
+    }
+    let locations = db.get_all_locations().map_err(|e| e.to_string())?;
+    db.save_locations(&locations).map_err(|e| e.to_string())?;
+    migrated += locations.len();
+
+    // 早期的打卡记录没有保存时区和本地日期
+    for mut record in db.get_all_records().map_err(|e| e.to_string())? {
+        if record.local_date.is_some() {
+            continue;
+        }
+        let timezone = locations
+            .iter()
+            .find(|l| l.id == record.location_id)
+            .map(|l| l.timezone)
+            .unwrap_or(DEFAULT_TIMEZONE);
+        record.localize(timezone);
+        db.save_record(&record).map_err(|e| e.to_string())?;
+        migrated += 1;
+    }
+
+    let settings = db.get_settings().map_err(|e| e.to_string())?;
+    db.save_settings(&settings).map_err(|e| e.to_string())?;
+    db.clear_summaries().map_err(|e| e.to_string())?;
+    Ok(migrated)
+}
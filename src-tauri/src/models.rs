@@ -1,6 +1,16 @@
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+/// 位置未设置时区时使用的时区
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Shanghai;
+
+fn default_timezone() -> Tz {
+    DEFAULT_TIMEZONE
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
@@ -10,6 +20,71 @@ pub struct User {
     pub role: UserRole,
     pub admin_id: Option<String>,
     pub location_id: Option<String>,
+    pub shift_id: Option<String>,
+    #[serde(default)]
+    pub location_assignments: Vec<LocationAssignment>,
+    #[serde(default)]
+    pub profile: UserProfile,
+    /// 停用的账号不能登录，其 API 密钥也随之失效
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// 返回给前端和接口调用方的用户信息，不包含密码
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    pub id: String,
+    pub username: String,
+    pub role: UserRole,
+    pub admin_id: Option<String>,
+    pub location_id: Option<String>,
+    pub shift_id: Option<String>,
+    pub location_assignments: Vec<LocationAssignment>,
+    pub profile: UserProfile,
+    pub disabled: bool,
+}
+
+impl From<User> for UserInfo {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            role: user.role,
+            admin_id: user.admin_id,
+            location_id: user.location_id,
+            shift_id: user.shift_id,
+            location_assignments: user.location_assignments,
+            profile: user.profile,
+            disabled: user.disabled,
+        }
+    }
+}
+
+/// 员工资料，均为可选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserProfile {
+    pub display_name: Option<String>,
+    pub employee_no: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "id", rename_all = "camelCase")]
+pub enum AssignmentTarget {
+    Location(String),
+    Group(String),
+}
+
+/// 用户额外可打卡的位置或位置组，起止日期为空表示不限
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAssignment {
+    pub target: AssignmentTarget,
+    pub valid_from: Option<NaiveDate>,
+    pub valid_until: Option<NaiveDate>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -28,6 +103,107 @@ pub struct Location {
     pub longitude: f64,
     pub radius: f64,
     pub admin_id: String,
+    #[serde(default)]
+    pub check_in_windows: Vec<CheckInWindow>,
+    #[serde(default)]
+    pub window_policy: WindowPolicy,
+    pub geofence: Option<Geofence>,
+    #[serde(default)]
+    pub buffer: f64,
+    pub calendar_id: Option<String>,
+    /// IANA 时区，打卡所属日期和班次时间都按该时区计算
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GeoPoint {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// 打卡围栏，未设置时使用位置的中心点和半径作为圆形围栏
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Geofence {
+    Circle { latitude: f64, longitude: f64, radius: f64 },
+    Polygon { vertices: Vec<GeoPoint> },
+    Union { shapes: Vec<Geofence> },
+}
+
+/// 允许打卡的时间段，`weekdays` 为空表示每天适用，结束时间早于开始时间表示跨夜
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckInWindow {
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+}
+
+/// 时间段外打卡的处理方式：拒绝，或者照常打卡但标记
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowPolicy {
+    #[default]
+    Reject,
+    Flag,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationGroup {
+    pub id: String,
+    pub name: String,
+    pub location_ids: Vec<String>,
+    pub admin_id: String,
+    pub calendar_id: Option<String>,
+}
+
+/// 员工分组，用于按人设置节假日日历
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserGroup {
+    pub id: String,
+    pub name: String,
+    pub user_ids: Vec<String>,
+    pub admin_id: String,
+    pub calendar_id: Option<String>,
+}
+
+/// 可以设置节假日日历的对象
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "id", rename_all = "camelCase")]
+pub enum CalendarTarget {
+    Location(String),
+    Group(String),
+    UserGroup(String),
+}
+
+/// 节假日日历中的特殊日期：法定假日休息，调休日即使是周末也要上班
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DayKind {
+    Holiday,
+    Workday,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub kind: DayKind,
+    pub name: String,
+}
+
+/// 可挂到位置、位置组或员工分组上的节假日日历，未列出的日期按班次的工作日判断
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HolidayCalendar {
+    pub id: String,
+    pub name: String,
+    pub days: Vec<CalendarDay>,
+    pub admin_id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,18 +212,253 @@ pub struct AttendanceRecord {
     pub id: String,
     pub user_id: String,
     pub location_id: String,
+    pub location_group_id: Option<String>,
     pub latitude: f64,
     pub longitude: f64,
     pub timestamp: i64,
     pub status: AttendanceStatus,
     pub error_message: Option<String>,
+    #[serde(default)]
+    pub punch_type: PunchType,
+    pub punctuality: Option<Punctuality>,
+    #[serde(default)]
+    pub flags: Vec<RecordFlag>,
+    pub accuracy: Option<f64>,
+    pub altitude: Option<f64>,
+    pub fix_timestamp: Option<i64>,
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub risk_score: u32,
+    #[serde(default)]
+    pub risk_reasons: Vec<RiskReason>,
+    #[serde(default)]
+    pub suspicious: bool,
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
+    pub source: RecordSource,
+    pub synced_at: Option<i64>,
+    pub correction_id: Option<String>,
+    /// 打卡位置的时区及打卡时间在该时区的日期，旧记录为空
+    pub timezone: Option<Tz>,
+    pub local_date: Option<NaiveDate>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
 pub enum AttendanceStatus {
     Success,
     Failed,
+    OutsideWindow,
+    LowAccuracy,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FlagKind {
+    OutsideWindow,
+}
+
+/// 记录来源：在线打卡、离线采集后补传，或补卡审批通过后手动生成
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordSource {
+    #[default]
+    Online,
+    LateSynced,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// 补卡申请，`requested_time` 为申请补记的打卡时间（秒级时间戳）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionRequest {
+    pub id: String,
+    pub user_id: String,
+    pub admin_id: Option<String>,
+    pub punch_type: PunchType,
+    pub requested_time: i64,
+    pub location_id: Option<String>,
+    pub reason: String,
+    pub status: ApprovalStatus,
+    pub created_at: i64,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<i64>,
+    pub review_comment: Option<String>,
+    pub record_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RiskKind {
+    ImpossibleTravel,
+    RepeatedCoordinates,
+    StaleFix,
+    FenceCentre,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskReason {
+    pub kind: RiskKind,
+    pub detail: String,
+}
+
+/// 打卡被接受但需要管理员留意的情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordFlag {
+    pub kind: FlagKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PunchType {
+    #[default]
+    In,
+    Out,
+}
+
+/// 成功打卡相对于班次的准点情况，分钟数为与班次开始/结束时间的差值
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "minutes", rename_all = "camelCase")]
+pub enum Punctuality {
+    OnTime,
+    Late(i64),
+    EarlyLeave(i64),
+}
+
+/// 班次，`weekdays` 使用 1（周一）到 7（周日）表示上班日
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shift {
+    pub id: String,
+    pub name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub grace_minutes: i64,
+    pub weekdays: Vec<u32>,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DayStatus {
+    Present,
+    Absent,
+    Incomplete,
+    Rest,
+    Leave,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyAttendance {
+    pub user_id: String,
+    pub date: NaiveDate,
+    pub status: DayStatus,
+    pub first_in: Option<i64>,
+    pub last_out: Option<i64>,
+    pub late_minutes: i64,
+    pub early_leave_minutes: i64,
+    pub leave_days: f64,
+    /// 已批准的加班时长（分钟），未乘倍率
+    pub overtime_minutes: i64,
+}
+
+/// 预先计算并存储的每日考勤汇总，相关记录变化时失效重算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailySummary {
+    pub user_id: String,
+    pub date: NaiveDate,
+    pub status: DayStatus,
+    pub first_in: Option<i64>,
+    pub last_out: Option<i64>,
+    pub worked_minutes: i64,
+    pub late_minutes: i64,
+    pub early_leave_minutes: i64,
+    pub leave_days: f64,
+    pub overtime_minutes: i64,
+    pub failed_attempts: u32,
+    /// 按班次和节假日日历是否需要出勤
+    #[serde(default)]
+    pub scheduled: bool,
+    pub computed_at: i64,
+}
+
+/// 月报中的各项统计，天数和次数按每日汇总累加
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportFigures {
+    pub working_days: u32,
+    pub present_days: u32,
+    pub late_count: u32,
+    pub late_minutes: i64,
+    pub early_leave_count: u32,
+    pub early_leave_minutes: i64,
+    pub absent_days: u32,
+    pub incomplete_days: u32,
+    pub leave_days: f64,
+    pub overtime_minutes: i64,
+    pub failed_attempts: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyReportRow {
+    pub user_id: String,
+    pub username: String,
+    pub figures: ReportFigures,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyReport {
+    pub admin_id: String,
+    /// `YYYY-MM`
+    pub month: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub rows: Vec<MonthlyReportRow>,
+    pub totals: ReportFigures,
+}
+
+/// 加班发生日的类型，决定加班倍率
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OvertimeDayType {
+    Weekday,
+    Weekend,
+    Holiday,
+}
+
+/// 某个工作日的加班，由上下班打卡计算得出，审批通过后才计入统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OvertimeRecord {
+    pub id: String,
+    pub user_id: String,
+    pub admin_id: Option<String>,
+    pub date: NaiveDate,
+    pub day_type: OvertimeDayType,
+    pub start: i64,
+    pub end: i64,
+    pub minutes: i64,
+    pub multiplier: f64,
+    pub status: ApprovalStatus,
+    pub created_at: i64,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<i64>,
+    pub review_comment: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -67,6 +478,15 @@ pub struct CreateLocationRequest {
     pub longitude: f64,
     pub radius: f64,
     pub admin_id: String,
+    #[serde(default)]
+    pub check_in_windows: Vec<CheckInWindow>,
+    #[serde(default)]
+    pub window_policy: WindowPolicy,
+    pub geofence: Option<Geofence>,
+    #[serde(default)]
+    pub buffer: f64,
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,6 +496,572 @@ pub struct UpdateLocationRequest {
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
     pub radius: Option<f64>,
+    pub check_in_windows: Option<Vec<CheckInWindow>>,
+    pub window_policy: Option<WindowPolicy>,
+    pub geofence: Option<Geofence>,
+    pub buffer: Option<f64>,
+    pub timezone: Option<Tz>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLocationGroupRequest {
+    pub name: String,
+    pub location_ids: Vec<String>,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateLocationGroupRequest {
+    pub name: Option<String>,
+    pub location_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUserGroupRequest {
+    pub name: String,
+    pub user_ids: Vec<String>,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserGroupRequest {
+    pub name: Option<String>,
+    pub user_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateHolidayCalendarRequest {
+    pub name: String,
+    #[serde(default)]
+    pub days: Vec<CalendarDay>,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHolidayCalendarRequest {
+    pub name: Option<String>,
+    pub days: Option<Vec<CalendarDay>>,
+}
+
+/// 假期额度的发放方式：年初一次发放，或按月累积
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AccrualMethod {
+    Annual,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaveType {
+    pub id: String,
+    pub name: String,
+    pub annual_days: f64,
+    pub accrual: AccrualMethod,
+    pub paid: bool,
+    pub admin_id: String,
+}
+
+/// 请假起止日的时段，开始日只能是全天或下午，结束日只能是全天或上午
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DayPart {
+    #[default]
+    Full,
+    Morning,
+    Afternoon,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaveRequest {
+    pub id: String,
+    pub user_id: String,
+    pub admin_id: Option<String>,
+    pub leave_type_id: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub start_part: DayPart,
+    pub end_part: DayPart,
+    pub days: f64,
+    pub reason: String,
+    pub status: ApprovalStatus,
+    pub created_at: i64,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<i64>,
+    pub review_comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaveBalance {
+    pub user_id: String,
+    pub leave_type_id: String,
+    pub year: i32,
+    pub entitled: f64,
+    pub accrued: f64,
+    pub adjustment: f64,
+    pub used: f64,
+    pub pending: f64,
+    pub available: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLeaveTypeRequest {
+    pub name: String,
+    pub annual_days: f64,
+    pub accrual: AccrualMethod,
+    pub paid: bool,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateLeaveTypeRequest {
+    pub name: Option<String>,
+    pub annual_days: Option<f64>,
+    pub accrual: Option<AccrualMethod>,
+    pub paid: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateLeaveRequest {
+    pub user_id: String,
+    pub leave_type_id: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    #[serde(default)]
+    pub start_part: DayPart,
+    #[serde(default)]
+    pub end_part: DayPart,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCorrectionRequest {
+    pub user_id: String,
+    pub punch_type: PunchType,
+    pub requested_time: i64,
+    pub location_id: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewRequest {
+    pub admin_id: String,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShiftRequest {
+    pub name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub grace_minutes: i64,
+    pub weekdays: Vec<u32>,
+    pub admin_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateShiftRequest {
+    pub name: Option<String>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub grace_minutes: Option<i64>,
+    pub weekdays: Option<Vec<u32>>,
+}
+
+/// 全局配置，缺省字段使用默认值以便旧数据升级
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub accuracy: AccuracyPolicy,
+    pub plausibility: PlausibilityPolicy,
+    pub punch: PunchPolicy,
+    pub offline: OfflinePolicy,
+    pub correction: CorrectionPolicy,
+    pub overtime: OvertimePolicy,
+    pub server: ServerSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccuracyPolicy {
+    /// 定位精度超过该值（米）时拒绝打卡，为空表示不限制
+    pub max_accuracy: Option<f64>,
+    /// 精度圆与围栏的重叠比例（0 到 1）达到该值时视为在范围内
+    pub min_overlap: f64,
+}
+
+impl Default for AccuracyPolicy {
+    fn default() -> Self {
+        Self {
+            max_accuracy: None,
+            min_overlap: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlausibilityPolicy {
+    /// 两次打卡之间允许的最大移动速度（km/h）
+    pub max_speed_kmh: f64,
+    /// 与最近多少条记录比较坐标是否完全相同
+    pub repeat_window: usize,
+    /// 定位时间早于打卡时间超过该秒数视为过期定位
+    pub max_fix_age_seconds: i64,
+    /// 风险分达到该值时标记为可疑
+    pub flag_threshold: u32,
+}
+
+impl Default for PlausibilityPolicy {
+    fn default() -> Self {
+        Self {
+            max_speed_kmh: 200.0,
+            repeat_window: 5,
+            max_fix_age_seconds: 300,
+            flag_threshold: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PunchPolicy {
+    /// 同类型成功打卡之间的最短间隔（秒）
+    pub min_interval_seconds: i64,
+}
+
+impl Default for PunchPolicy {
+    fn default() -> Self {
+        Self {
+            min_interval_seconds: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OfflinePolicy {
+    /// 离线打卡从采集到同步允许的最长时间（小时）
+    pub max_age_hours: i64,
+}
+
+impl Default for OfflinePolicy {
+    fn default() -> Self {
+        Self { max_age_hours: 72 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CorrectionPolicy {
+    /// 每人每月可提交的补卡次数，已驳回的申请不计入
+    pub monthly_quota: u32,
+}
+
+impl Default for CorrectionPolicy {
+    fn default() -> Self {
+        Self { monthly_quota: 3 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OvertimePolicy {
+    /// 低于该时长（分钟）的加班不计
+    pub min_minutes: i64,
+    /// 加班时长按该粒度（分钟）向下取整，0 表示不取整
+    pub rounding_minutes: i64,
+    pub weekday_multiplier: f64,
+    pub weekend_multiplier: f64,
+    pub holiday_multiplier: f64,
+}
+
+impl Default for OvertimePolicy {
+    fn default() -> Self {
+        Self {
+            min_minutes: 30,
+            rounding_minutes: 30,
+            weekday_multiplier: 1.5,
+            weekend_multiplier: 2.0,
+            holiday_multiplier: 3.0,
+        }
+    }
+}
+
+/// 内置 HTTP 接口，默认关闭，修改后重启应用生效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerSettings {
+    pub enabled: bool,
+    /// 监听地址，例如 `127.0.0.1:8787`
+    pub address: String,
+    /// 登录会话的有效时长（小时）
+    pub session_hours: i64,
+    /// 允许跨域访问的来源，例如 `http://tauri.localhost`；为空时不允许跨域
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:8787".to_string(),
+            session_hours: 12,
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+/// 供脚本调用 HTTP 接口的密钥，以所属管理员的身份访问；只保存密钥的 SHA-256 哈希
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub admin_id: String,
+    pub key_hash: String,
+    /// 密钥开头几位，便于在列表中辨认
+    pub prefix: String,
+    pub created_at: i64,
+}
+
+/// 新建的密钥，明文只在创建时返回一次
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    pub secret: String,
+}
+
+/// 可订阅的 webhook 事件，`ping` 只用于测试端点
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebhookEvent {
+    CheckInSuccess,
+    CheckInFailed,
+    LateArrival,
+    CorrectionApproved,
+    UserCreated,
+    Ping,
+}
+
+/// 管理员登记的 webhook 端点，`secret` 用于对请求体签名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub admin_id: String,
+    pub name: String,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequest {
+    pub admin_id: String,
+    pub name: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequest {
+    pub name: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+}
+
+/// 待投递的事件，保存在发件箱中直到投递成功或超过重试次数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    /// 签名所用的请求体原文
+    pub payload: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub created_at: i64,
+}
+
+/// 每次投递尝试的结果，`next_attempt_at` 为空表示不再重试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryLog {
+    pub id: String,
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    pub attempt: u32,
+    pub timestamp: i64,
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub next_attempt_at: Option<i64>,
+}
+
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// 数据库备份，按数据表保存原始的键值
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseBackup {
+    pub version: u32,
+    pub created_at: i64,
+    pub trees: BTreeMap<String, Vec<(String, String)>>,
+}
+
+/// 打卡记录查询条件，为空的条件不过滤；日期按记录的本地日期比较
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordQuery {
+    pub admin_id: Option<String>,
+    pub user_id: Option<String>,
+    pub location_id: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub status: Option<AttendanceStatus>,
+    pub punch_type: Option<PunchType>,
+}
+
+/// 导出打卡记录时可选的列
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordColumn {
+    Username,
+    LocationName,
+    Date,
+    Time,
+    Timezone,
+    PunchType,
+    Status,
+    Punctuality,
+    Latitude,
+    Longitude,
+    Accuracy,
+    RiskScore,
+    Source,
+    ErrorMessage,
+}
+
+/// 导出月报时可选的列
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportColumn {
+    Username,
+    WorkingDays,
+    PresentDays,
+    LateCount,
+    LateMinutes,
+    EarlyLeaveCount,
+    EarlyLeaveMinutes,
+    AbsentDays,
+    IncompleteDays,
+    LeaveDays,
+    OvertimeMinutes,
+    FailedAttempts,
+}
+
+/// 导入文件中某一行的错误，`line` 为文件中的行号（表头为第 1 行）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowError {
+    pub line: usize,
+    pub username: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserImportReport {
+    pub total_rows: usize,
+    pub imported: Vec<UserInfo>,
+    pub errors: Vec<ImportRowError>,
+    /// 仅校验时为 false
+    pub applied: bool,
+}
+
+/// 打卡轨迹的导出格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackFormat {
+    Gpx,
+    Kml,
+}
+
+/// 导入 GeoJSON 时同名位置的处理方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GeoJsonImportMode {
+    UpdateByName,
+    CreateNew,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// `index` 为要素在 FeatureCollection 中的序号，从 0 开始
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationChange {
+    pub index: usize,
+    pub name: String,
+    pub action: ImportAction,
+    pub location_id: Option<String>,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureError {
+    pub index: usize,
+    pub name: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationImportReport {
+    pub changes: Vec<LocationChange>,
+    pub errors: Vec<FeatureError>,
+    /// 仅校验时为 false
+    pub applied: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,15 +1073,25 @@ pub struct LoginRequest {
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub success: bool,
-    pub user: Option<User>,
+    pub user: Option<UserInfo>,
     pub message: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckInRequest {
     pub user_id: String,
     pub latitude: f64,
     pub longitude: f64,
+    #[serde(default)]
+    pub punch_type: PunchType,
+    /// 水平精度（米）
+    pub accuracy: Option<f64>,
+    pub altitude: Option<f64>,
+    /// 定位时间（秒级时间戳）
+    pub fix_timestamp: Option<i64>,
+    pub provider: Option<String>,
+    /// 客户端生成的幂等键，重试时返回首次提交产生的记录
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -105,6 +1101,35 @@ pub struct CheckInResponse {
     pub message: Option<String>,
 }
 
+/// 离线采集的打卡，`captured_at` 为设备记录的打卡时间（秒级时间戳）
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineCheckIn {
+    pub request: CheckInRequest,
+    pub captured_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PendingStatus {
+    Pending,
+    Synced,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingCheckIn {
+    pub id: String,
+    pub user_id: String,
+    pub request: CheckInRequest,
+    pub captured_at: i64,
+    pub queued_at: i64,
+    pub status: PendingStatus,
+    pub record_id: Option<String>,
+    pub message: Option<String>,
+}
+
 impl User {
     pub fn new(username: String, password: String, role: UserRole, admin_id: Option<String>) -> Self {
         Self {
@@ -114,10 +1139,67 @@ impl User {
             role,
             admin_id,
             location_id: None,
+            shift_id: None,
+            location_assignments: Vec::new(),
+            profile: UserProfile::default(),
+            disabled: false,
         }
     }
 }
 
+impl LocationAssignment {
+    pub fn is_active_on(&self, date: NaiveDate) -> bool {
+        self.valid_from.is_none_or(|from| date >= from)
+            && self.valid_until.is_none_or(|until| date <= until)
+    }
+}
+
+impl LocationGroup {
+    pub fn new(name: String, location_ids: Vec<String>, admin_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            location_ids,
+            admin_id,
+            calendar_id: None,
+        }
+    }
+}
+
+impl UserGroup {
+    pub fn new(name: String, user_ids: Vec<String>, admin_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            user_ids,
+            admin_id,
+            calendar_id: None,
+        }
+    }
+}
+
+impl HolidayCalendar {
+    pub fn new(name: String, days: Vec<CalendarDay>, admin_id: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            days,
+            admin_id,
+        }
+    }
+    
+    pub fn day_kind(&self, date: NaiveDate) -> Option<DayKind> {
+        self.days.iter().find(|d| d.date == date).map(|d| d.kind)
+    }
+    
+    /// 合并导入的日期，同一天以新导入的为准
+    pub fn merge(&mut self, days: Vec<CalendarDay>) {
+        self.days.retain(|d| !days.iter().any(|n| n.date == d.date));
+        self.days.extend(days);
+        self.days.sort_by_key(|d| d.date);
+    }
+}
+
 impl Location {
     pub fn new(name: String, latitude: f64, longitude: f64, radius: f64, admin_id: String) -> Self {
         Self {
@@ -127,6 +1209,135 @@ impl Location {
             longitude,
             radius,
             admin_id,
+            check_in_windows: Vec::new(),
+            window_policy: WindowPolicy::Reject,
+            geofence: None,
+            buffer: 0.0,
+            calendar_id: None,
+            timezone: DEFAULT_TIMEZONE,
+        }
+    }
+    
+    pub fn fence(&self) -> Geofence {
+        self.geofence.clone().unwrap_or(Geofence::Circle {
+            latitude: self.latitude,
+            longitude: self.longitude,
+            radius: self.radius,
+        })
+    }
+}
+
+impl Shift {
+    pub fn new(
+        name: String,
+        start_time: NaiveTime,
+        end_time: NaiveTime,
+        grace_minutes: i64,
+        weekdays: Vec<u32>,
+        admin_id: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            start_time,
+            end_time,
+            grace_minutes,
+            weekdays,
+            admin_id,
+        }
+    }
+}
+
+impl CorrectionRequest {
+    pub fn new(request: CreateCorrectionRequest, admin_id: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: request.user_id,
+            admin_id,
+            punch_type: request.punch_type,
+            requested_time: request.requested_time,
+            location_id: request.location_id,
+            reason: request.reason,
+            status: ApprovalStatus::Pending,
+            created_at: chrono::Utc::now().timestamp(),
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+            record_id: None,
+        }
+    }
+}
+
+impl LeaveType {
+    pub fn new(
+        name: String,
+        annual_days: f64,
+        accrual: AccrualMethod,
+        paid: bool,
+        admin_id: String,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            annual_days,
+            accrual,
+            paid,
+            admin_id,
+        }
+    }
+}
+
+impl LeaveRequest {
+    pub fn new(request: CreateLeaveRequest, admin_id: Option<String>, days: f64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: request.user_id,
+            admin_id,
+            leave_type_id: request.leave_type_id,
+            start_date: request.start_date,
+            end_date: request.end_date,
+            start_part: request.start_part,
+            end_part: request.end_part,
+            days,
+            reason: request.reason,
+            status: ApprovalStatus::Pending,
+            created_at: chrono::Utc::now().timestamp(),
+            reviewed_by: None,
+            reviewed_at: None,
+            review_comment: None,
+        }
+    }
+    
+    /// 请假在指定日期占用的天数（0、0.5 或 1），不区分是否为工作日
+    pub fn portion_on(&self, date: NaiveDate) -> f64 {
+        if date < self.start_date || date > self.end_date {
+            return 0.0;
+        }
+        let part = if date == self.start_date && self.start_part != DayPart::Full {
+            self.start_part
+        } else if date == self.end_date {
+            self.end_part
+        } else {
+            DayPart::Full
+        };
+        match part {
+            DayPart::Full => 1.0,
+            DayPart::Morning | DayPart::Afternoon => 0.5,
+        }
+    }
+}
+
+impl PendingCheckIn {
+    pub fn new(request: CheckInRequest, captured_at: i64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: request.user_id.clone(),
+            request,
+            captured_at,
+            queued_at: chrono::Utc::now().timestamp(),
+            status: PendingStatus::Pending,
+            record_id: None,
+            message: None,
         }
     }
 }
@@ -137,6 +1348,7 @@ impl AttendanceRecord {
         location_id: String,
         latitude: f64,
         longitude: f64,
+        punch_type: PunchType,
         status: AttendanceStatus,
         error_message: Option<String>,
     ) -> Self {
@@ -144,11 +1356,35 @@ impl AttendanceRecord {
             id: Uuid::new_v4().to_string(),
             user_id,
             location_id,
+            location_group_id: None,
             latitude,
             longitude,
             timestamp: chrono::Utc::now().timestamp(),
             status,
             error_message,
+            punch_type,
+            punctuality: None,
+            flags: Vec::new(),
+            accuracy: None,
+            altitude: None,
+            fix_timestamp: None,
+            provider: None,
+            risk_score: 0,
+            risk_reasons: Vec::new(),
+            suspicious: false,
+            idempotency_key: None,
+            source: RecordSource::Online,
+            synced_at: None,
+            correction_id: None,
+            timezone: None,
+            local_date: None,
         }
     }
+    
+    /// 按打卡位置的时区记录打卡时间所在的本地日期，需在设置 `timestamp` 之后调用
+    pub fn localize(&mut self, timezone: Tz) {
+        self.timezone = Some(timezone);
+        self.local_date = DateTime::from_timestamp(self.timestamp, 0)
+            .map(|dt| dt.with_timezone(&timezone).date_naive());
+    }
 }
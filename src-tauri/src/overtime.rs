@@ -0,0 +1,416 @@
+use crate::database::Database;
+use crate::holiday;
+use crate::models::{
+    ApprovalStatus, AttendanceRecord, AttendanceStatus, DayKind, HolidayCalendar, OvertimeDayType,
+    OvertimePolicy, OvertimeRecord, PunchType, ReviewRequest, Shift, User, DEFAULT_TIMEZONE,
+};
+use crate::schedule;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+pub fn validate_policy(policy: &OvertimePolicy) -> Result<(), String> {
+    if policy.min_minutes < 0 || policy.rounding_minutes < 0 {
+        return Err("加班最低时长和取整粒度不能为负数".to_string());
+    }
+    if [
+        policy.weekday_multiplier,
+        policy.weekend_multiplier,
+        policy.holiday_multiplier,
+    ]
+    .iter()
+    .any(|m| *m <= 0.0)
+    {
+        return Err("加班倍率必须大于 0".to_string());
+    }
+    Ok(())
+}
+
+fn day_type(shift: &Shift, calendar: Option<&HolidayCalendar>, date: NaiveDate) -> OvertimeDayType {
+    if schedule::is_working_day(Some(shift), calendar, date) {
+        OvertimeDayType::Weekday
+    } else if calendar.and_then(|c| c.day_kind(date)) == Some(DayKind::Holiday) {
+        OvertimeDayType::Holiday
+    } else {
+        OvertimeDayType::Weekend
+    }
+}
+
+fn multiplier(policy: &OvertimePolicy, day_type: OvertimeDayType) -> f64 {
+    match day_type {
+        OvertimeDayType::Weekday => policy.weekday_multiplier,
+        OvertimeDayType::Weekend => policy.weekend_multiplier,
+        OvertimeDayType::Holiday => policy.holiday_multiplier,
+    }
+}
+
+/// 按粒度向下取整，不足最低时长时返回 0
+pub fn round_minutes(policy: &OvertimePolicy, minutes: i64) -> i64 {
+    if minutes < policy.min_minutes.max(1) {
+        return 0;
+    }
+    if policy.rounding_minutes > 0 {
+        minutes / policy.rounding_minutes * policy.rounding_minutes
+    } else {
+        minutes
+    }
+}
+
+/// 某个班次日期的加班时段（开始、结束时间戳）：工作日从班次结束算起，休息日和节假日按整段出勤计算
+fn session(
+    shift: &Shift,
+    working_day: bool,
+    date: NaiveDate,
+    records: &[AttendanceRecord],
+) -> Option<(i64, i64)> {
+    let day_records: Vec<&AttendanceRecord> = records
+        .iter()
+        .filter(|r| r.status == AttendanceStatus::Success)
+        .filter(|r| schedule::work_date(r, Some(shift)) == date)
+        .collect();
+    let first_in = day_records
+        .iter()
+        .filter(|r| r.punch_type == PunchType::In)
+        .map(|r| r.timestamp)
+        .min()?;
+    let last_out = day_records
+        .iter()
+        .filter(|r| r.punch_type == PunchType::Out)
+        .map(|r| r.timestamp)
+        .max()?;
+
+    let start = if working_day {
+        let tz = day_records
+            .iter()
+            .find(|r| r.timestamp == last_out)
+            .and_then(|r| r.timezone)
+            .unwrap_or(DEFAULT_TIMEZONE);
+        schedule::shift_end(shift, date, tz).max(first_in)
+    } else {
+        first_in
+    };
+
+    (last_out > start).then_some((start, last_out))
+}
+
+/// 重新计算用户某天的加班；已审批的记录保持不变，待审批的记录随打卡更新
+pub fn refresh(
+    db: &Database,
+    user: &User,
+    date: NaiveDate,
+) -> Result<Option<OvertimeRecord>, String> {
+    let existing = db
+        .get_overtime_by_user(&user.id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|o| o.date == date);
+    if let Some(existing) = existing
+        .as_ref()
+        .filter(|o| o.status != ApprovalStatus::Pending)
+    {
+        return Ok(Some(existing.clone()));
+    }
+
+    let shift = match &user.shift_id {
+        Some(id) => db.get_shift(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let policy = db.get_settings().map_err(|e| e.to_string())?.overtime;
+    let calendar = holiday::user_calendar(db, user, date).map_err(|e| e.to_string())?;
+    let records = db
+        .get_records_by_user(&user.id)
+        .map_err(|e| e.to_string())?;
+
+    let computed = shift.as_ref().and_then(|shift| {
+        let day_type = day_type(shift, calendar.as_ref(), date);
+        let (start, end) = session(shift, day_type == OvertimeDayType::Weekday, date, &records)?;
+        let minutes = round_minutes(&policy, (end - start) / 60);
+        (minutes > 0).then_some((day_type, start, end, minutes))
+    });
+
+    let Some((day_type, start, end, minutes)) = computed else {
+        if let Some(existing) = existing {
+            db.delete_overtime(&existing.id)
+                .map_err(|e| e.to_string())?;
+        }
+        return Ok(None);
+    };
+
+    let (id, created_at) = existing
+        .map(|o| (o.id, o.created_at))
+        .unwrap_or_else(|| (Uuid::new_v4().to_string(), chrono::Utc::now().timestamp()));
+    let overtime = OvertimeRecord {
+        id,
+        user_id: user.id.clone(),
+        admin_id: user.admin_id.clone(),
+        date,
+        day_type,
+        start,
+        end,
+        minutes,
+        multiplier: multiplier(&policy, day_type),
+        status: ApprovalStatus::Pending,
+        created_at,
+        reviewed_by: None,
+        reviewed_at: None,
+        review_comment: None,
+    };
+    db.save_overtime(&overtime).map_err(|e| e.to_string())?;
+    Ok(Some(overtime))
+}
+
+/// 下班打卡成功后更新当天的加班
+pub fn refresh_after_punch(
+    db: &Database,
+    user: &User,
+    record: &AttendanceRecord,
+) -> Result<(), String> {
+    if record.punch_type != PunchType::Out || record.status != AttendanceStatus::Success {
+        return Ok(());
+    }
+    let shift = match &user.shift_id {
+        Some(id) => db.get_shift(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    refresh(db, user, schedule::work_date(record, shift.as_ref()))?;
+    Ok(())
+}
+
+pub fn recalculate(
+    db: &Database,
+    user: &User,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<OvertimeRecord>, String> {
+    let mut overtime = Vec::new();
+    for date in start_date.iter_days().take_while(|d| *d <= end_date) {
+        overtime.extend(refresh(db, user, date)?);
+    }
+    Ok(overtime)
+}
+
+fn pending_for_review(
+    db: &Database,
+    overtime_id: &str,
+    review: &ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    let overtime = db
+        .get_overtime(overtime_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "加班记录不存在".to_string())?;
+
+    if overtime.status != ApprovalStatus::Pending {
+        return Err("该加班已处理".to_string());
+    }
+    if overtime.admin_id.as_ref() != Some(&review.admin_id) {
+        return Err("无权审批该加班".to_string());
+    }
+
+    Ok(overtime)
+}
+
+fn finish_review(
+    db: &Database,
+    overtime_id: &str,
+    review: ReviewRequest,
+    status: ApprovalStatus,
+) -> Result<OvertimeRecord, String> {
+    let mut overtime = pending_for_review(db, overtime_id, &review)?;
+
+    overtime.status = status;
+    overtime.reviewed_by = Some(review.admin_id);
+    overtime.reviewed_at = Some(chrono::Utc::now().timestamp());
+    overtime.review_comment = review.comment;
+    db.save_overtime(&overtime).map_err(|e| e.to_string())?;
+    Ok(overtime)
+}
+
+pub fn approve(
+    db: &Database,
+    overtime_id: &str,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    finish_review(db, overtime_id, review, ApprovalStatus::Approved)
+}
+
+pub fn reject(
+    db: &Database,
+    overtime_id: &str,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    finish_review(db, overtime_id, review, ApprovalStatus::Rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CalendarDay, UserRole};
+    use crate::test_support::{self, at, date, Fixture};
+
+    fn setup() -> Fixture {
+        let mut fixture = test_support::setup();
+        let shift = test_support::day_shift(&fixture.db, &fixture.admin, vec![1, 2, 3, 4, 5]);
+        fixture.user.shift_id = Some(shift.id);
+        fixture.db.save_user(&fixture.user).unwrap();
+        fixture
+    }
+
+    fn punch(fixture: &Fixture, punch_type: PunchType, day: u32, hour: u32, minute: u32) {
+        test_support::punch(
+            &fixture.db,
+            &fixture.user,
+            &fixture.location,
+            punch_type,
+            AttendanceStatus::Success,
+            at(day, hour, minute),
+        );
+    }
+
+    fn review(admin: &User) -> ReviewRequest {
+        ReviewRequest {
+            admin_id: admin.id.clone(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn rounding_drops_short_overtime() {
+        let policy = OvertimePolicy::default();
+        assert_eq!(round_minutes(&policy, 29), 0);
+        assert_eq!(round_minutes(&policy, 59), 30);
+        assert_eq!(round_minutes(&policy, 95), 90);
+
+        let exact = OvertimePolicy {
+            rounding_minutes: 0,
+            ..OvertimePolicy::default()
+        };
+        assert_eq!(round_minutes(&exact, 47), 47);
+    }
+
+    #[test]
+    fn negative_or_zero_policy_values_are_rejected() {
+        let negative = OvertimePolicy {
+            min_minutes: -1,
+            ..OvertimePolicy::default()
+        };
+        assert!(validate_policy(&negative).is_err());
+        let zero = OvertimePolicy {
+            holiday_multiplier: 0.0,
+            ..OvertimePolicy::default()
+        };
+        assert!(validate_policy(&zero).is_err());
+        assert!(validate_policy(&OvertimePolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn weekday_overtime_starts_at_shift_end() {
+        let fixture = setup();
+        // 周五 09:00 上班，20:10 下班
+        punch(&fixture, PunchType::In, 14, 9, 0);
+        punch(&fixture, PunchType::Out, 14, 20, 10);
+
+        let overtime = refresh(&fixture.db, &fixture.user, date(14))
+            .unwrap()
+            .unwrap();
+        assert_eq!(overtime.day_type, OvertimeDayType::Weekday);
+        assert_eq!(overtime.minutes, 120);
+        assert_eq!(overtime.multiplier, 1.5);
+        assert_eq!(overtime.status, ApprovalStatus::Pending);
+    }
+
+    #[test]
+    fn rest_days_count_the_whole_session() {
+        let fixture = setup();
+        let calendar = HolidayCalendar::new(
+            "日历".to_string(),
+            vec![CalendarDay {
+                date: date(17),
+                kind: DayKind::Holiday,
+                name: "假日".to_string(),
+            }],
+            fixture.admin.id.clone(),
+        );
+        fixture.db.save_calendar(&calendar).unwrap();
+        let mut location = fixture.location.clone();
+        location.calendar_id = Some(calendar.id);
+        fixture.db.save_location(&location).unwrap();
+
+        // 周六
+        punch(&fixture, PunchType::In, 15, 10, 0);
+        punch(&fixture, PunchType::Out, 15, 13, 0);
+        // 周一，节假日
+        punch(&fixture, PunchType::In, 17, 9, 0);
+        punch(&fixture, PunchType::Out, 17, 11, 0);
+
+        let overtime = recalculate(&fixture.db, &fixture.user, date(15), date(17)).unwrap();
+        assert_eq!(overtime.len(), 2);
+        assert_eq!(overtime[0].day_type, OvertimeDayType::Weekend);
+        assert_eq!(overtime[0].minutes, 180);
+        assert_eq!(overtime[0].multiplier, 2.0);
+        assert_eq!(overtime[1].day_type, OvertimeDayType::Holiday);
+        assert_eq!(overtime[1].minutes, 120);
+        assert_eq!(overtime[1].multiplier, 3.0);
+    }
+
+    #[test]
+    fn approved_overtime_is_not_recomputed() {
+        let fixture = setup();
+        punch(&fixture, PunchType::In, 14, 9, 0);
+        punch(&fixture, PunchType::Out, 14, 19, 0);
+        let overtime = refresh(&fixture.db, &fixture.user, date(14))
+            .unwrap()
+            .unwrap();
+        approve(&fixture.db, &overtime.id, review(&fixture.admin)).unwrap();
+
+        punch(&fixture, PunchType::Out, 14, 21, 0);
+        let refreshed = refresh(&fixture.db, &fixture.user, date(14))
+            .unwrap()
+            .unwrap();
+        assert_eq!(refreshed.minutes, 60);
+        assert_eq!(refreshed.status, ApprovalStatus::Approved);
+        assert_eq!(
+            approve(&fixture.db, &overtime.id, review(&fixture.admin)).unwrap_err(),
+            "该加班已处理"
+        );
+    }
+
+    #[test]
+    fn pending_overtime_is_dropped_when_it_no_longer_qualifies() {
+        let fixture = setup();
+        punch(&fixture, PunchType::In, 14, 9, 0);
+        punch(&fixture, PunchType::Out, 14, 19, 0);
+        refresh(&fixture.db, &fixture.user, date(14)).unwrap();
+
+        let mut settings = fixture.db.get_settings().unwrap();
+        settings.overtime.min_minutes = 90;
+        fixture.db.save_settings(&settings).unwrap();
+
+        assert!(refresh(&fixture.db, &fixture.user, date(14))
+            .unwrap()
+            .is_none());
+        assert!(fixture
+            .db
+            .get_overtime_by_user(&fixture.user.id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn other_admins_cannot_review() {
+        let fixture = setup();
+        punch(&fixture, PunchType::In, 14, 9, 0);
+        punch(&fixture, PunchType::Out, 14, 19, 0);
+        let overtime = refresh(&fixture.db, &fixture.user, date(14))
+            .unwrap()
+            .unwrap();
+        let other = User::new(
+            "other".to_string(),
+            "admin456".to_string(),
+            UserRole::Admin,
+            None,
+        );
+        assert_eq!(
+            reject(&fixture.db, &overtime.id, review(&other)).unwrap_err(),
+            "无权审批该加班"
+        );
+    }
+}
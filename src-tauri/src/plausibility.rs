@@ -0,0 +1,121 @@
+use crate::geo;
+use crate::models::{
+    AttendanceRecord, GeoPoint, Geofence, Location, PlausibilityPolicy, RiskKind, RiskReason,
+};
+
+/// 未来时间的定位允许的时钟误差（秒）
+const CLOCK_SKEW: i64 = 60;
+
+impl RiskKind {
+    pub fn weight(self) -> u32 {
+        match self {
+            RiskKind::ImpossibleTravel => 60,
+            RiskKind::FenceCentre => 50,
+            RiskKind::RepeatedCoordinates => 30,
+            RiskKind::StaleFix => 25,
+        }
+    }
+}
+
+pub fn risk_score(reasons: &[RiskReason]) -> u32 {
+    reasons
+        .iter()
+        .map(|r| r.kind.weight())
+        .sum::<u32>()
+        .min(100)
+}
+
+fn fence_anchors(fence: &Geofence, anchors: &mut Vec<GeoPoint>) {
+    match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            ..
+        } => anchors.push(GeoPoint {
+            latitude: *latitude,
+            longitude: *longitude,
+        }),
+        Geofence::Polygon { vertices } => anchors.extend(vertices.iter().copied()),
+        Geofence::Union { shapes } => shapes.iter().for_each(|s| fence_anchors(s, anchors)),
+    }
+}
+
+/// 将本次打卡与用户历史记录比对，返回可疑原因；`previous` 不需要排序
+pub fn assess(
+    policy: &PlausibilityPolicy,
+    previous: &[AttendanceRecord],
+    location: &Location,
+    point: GeoPoint,
+    fix_timestamp: Option<i64>,
+    now: i64,
+) -> Vec<RiskReason> {
+    let mut reasons = Vec::new();
+
+    if let Some(last) = previous.iter().max_by_key(|r| r.timestamp) {
+        let distance = geo::calculate_distance(
+            last.latitude,
+            last.longitude,
+            point.latitude,
+            point.longitude,
+        );
+        let elapsed = now - last.timestamp;
+        let speed = if elapsed > 0 {
+            distance / elapsed as f64 * 3.6
+        } else if distance > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+
+        if speed > policy.max_speed_kmh {
+            reasons.push(RiskReason {
+                kind: RiskKind::ImpossibleTravel,
+                detail: format!(
+                    "距上次打卡 {:.0} 米，用时 {} 秒，速度约 {:.0} km/h",
+                    distance, elapsed, speed
+                ),
+            });
+        }
+    }
+
+    let mut recent: Vec<&AttendanceRecord> = previous.iter().collect();
+    recent.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    let repeats = recent
+        .iter()
+        .take(policy.repeat_window)
+        .filter(|r| r.latitude == point.latitude && r.longitude == point.longitude)
+        .count();
+    if repeats > 0 {
+        reasons.push(RiskReason {
+            kind: RiskKind::RepeatedCoordinates,
+            detail: format!(
+                "坐标与最近 {} 条记录中的 {} 条完全相同",
+                policy.repeat_window, repeats
+            ),
+        });
+    }
+
+    if let Some(fix_timestamp) = fix_timestamp {
+        let age = now - fix_timestamp;
+        if age > policy.max_fix_age_seconds || age < -CLOCK_SKEW {
+            reasons.push(RiskReason {
+                kind: RiskKind::StaleFix,
+                detail: format!("定位时间与打卡时间相差 {} 秒", age),
+            });
+        }
+    }
+
+    let mut anchors = vec![GeoPoint {
+        latitude: location.latitude,
+        longitude: location.longitude,
+    }];
+    fence_anchors(&location.fence(), &mut anchors);
+    if anchors.contains(&point) {
+        reasons.push(RiskReason {
+            kind: RiskKind::FenceCentre,
+            detail: "坐标与打卡围栏的中心或顶点完全相同".to_string(),
+        });
+    }
+
+    reasons
+}
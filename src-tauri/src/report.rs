@@ -0,0 +1,126 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::models::{
+    DailySummary, DayStatus, MonthlyReport, MonthlyReportRow, ReportFigures, User, UserRole,
+};
+use crate::summary;
+use chrono::{Datelike, Duration, NaiveDate};
+
+impl ReportFigures {
+    pub fn add_day(&mut self, day: &DailySummary) {
+        if day.scheduled {
+            self.working_days += 1;
+        }
+        match day.status {
+            DayStatus::Present => self.present_days += 1,
+            DayStatus::Absent => self.absent_days += 1,
+            DayStatus::Incomplete => self.incomplete_days += 1,
+            DayStatus::Rest | DayStatus::Leave => {}
+        }
+        if day.late_minutes > 0 {
+            self.late_count += 1;
+            self.late_minutes += day.late_minutes;
+        }
+        if day.early_leave_minutes > 0 {
+            self.early_leave_count += 1;
+            self.early_leave_minutes += day.early_leave_minutes;
+        }
+        self.leave_days += day.leave_days;
+        self.overtime_minutes += day.overtime_minutes;
+        self.failed_attempts += day.failed_attempts;
+    }
+
+    pub fn merge(&mut self, other: &ReportFigures) {
+        self.working_days += other.working_days;
+        self.present_days += other.present_days;
+        self.late_count += other.late_count;
+        self.late_minutes += other.late_minutes;
+        self.early_leave_count += other.early_leave_count;
+        self.early_leave_minutes += other.early_leave_minutes;
+        self.absent_days += other.absent_days;
+        self.incomplete_days += other.incomplete_days;
+        self.leave_days += other.leave_days;
+        self.overtime_minutes += other.overtime_minutes;
+        self.failed_attempts += other.failed_attempts;
+    }
+}
+
+/// 解析 `YYYY-MM`，返回该月的第一天和最后一天
+pub fn month_range(month: &str) -> Result<(NaiveDate, NaiveDate), String> {
+    let start = NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| format!("月份格式应为 YYYY-MM: {}", month))?;
+    let next = if start.month() == 12 {
+        NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+    }
+    .ok_or_else(|| format!("无效的月份: {}", month))?;
+    Ok((start, next - Duration::days(1)))
+}
+
+/// 管理员名下每个员工在某月的每日汇总，按用户名排序
+pub struct TeamMonth {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub members: Vec<(User, Vec<DailySummary>)>,
+}
+
+pub fn team_month(db: &Database, admin_id: &str, month: &str) -> Result<TeamMonth, String> {
+    let (start_date, end_date) = month_range(month)?;
+
+    let mut users: Vec<User> = db
+        .get_all_users()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|u| u.role == UserRole::User && u.admin_id.as_deref() == Some(admin_id))
+        .collect();
+    users.sort_by(|a, b| a.username.cmp(&b.username));
+
+    let mut members = Vec::new();
+    for user in users {
+        // 尚未到来的日期不计入，避免把未来的工作日算作缺勤
+        let until = end_date.min(checkin::user_today(db, &user));
+        let days = if start_date <= until {
+            summary::summaries(db, &user, start_date, until)?
+        } else {
+            Vec::new()
+        };
+        members.push((user, days));
+    }
+
+    Ok(TeamMonth {
+        start_date,
+        end_date,
+        members,
+    })
+}
+
+/// 按员工汇总每日数据，并累计合计行
+pub fn summarize(admin_id: &str, team: &TeamMonth) -> MonthlyReport {
+    let mut rows = Vec::new();
+    let mut totals = ReportFigures::default();
+    for (user, days) in &team.members {
+        let mut figures = ReportFigures::default();
+        days.iter().for_each(|day| figures.add_day(day));
+        totals.merge(&figures);
+        rows.push(MonthlyReportRow {
+            user_id: user.id.clone(),
+            username: user.username.clone(),
+            figures,
+        });
+    }
+
+    MonthlyReport {
+        admin_id: admin_id.to_string(),
+        month: team.start_date.format("%Y-%m").to_string(),
+        start_date: team.start_date,
+        end_date: team.end_date,
+        rows,
+        totals,
+    }
+}
+
+/// 管理员名下所有员工的月度考勤统计，基于每日汇总计算
+pub fn monthly_report(db: &Database, admin_id: &str, month: &str) -> Result<MonthlyReport, String> {
+    Ok(summarize(admin_id, &team_month(db, admin_id, month)?))
+}
@@ -0,0 +1,419 @@
+use crate::models::{
+    AttendanceRecord, AttendanceStatus, CheckInWindow, DailyAttendance, DayKind, DayStatus,
+    HolidayCalendar, LeaveRequest, OvertimeRecord, PunchType, Punctuality, Shift, DEFAULT_TIMEZONE,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+pub fn local_datetime(timestamp: i64, tz: Tz) -> NaiveDateTime {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&tz)
+        .naive_local()
+}
+
+pub fn validate_shift(shift: &Shift) -> Result<(), String> {
+    if shift.name.trim().is_empty() {
+        return Err("班次名称不能为空".to_string());
+    }
+    if shift.grace_minutes < 0 {
+        return Err("宽限时间不能为负数".to_string());
+    }
+    if shift.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+        return Err("工作日必须在 1 到 7 之间".to_string());
+    }
+    if shift.start_time == shift.end_time {
+        return Err("班次开始和结束时间不能相同".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_windows(windows: &[CheckInWindow]) -> Result<(), String> {
+    for window in windows {
+        if window.start_time == window.end_time {
+            return Err("打卡时间段的开始和结束时间不能相同".to_string());
+        }
+        if window.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+            return Err("打卡时间段的星期必须在 1 到 7 之间".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn window_contains(window: &CheckInWindow, at: NaiveDateTime) -> bool {
+    let time = at.time();
+    let (date, in_time) = if window.start_time < window.end_time {
+        (
+            at.date(),
+            time >= window.start_time && time <= window.end_time,
+        )
+    } else if time >= window.start_time {
+        (at.date(), true)
+    } else {
+        // 跨夜时间段的后半段属于前一天的星期
+        (at.date() - Duration::days(1), time <= window.end_time)
+    };
+
+    in_time
+        && (window.weekdays.is_empty()
+            || window
+                .weekdays
+                .contains(&date.weekday().number_from_monday()))
+}
+
+/// 没有配置时间段时任何时间都允许打卡
+pub fn within_windows(windows: &[CheckInWindow], at: NaiveDateTime) -> bool {
+    windows.is_empty() || windows.iter().any(|w| window_contains(w, at))
+}
+
+pub fn describe_windows(windows: &[CheckInWindow]) -> String {
+    windows
+        .iter()
+        .map(|w| {
+            format!(
+                "{}-{}",
+                w.start_time.format("%H:%M"),
+                w.end_time.format("%H:%M")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn works_on(shift: &Shift, date: NaiveDate) -> bool {
+    shift
+        .weekdays
+        .contains(&date.weekday().number_from_monday())
+}
+
+/// 节假日日历优先于班次设置：法定假日休息，调休日上班；未分配班次时按周一至周五计算
+pub fn is_working_day(
+    shift: Option<&Shift>,
+    calendar: Option<&HolidayCalendar>,
+    date: NaiveDate,
+) -> bool {
+    match calendar.and_then(|c| c.day_kind(date)) {
+        Some(DayKind::Holiday) => false,
+        Some(DayKind::Workday) => true,
+        None => match shift {
+            Some(shift) => works_on(shift, date),
+            None => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+        },
+    }
+}
+
+/// 本地时间对应的时间戳：夏令时跳过的时间按跳过后的时间计算，重复的时间取较早的一次
+pub fn local_timestamp(tz: Tz, at: NaiveDateTime) -> i64 {
+    tz.from_local_datetime(&at)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(at + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| at.and_utc().timestamp())
+}
+
+fn shift_start(shift: &Shift, date: NaiveDate, tz: Tz) -> i64 {
+    local_timestamp(tz, date.and_time(shift.start_time))
+}
+
+/// 结束时间不晚于开始时间的班次在次日结束
+pub fn shift_end(shift: &Shift, date: NaiveDate, tz: Tz) -> i64 {
+    let end_date = if shift.end_time <= shift.start_time {
+        date + Duration::days(1)
+    } else {
+        date
+    };
+    local_timestamp(tz, end_date.and_time(shift.end_time))
+}
+
+/// 班次前后多长时间内的打卡仍归属该班次
+const SHIFT_TOLERANCE_HOURS: i64 = 4;
+
+/// 打卡所属的班次日期：落在某天班次前后容差范围内的打卡归到那一天，跨夜班次的下班卡会归到前一天；
+/// 同时落在多天范围内时取上班/下班时间最接近的一天，都不在范围内时按本地日期计算
+pub fn shift_date(shift: &Shift, punch_type: PunchType, timestamp: i64, tz: Tz) -> NaiveDate {
+    let today = local_datetime(timestamp, tz).date();
+    let tolerance = SHIFT_TOLERANCE_HOURS * 3600;
+    let anchor = |date: NaiveDate| match punch_type {
+        PunchType::In => shift_start(shift, date, tz),
+        PunchType::Out => shift_end(shift, date, tz),
+    };
+
+    [today, today - Duration::days(1), today + Duration::days(1)]
+        .into_iter()
+        .filter(|date| {
+            shift_start(shift, *date, tz) - tolerance <= timestamp
+                && timestamp <= shift_end(shift, *date, tz) + tolerance
+        })
+        .min_by_key(|date| (timestamp - anchor(*date)).abs())
+        .unwrap_or(today)
+}
+
+pub fn classify_punch(shift: &Shift, punch_type: PunchType, timestamp: i64, tz: Tz) -> Punctuality {
+    let date = shift_date(shift, punch_type, timestamp, tz);
+
+    match punch_type {
+        PunchType::In => {
+            let late = (timestamp - shift_start(shift, date, tz)) / 60;
+            if late > shift.grace_minutes {
+                Punctuality::Late(late)
+            } else {
+                Punctuality::OnTime
+            }
+        }
+        PunchType::Out => {
+            let early = (shift_end(shift, date, tz) - timestamp) / 60;
+            if early > shift.grace_minutes {
+                Punctuality::EarlyLeave(early)
+            } else {
+                Punctuality::OnTime
+            }
+        }
+    }
+}
+
+/// 非工作日的打卡不计迟到早退
+pub fn punctuality(
+    shift: &Shift,
+    calendar: Option<&HolidayCalendar>,
+    punch_type: PunchType,
+    timestamp: i64,
+    tz: Tz,
+) -> Option<Punctuality> {
+    let date = shift_date(shift, punch_type, timestamp, tz);
+    is_working_day(Some(shift), calendar, date)
+        .then(|| classify_punch(shift, punch_type, timestamp, tz))
+}
+
+/// 按打卡位置的时区计算打卡所属的日期，旧记录没有时区时使用默认时区
+pub fn work_date(record: &AttendanceRecord, shift: Option<&Shift>) -> NaiveDate {
+    let tz = record.timezone.unwrap_or(DEFAULT_TIMEZONE);
+    match shift {
+        Some(shift) => shift_date(shift, record.punch_type, record.timestamp, tz),
+        None => local_datetime(record.timestamp, tz).date(),
+    }
+}
+
+/// `leaves` 和 `overtime` 只应包含已批准的记录，全天请假覆盖的缺勤或缺卡视为请假；
+/// 半天假不覆盖另外半天，没有打卡仍算缺勤
+pub fn derive_day(
+    user_id: &str,
+    date: NaiveDate,
+    shift: Option<&Shift>,
+    calendar: Option<&HolidayCalendar>,
+    records: &[AttendanceRecord],
+    leaves: &[LeaveRequest],
+    overtime: &[OvertimeRecord],
+) -> DailyAttendance {
+    let day_records: Vec<&AttendanceRecord> = records
+        .iter()
+        .filter(|r| r.status == AttendanceStatus::Success && work_date(r, shift) == date)
+        .collect();
+
+    let first_in = day_records
+        .iter()
+        .filter(|r| r.punch_type == PunchType::In)
+        .min_by_key(|r| r.timestamp);
+    let last_out = day_records
+        .iter()
+        .filter(|r| r.punch_type == PunchType::Out)
+        .max_by_key(|r| r.timestamp);
+
+    let late_minutes = match first_in.and_then(|r| r.punctuality) {
+        Some(Punctuality::Late(minutes)) => minutes,
+        _ => 0,
+    };
+    let early_leave_minutes = match last_out.and_then(|r| r.punctuality) {
+        Some(Punctuality::EarlyLeave(minutes)) => minutes,
+        _ => 0,
+    };
+
+    let working_day = is_working_day(shift, calendar, date);
+    let leave_days = if working_day {
+        leaves
+            .iter()
+            .map(|l| l.portion_on(date))
+            .sum::<f64>()
+            .min(1.0)
+    } else {
+        0.0
+    };
+
+    let scheduled = shift.is_some() && working_day;
+    let status = match (first_in, last_out) {
+        (Some(_), Some(_)) => DayStatus::Present,
+        _ if scheduled && leave_days >= 1.0 => DayStatus::Leave,
+        (None, None) if scheduled => DayStatus::Absent,
+        (None, None) => DayStatus::Rest,
+        _ => DayStatus::Incomplete,
+    };
+
+    DailyAttendance {
+        user_id: user_id.to_string(),
+        date,
+        status,
+        first_in: first_in.map(|r| r.timestamp),
+        last_out: last_out.map(|r| r.timestamp),
+        late_minutes,
+        early_leave_minutes,
+        leave_days,
+        overtime_minutes: overtime
+            .iter()
+            .filter(|o| o.date == date)
+            .map(|o| o.minutes)
+            .sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreateLeaveRequest, DayPart};
+    use chrono::NaiveTime;
+
+    const TZ: Tz = chrono_tz::Asia::Shanghai;
+
+    fn shift(start: (u32, u32), end: (u32, u32)) -> Shift {
+        Shift::new(
+            "班次".to_string(),
+            NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+            NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
+            5,
+            vec![1, 2, 3, 4, 5, 6, 7],
+            "admin".to_string(),
+        )
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> i64 {
+        local_timestamp(TZ, date(day).and_hms_opt(hour, minute, 0).unwrap())
+    }
+
+    fn punch(punch_type: PunchType, timestamp: i64) -> AttendanceRecord {
+        let mut record = AttendanceRecord::new(
+            "user".to_string(),
+            "location".to_string(),
+            0.0,
+            0.0,
+            punch_type,
+            AttendanceStatus::Success,
+            None,
+        );
+        record.timestamp = timestamp;
+        record.localize(TZ);
+        record
+    }
+
+    fn leave(start_part: DayPart) -> LeaveRequest {
+        let request = CreateLeaveRequest {
+            user_id: "user".to_string(),
+            leave_type_id: "type".to_string(),
+            start_date: date(10),
+            end_date: date(10),
+            start_part,
+            end_part: DayPart::Full,
+            reason: String::new(),
+        };
+        LeaveRequest::new(request, None, 1.0)
+    }
+
+    #[test]
+    fn late_evening_punch_stays_on_the_same_day() {
+        let shift = shift((9, 0), (18, 0));
+        let timestamp = at(10, 22, 0);
+        assert_eq!(shift_date(&shift, PunchType::In, timestamp, TZ), date(10));
+        assert_eq!(
+            classify_punch(&shift, PunchType::In, timestamp, TZ),
+            Punctuality::Late(13 * 60)
+        );
+    }
+
+    #[test]
+    fn punch_outside_every_window_uses_the_local_date() {
+        let shift = shift((9, 0), (18, 0));
+        assert_eq!(
+            shift_date(&shift, PunchType::In, at(10, 23, 30), TZ),
+            date(10)
+        );
+        assert_eq!(
+            shift_date(&shift, PunchType::Out, at(10, 2, 0), TZ),
+            date(10)
+        );
+    }
+
+    #[test]
+    fn overnight_clock_out_belongs_to_the_previous_day() {
+        let shift = shift((22, 0), (6, 0));
+        assert_eq!(
+            shift_date(&shift, PunchType::In, at(10, 21, 55), TZ),
+            date(10)
+        );
+        assert_eq!(
+            shift_date(&shift, PunchType::Out, at(11, 6, 10), TZ),
+            date(10)
+        );
+        assert_eq!(
+            classify_punch(&shift, PunchType::Out, at(11, 5, 0), TZ),
+            Punctuality::EarlyLeave(60)
+        );
+    }
+
+    #[test]
+    fn grace_period_counts_as_on_time() {
+        let shift = shift((9, 0), (18, 0));
+        assert_eq!(
+            classify_punch(&shift, PunchType::In, at(10, 9, 5), TZ),
+            Punctuality::OnTime
+        );
+        assert_eq!(
+            classify_punch(&shift, PunchType::In, at(10, 9, 6), TZ),
+            Punctuality::Late(6)
+        );
+    }
+
+    #[test]
+    fn full_day_leave_covers_a_missing_day() {
+        let shift = shift((9, 0), (18, 0));
+        let day = derive_day(
+            "user",
+            date(10),
+            Some(&shift),
+            None,
+            &[],
+            &[leave(DayPart::Full)],
+            &[],
+        );
+        assert_eq!(day.status, DayStatus::Leave);
+        assert_eq!(day.leave_days, 1.0);
+    }
+
+    #[test]
+    fn half_day_leave_without_punches_is_absent() {
+        let shift = shift((9, 0), (18, 0));
+        let leaves = [leave(DayPart::Afternoon)];
+        let day = derive_day("user", date(10), Some(&shift), None, &[], &leaves, &[]);
+        assert_eq!(day.status, DayStatus::Absent);
+        assert_eq!(day.leave_days, 0.5);
+    }
+
+    #[test]
+    fn punches_on_both_ends_are_present() {
+        let shift = shift((9, 0), (18, 0));
+        let mut clock_in = punch(PunchType::In, at(10, 9, 30));
+        clock_in.punctuality = Some(classify_punch(
+            &shift,
+            PunchType::In,
+            clock_in.timestamp,
+            TZ,
+        ));
+        let records = [clock_in, punch(PunchType::Out, at(10, 18, 0))];
+        let day = derive_day("user", date(10), Some(&shift), None, &records, &[], &[]);
+        assert_eq!(day.status, DayStatus::Present);
+        assert_eq!(day.late_minutes, 30);
+    }
+}
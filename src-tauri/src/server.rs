@@ -0,0 +1,984 @@
+use crate::auth::{self, Principal, Sessions};
+use crate::database::Database;
+use crate::models::*;
+use crate::service::{self, AppState};
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// 员工身份可以调用的接口，其余接口只允许管理员调用
+const SELF_SERVICE_COMMANDS: &[&str] = &[
+    "get_user_location",
+    "get_user_locations",
+    "get_attendance_records",
+    "check_in",
+    "queue_offline_check_ins",
+    "sync_pending_check_ins",
+    "get_pending_check_ins",
+    "submit_correction_request",
+    "get_correction_requests_by_user",
+    "submit_leave_request",
+    "get_leave_requests_by_user",
+    "get_leave_balances",
+    "get_overtime_by_user",
+    "get_daily_attendance",
+    "get_daily_summaries",
+    "get_settings",
+];
+
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "未登录或凭证无效")
+    }
+
+    fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, message)
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// 把各个接口不同形式的返回值统一转换为 JSON，业务错误对应 400
+trait Reply {
+    fn reply(self) -> Result<Value, ApiError>;
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, ApiError> {
+    serde_json::to_value(value).map_err(|e| ApiError::internal(e.to_string()))
+}
+
+impl<T: Serialize> Reply for Result<T, String> {
+    fn reply(self) -> Result<Value, ApiError> {
+        self.map_err(ApiError::bad_request).and_then(to_value)
+    }
+}
+
+impl<T: Serialize> Reply for Vec<T> {
+    fn reply(self) -> Result<Value, ApiError> {
+        to_value(self)
+    }
+}
+
+impl<T: Serialize> Reply for Option<T> {
+    fn reply(self) -> Result<Value, ApiError> {
+        to_value(self)
+    }
+}
+
+impl Reply for CheckInResponse {
+    fn reply(self) -> Result<Value, ApiError> {
+        to_value(self)
+    }
+}
+
+/// 参数名与前端 `invoke` 相同（camelCase），缺省的可选参数视为空
+macro_rules! dispatch {
+    ($db:expr, $command:expr, $args:expr; $($name:ident($($arg:ident: $ty:ty),*);)*) => {
+        match $command {
+            $(stringify!($name) => {
+                #[derive(Deserialize)]
+                #[serde(rename_all = "camelCase")]
+                struct Args {
+                    $($arg: $ty,)*
+                }
+                let Args { $($arg),* } = serde_json::from_value($args)
+                    .map_err(|e| ApiError::bad_request(format!("参数错误: {}", e)))?;
+                service::$name($db, $($arg),*).reply()
+            })*
+            _ => Err(ApiError::new(StatusCode::NOT_FOUND, format!("未知接口: {}", $command))),
+        }
+    };
+}
+
+/// 写入本机文件的导出接口不对外提供
+fn call(db: &Database, command: &str, args: Value) -> Result<Value, ApiError> {
+    dispatch!(db, command, args;
+        get_all_users();
+        get_users_by_admin(admin_id: String);
+        create_user(user: CreateUserRequest);
+        import_users(admin_id: String, content: String, dry_run: bool);
+        delete_user(user_id: String);
+        update_user_location(user_id: String, location_id: String);
+        get_user_location(user_id: String);
+        get_user_locations(user_id: String);
+        update_user_location_assignments(user_id: String, assignments: Vec<LocationAssignment>);
+        get_location_groups_by_admin(admin_id: String);
+        create_location_group(group: CreateLocationGroupRequest);
+        update_location_group(group_id: String, group: UpdateLocationGroupRequest);
+        delete_location_group(group_id: String);
+        get_user_groups_by_admin(admin_id: String);
+        create_user_group(group: CreateUserGroupRequest);
+        update_user_group(group_id: String, group: UpdateUserGroupRequest);
+        delete_user_group(group_id: String);
+        get_holiday_calendars_by_admin(admin_id: String);
+        create_holiday_calendar(calendar: CreateHolidayCalendarRequest);
+        update_holiday_calendar(calendar_id: String, calendar: UpdateHolidayCalendarRequest);
+        delete_holiday_calendar(calendar_id: String);
+        import_holiday_calendar_ics(calendar_id: String, content: String);
+        import_cn_statutory_holidays(calendar_id: String, year: i32);
+        set_holiday_calendar(target: CalendarTarget, calendar_id: Option<String>);
+        get_all_locations();
+        get_locations_by_admin(admin_id: String);
+        create_location(location: CreateLocationRequest);
+        import_locations_geojson(admin_id: String, content: String, mode: GeoJsonImportMode, dry_run: bool);
+        update_location(location_id: String, location: UpdateLocationRequest);
+        delete_location(location_id: String);
+        get_attendance_records(user_id: Option<String>);
+        get_attendance_records_by_admin(admin_id: String);
+        query_attendance_records(query: RecordQuery);
+        get_suspicious_records(admin_id: String, min_score: Option<u32>);
+        check_in(request: CheckInRequest);
+        queue_offline_check_ins(check_ins: Vec<OfflineCheckIn>);
+        sync_pending_check_ins(user_id: Option<String>);
+        get_pending_check_ins(user_id: String);
+        submit_correction_request(request: CreateCorrectionRequest);
+        get_correction_requests_by_user(user_id: String);
+        get_correction_requests_by_admin(admin_id: String, status: Option<ApprovalStatus>);
+        approve_correction_request(correction_id: String, review: ReviewRequest);
+        reject_correction_request(correction_id: String, review: ReviewRequest);
+        get_leave_types_by_admin(admin_id: String);
+        create_leave_type(leave_type: CreateLeaveTypeRequest);
+        update_leave_type(leave_type_id: String, leave_type: UpdateLeaveTypeRequest);
+        delete_leave_type(leave_type_id: String);
+        submit_leave_request(request: CreateLeaveRequest);
+        get_leave_requests_by_user(user_id: String);
+        get_leave_requests_by_admin(admin_id: String, status: Option<ApprovalStatus>);
+        approve_leave_request(leave_id: String, review: ReviewRequest);
+        reject_leave_request(leave_id: String, review: ReviewRequest);
+        get_leave_balances(user_id: String, year: i32);
+        set_leave_adjustment(user_id: String, leave_type_id: String, year: i32, days: f64);
+        get_overtime_by_user(user_id: String);
+        get_overtime_by_admin(admin_id: String, status: Option<ApprovalStatus>);
+        recalculate_overtime(user_id: String, start_date: NaiveDate, end_date: NaiveDate);
+        approve_overtime(overtime_id: String, review: ReviewRequest);
+        reject_overtime(overtime_id: String, review: ReviewRequest);
+        get_api_keys_by_admin(admin_id: String);
+        create_api_key(admin_id: String, name: String);
+        delete_api_key(admin_id: String, key_id: String);
+        get_webhooks_by_admin(admin_id: String);
+        create_webhook(webhook: CreateWebhookRequest);
+        update_webhook(admin_id: String, webhook_id: String, webhook: UpdateWebhookRequest);
+        delete_webhook(admin_id: String, webhook_id: String);
+        test_webhook(admin_id: String, webhook_id: String);
+        get_webhook_deliveries(admin_id: String, webhook_id: String);
+        get_settings();
+        update_settings(settings: Settings);
+        get_shifts_by_admin(admin_id: String);
+        create_shift(shift: CreateShiftRequest);
+        update_shift(shift_id: String, shift: UpdateShiftRequest);
+        delete_shift(shift_id: String);
+        update_user_shift(user_id: String, shift_id: Option<String>);
+        get_daily_attendance(user_id: String, start_date: NaiveDate, end_date: NaiveDate);
+        get_daily_summaries(user_id: String, start_date: NaiveDate, end_date: NaiveDate);
+        recompute_daily_summaries(user_id: String, start_date: NaiveDate, end_date: NaiveDate);
+        generate_monthly_report(admin_id: String, month: String);
+    )
+}
+
+/// 参数中引用的对象，按其所属管理员检查权限
+enum Resource<'a> {
+    User(&'a str),
+    Admin(&'a str),
+    Location(&'a str),
+    LocationGroup(&'a str),
+    UserGroup(&'a str),
+    Calendar(&'a str),
+    Shift(&'a str),
+    LeaveType(&'a str),
+    Correction(&'a str),
+    Leave(&'a str),
+    Overtime(&'a str),
+}
+
+/// 按参数名识别引用的对象；员工分组接口的 `groupId` 指员工分组，其余指位置组
+fn resource<'a>(command: &str, key: &str, id: &'a str) -> Option<Resource<'a>> {
+    Some(match key {
+        // 打卡请求的字段为 snake_case
+        "userId" | "user_id" | "userIds" => Resource::User(id),
+        "adminId" => Resource::Admin(id),
+        "locationId" | "locationIds" => Resource::Location(id),
+        "groupId" if command.ends_with("user_group") => Resource::UserGroup(id),
+        "groupId" => Resource::LocationGroup(id),
+        "calendarId" => Resource::Calendar(id),
+        "shiftId" => Resource::Shift(id),
+        "leaveTypeId" => Resource::LeaveType(id),
+        "correctionId" => Resource::Correction(id),
+        "leaveId" => Resource::Leave(id),
+        "overtimeId" => Resource::Overtime(id),
+        _ => return None,
+    })
+}
+
+/// 位置分配和节假日日历的目标 `{ "type": ..., "id": ... }`
+fn target<'a>(kind: &str, id: &'a str) -> Option<Resource<'a>> {
+    match kind {
+        "location" => Some(Resource::Location(id)),
+        "group" => Some(Resource::LocationGroup(id)),
+        "userGroup" => Some(Resource::UserGroup(id)),
+        _ => None,
+    }
+}
+
+/// 参数中（包括嵌套对象和数组）引用的所有对象
+fn collect_refs<'a>(
+    command: &str,
+    key: Option<&str>,
+    value: &'a Value,
+    refs: &mut Vec<Resource<'a>>,
+) {
+    match value {
+        Value::String(id) => refs.extend(key.and_then(|k| resource(command, k, id))),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|v| collect_refs(command, key, v, refs)),
+        Value::Object(map) => {
+            let kind = map.get("type").and_then(Value::as_str);
+            let id = map.get("id").and_then(Value::as_str);
+            if let (Some(kind), Some(id)) = (kind, id) {
+                refs.extend(target(kind, id));
+            }
+            for (k, v) in map {
+                collect_refs(command, Some(k), v, refs);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 对象所属的管理员，管理员账号属于其本人；对象不存在时返回 None
+fn owner(db: &Database, resource: &Resource) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    Ok(match *resource {
+        Resource::User(id) => db.get_user(id)?.and_then(|u| match u.role {
+            UserRole::Admin => Some(u.id),
+            UserRole::User => u.admin_id,
+        }),
+        Resource::Admin(id) => db
+            .get_user(id)?
+            .filter(|u| u.role == UserRole::Admin)
+            .map(|u| u.id),
+        Resource::Location(id) => db.get_location(id)?.map(|l| l.admin_id),
+        Resource::LocationGroup(id) => db.get_location_group(id)?.map(|g| g.admin_id),
+        Resource::UserGroup(id) => db.get_user_group(id)?.map(|g| g.admin_id),
+        Resource::Calendar(id) => db.get_calendar(id)?.map(|c| c.admin_id),
+        Resource::Shift(id) => db.get_shift(id)?.map(|s| s.admin_id),
+        Resource::LeaveType(id) => db.get_leave_type(id)?.map(|t| t.admin_id),
+        Resource::Correction(id) => db.get_correction(id)?.and_then(|c| c.admin_id),
+        Resource::Leave(id) => db.get_leave_request(id)?.and_then(|l| l.admin_id),
+        Resource::Overtime(id) => db.get_overtime(id)?.and_then(|o| o.admin_id),
+    })
+}
+
+/// 员工只能操作自己的数据，管理员只能操作自己名下的员工和对象；
+/// 参数中引用的每个对象（包括嵌套的打卡请求、分配目标等）都必须属于请求者所在的管理员
+fn authorize(
+    db: &Database,
+    principal: &Principal,
+    command: &str,
+    args: &mut Value,
+) -> Result<(), ApiError> {
+    let internal = |e: Box<dyn std::error::Error>| ApiError::internal(e.to_string());
+    let team = match principal.role {
+        UserRole::User => {
+            if !SELF_SERVICE_COMMANDS.contains(&command) {
+                return Err(ApiError::forbidden("员工无权调用该接口"));
+            }
+            // 可选的用户参数缺省或为 null 时会返回所有人的数据，这里一律替换为本人
+            if let Value::Object(map) = args {
+                map.insert(
+                    "userId".to_string(),
+                    Value::String(principal.user_id.clone()),
+                );
+            }
+            db.get_user(&principal.user_id)
+                .map_err(internal)?
+                .and_then(|u| u.admin_id)
+                .ok_or_else(|| ApiError::forbidden("账号没有所属管理员"))?
+        }
+        UserRole::Admin => {
+            if command == "get_all_users" || command == "get_all_locations" {
+                return Err(ApiError::forbidden("请使用按管理员查询的接口"));
+            }
+            // 系统设置作用于所有管理员，只能在本机修改
+            if command == "update_settings" {
+                return Err(ApiError::forbidden("系统设置只能在桌面端修改"));
+            }
+            if command == "create_user" {
+                let user = args.get("user");
+                let role = user.and_then(|u| u.get("role")).and_then(Value::as_str);
+                let admin_id = user.and_then(|u| u.get("adminId")).and_then(Value::as_str);
+                if role != Some("user") || admin_id != Some(principal.user_id.as_str()) {
+                    return Err(ApiError::forbidden("只能创建本人名下的员工"));
+                }
+            }
+            // 查询条件不带管理员时会返回所有管理员的记录
+            if command == "query_attendance_records" {
+                if let Some(Value::Object(query)) = args.get_mut("query") {
+                    match query.get("adminId") {
+                        None | Some(Value::Null) => {
+                            query.insert(
+                                "adminId".to_string(),
+                                Value::String(principal.user_id.clone()),
+                            );
+                        }
+                        Some(id) if id.as_str() != Some(principal.user_id.as_str()) => {
+                            return Err(ApiError::forbidden("只能查询本人名下的记录"));
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            // 这两个接口不传用户时作用于所有人
+            if (command == "get_attendance_records" || command == "sync_pending_check_ins")
+                && args.get("userId").is_none_or(Value::is_null)
+            {
+                return Err(ApiError::bad_request("请指定用户"));
+            }
+            principal.user_id.clone()
+        }
+    };
+
+    let mut refs = Vec::new();
+    collect_refs(command, None, args, &mut refs);
+    for resource in refs {
+        if let (UserRole::User, Resource::User(id)) = (&principal.role, &resource) {
+            if *id != principal.user_id {
+                return Err(ApiError::forbidden("只能访问本人的数据"));
+            }
+            continue;
+        }
+        if owner(db, &resource).map_err(internal)?.as_deref() != Some(team.as_str()) {
+            return Err(ApiError::forbidden("只能访问本人名下的数据"));
+        }
+    }
+    Ok(())
+}
+
+struct ServerState {
+    db: AppState,
+    sessions: Sessions,
+}
+
+type Shared = Arc<ServerState>;
+
+/// 支持 `Authorization: Bearer <令牌>` 和 `X-Api-Key: <密钥>` 两种写法
+fn bearer(headers: &HeaderMap) -> Option<String> {
+    let authorization = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let api_key = headers.get("x-api-key").and_then(|v| v.to_str().ok());
+    authorization.or(api_key).map(|t| t.trim().to_string())
+}
+
+/// 在阻塞线程中鉴权并调用接口，避免数据库锁阻塞异步运行时
+async fn run(
+    state: Shared,
+    headers: HeaderMap,
+    command: String,
+    mut args: Value,
+) -> Result<Json<Value>, ApiError> {
+    let token = bearer(&headers).ok_or_else(ApiError::unauthorized)?;
+    tokio::task::spawn_blocking(move || {
+        let db = state.db.lock().unwrap();
+        let principal = auth::authenticate(&db, &state.sessions, &token)
+            .map_err(ApiError::internal)?
+            .ok_or_else(ApiError::unauthorized)?;
+        authorize(&db, &principal, &command, &mut args)?;
+        call(&db, &command, args).map(Json)
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+}
+
+async fn command(
+    State(state): State<Shared>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<Value>, ApiError> {
+    let args = if body.is_empty() {
+        Value::Object(Map::new())
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| ApiError::bad_request(format!("请求体不是有效的 JSON: {}", e)))?
+    };
+    if !args.is_object() {
+        return Err(ApiError::bad_request("参数必须是 JSON 对象"));
+    }
+    run(state, headers, name, args).await
+}
+
+/// 只读的资源接口固定按请求者本人（管理员）查询，供报表脚本使用；`build` 根据管理员 ID 构造参数
+async fn admin_resource(
+    state: Shared,
+    headers: HeaderMap,
+    command: &str,
+    build: impl FnOnce(String) -> Value,
+) -> Result<Json<Value>, ApiError> {
+    let token = bearer(&headers).ok_or_else(ApiError::unauthorized)?;
+    let lookup = state.clone();
+    let principal = tokio::task::spawn_blocking(move || {
+        let db = lookup.db.lock().unwrap();
+        auth::authenticate(&db, &lookup.sessions, &token)
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+    .map_err(ApiError::internal)?
+    .ok_or_else(ApiError::unauthorized)?;
+    run(
+        state,
+        headers,
+        command.to_string(),
+        build(principal.user_id),
+    )
+    .await
+}
+
+/// 查询参数原样作为接口参数，并加上管理员 ID
+fn with_admin(query: HashMap<String, String>) -> impl FnOnce(String) -> Value {
+    move |admin_id| {
+        let mut args: Map<String, Value> = query
+            .into_iter()
+            .map(|(k, v)| (k, Value::String(v)))
+            .collect();
+        args.insert("adminId".to_string(), Value::String(admin_id));
+        Value::Object(args)
+    }
+}
+
+async fn users(State(state): State<Shared>, headers: HeaderMap) -> Result<Json<Value>, ApiError> {
+    let args = with_admin(HashMap::new());
+    admin_resource(state, headers, "get_users_by_admin", args).await
+}
+
+async fn locations(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    let args = with_admin(HashMap::new());
+    admin_resource(state, headers, "get_locations_by_admin", args).await
+}
+
+/// 查询参数与 `RecordQuery` 相同，例如 `?startDate=2025-01-01&status=success`
+async fn records(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, ApiError> {
+    let args = with_admin(query);
+    admin_resource(
+        state,
+        headers,
+        "query_attendance_records",
+        |admin_id| json!({ "query": args(admin_id) }),
+    )
+    .await
+}
+
+/// `?month=YYYY-MM`
+async fn monthly_report(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, ApiError> {
+    admin_resource(state, headers, "generate_monthly_report", with_admin(query)).await
+}
+
+/// `?startDate=YYYY-MM-DD&endDate=YYYY-MM-DD`，员工也可以查询本人的汇总
+async fn user_summaries(
+    State(state): State<Shared>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, ApiError> {
+    let mut args: Map<String, Value> = query
+        .into_iter()
+        .map(|(k, v)| (k, Value::String(v)))
+        .collect();
+    args.insert("userId".to_string(), Value::String(user_id));
+    run(
+        state,
+        headers,
+        "get_daily_summaries".to_string(),
+        Value::Object(args),
+    )
+    .await
+}
+
+/// `?status=pending`，不传时返回全部
+async fn overtime(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, ApiError> {
+    admin_resource(state, headers, "get_overtime_by_admin", with_admin(query)).await
+}
+
+/// `?status=pending`，不传时返回全部
+async fn leave_requests(
+    State(state): State<Shared>,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+) -> Result<Json<Value>, ApiError> {
+    let args = with_admin(query);
+    admin_resource(state, headers, "get_leave_requests_by_admin", args).await
+}
+
+async fn create_session(
+    State(state): State<Shared>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<Value>, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let db = state.db.lock().unwrap();
+        let response = service::login(&db, request);
+        let user = match response.user {
+            Some(user) if response.success => user,
+            _ => {
+                return Err(ApiError::new(
+                    StatusCode::UNAUTHORIZED,
+                    response.message.unwrap_or_else(|| "登录失败".to_string()),
+                ))
+            }
+        };
+        let hours = db
+            .get_settings()
+            .map_err(|e| ApiError::internal(e.to_string()))?
+            .server
+            .session_hours;
+        let (token, expires_at) = state.sessions.create(&user, hours);
+        Ok(Json(
+            json!({ "token": token, "expiresAt": expires_at, "user": user }),
+        ))
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+}
+
+async fn delete_session(State(state): State<Shared>, headers: HeaderMap) -> StatusCode {
+    if let Some(token) = bearer(&headers) {
+        state.sessions.remove(&token);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// 只允许设置中列出的来源跨域访问，默认不允许
+fn cors(origins: &[String]) -> CorsLayer {
+    let origins: Vec<HeaderValue> = origins.iter().filter_map(|o| o.parse().ok()).collect();
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static("x-api-key"),
+        ])
+}
+
+/// 所有接口都在 `/api/v1` 下：`POST /commands/{name}` 对应桌面端的同名命令，其余为只读资源。
+/// 客户端模式下桌面端页面跨域访问，需要在 `server.allowedOrigins` 中加入其来源
+pub fn router(db: AppState) -> Router {
+    let origins = db
+        .lock()
+        .unwrap()
+        .get_settings()
+        .map(|s| s.server.allowed_origins)
+        .unwrap_or_default();
+    let state = Arc::new(ServerState {
+        db,
+        sessions: Sessions::default(),
+    });
+    Router::new()
+        .route(
+            "/api/v1/session",
+            post(create_session).delete(delete_session),
+        )
+        .route("/api/v1/commands/{name}", post(command))
+        .route("/api/v1/users", get(users))
+        .route("/api/v1/users/{id}/summaries", get(user_summaries))
+        .route("/api/v1/locations", get(locations))
+        .route("/api/v1/records", get(records))
+        .route("/api/v1/reports/monthly", get(monthly_report))
+        .route("/api/v1/overtime", get(overtime))
+        .route("/api/v1/leave-requests", get(leave_requests))
+        .layer(cors(&origins))
+        .with_state(state)
+}
+
+pub async fn serve(db: AppState, address: &str) -> Result<(), String> {
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("无法监听 {}: {}", address, e))?;
+    println!("HTTP API listening on {}", address);
+    axum::serve(listener, router(db))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use std::sync::Mutex;
+
+    struct Fixture {
+        base: String,
+        db: AppState,
+        admin: User,
+        alice: User,
+        bob: User,
+        own_location: Location,
+        other_location: Location,
+    }
+
+    fn start(state: AppState) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(async move {
+                    let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                    axum::serve(listener, router(state)).await.unwrap();
+                })
+        });
+        format!("http://{}", address)
+    }
+
+    fn setup(allowed_origins: Vec<String>) -> Fixture {
+        let test_support::Fixture {
+            db,
+            admin,
+            user: alice,
+            location: own_location,
+        } = test_support::setup();
+        let other_admin = test_support::admin(&db, "other");
+        let bob = test_support::employee(&db, "bob", &admin);
+        let other_location = test_support::location(&db, "他人位置", &other_admin);
+        let mut settings = db.get_settings().unwrap();
+        settings.server.allowed_origins = allowed_origins;
+        db.save_settings(&settings).unwrap();
+
+        let db: AppState = Arc::new(Mutex::new(db));
+        Fixture {
+            base: start(db.clone()),
+            db,
+            admin,
+            alice,
+            bob,
+            own_location,
+            other_location,
+        }
+    }
+
+    fn response(result: Result<ureq::Response, ureq::Error>) -> (u16, Value) {
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => panic!("请求失败: {}", e),
+        };
+        let status = response.status();
+        let body = response.into_string().unwrap();
+        (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    fn post(fixture: &Fixture, token: Option<&str>, path: &str, body: Value) -> (u16, Value) {
+        let mut request = ureq::post(&format!("{}{}", fixture.base, path))
+            .set("Content-Type", "application/json");
+        if let Some(token) = token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        response(request.send_string(&body.to_string()))
+    }
+
+    fn call(fixture: &Fixture, token: &str, command: &str, args: Value) -> (u16, Value) {
+        post(
+            fixture,
+            Some(token),
+            &format!("/api/v1/commands/{}", command),
+            args,
+        )
+    }
+
+    fn login(fixture: &Fixture, username: &str, password: &str) -> String {
+        let (status, body) = post(
+            fixture,
+            None,
+            "/api/v1/session",
+            json!({ "username": username, "password": password }),
+        );
+        assert_eq!(status, 200);
+        body["token"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn responses_never_include_passwords() {
+        let fixture = setup(Vec::new());
+        let (status, body) = post(
+            &fixture,
+            None,
+            "/api/v1/session",
+            json!({ "username": "admin", "password": "admin123" }),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["user"]["username"], "admin");
+        assert!(body["user"].get("password").is_none());
+
+        let token = body["token"].as_str().unwrap();
+        let users = ureq::get(&format!("{}/api/v1/users", fixture.base))
+            .set("Authorization", &format!("Bearer {}", token))
+            .call();
+        let (status, users) = response(users);
+        assert_eq!(status, 200);
+        assert_eq!(users.as_array().unwrap().len(), 2);
+        assert!(users
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|u| u.get("password").is_none()));
+    }
+
+    #[test]
+    fn employees_cannot_punch_for_others() {
+        let fixture = setup(Vec::new());
+        let token = login(&fixture, "alice", "secret1");
+        let punch =
+            |user: &User| json!({ "user_id": user.id, "latitude": 31.0, "longitude": 121.0 });
+
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "check_in",
+            json!({ "request": punch(&fixture.bob) }),
+        );
+        assert_eq!(status, 403);
+        let queued = json!({ "checkIns": [{ "request": punch(&fixture.bob), "capturedAt": 0 }] });
+        let (status, _) = call(&fixture, &token, "queue_offline_check_ins", queued);
+        assert_eq!(status, 403);
+
+        let (status, body) = call(
+            &fixture,
+            &token,
+            "check_in",
+            json!({ "request": punch(&fixture.alice) }),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["success"], true);
+    }
+
+    #[test]
+    fn admins_cannot_touch_other_admins_resources() {
+        let fixture = setup(Vec::new());
+        let token = login(&fixture, "admin", "admin123");
+        let other = &fixture.other_location.id;
+
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "delete_location",
+            json!({ "locationId": other }),
+        );
+        assert_eq!(status, 403);
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "update_user_location",
+            json!({ "userId": fixture.alice.id, "locationId": other }),
+        );
+        assert_eq!(status, 403);
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "update_user_location_assignments",
+            json!({
+                "userId": fixture.alice.id,
+                "assignments": [{ "target": { "type": "location", "id": other } }],
+            }),
+        );
+        assert_eq!(status, 403);
+
+        let (status, body) = call(
+            &fixture,
+            &token,
+            "update_location",
+            json!({ "locationId": fixture.own_location.id, "location": { "radius": 150.0 } }),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["radius"], 150.0);
+    }
+
+    #[test]
+    fn record_queries_are_limited_to_the_calling_admin() {
+        let fixture = setup(Vec::new());
+        test_support::punch(
+            &fixture.db.lock().unwrap(),
+            &fixture.alice,
+            &fixture.own_location,
+            PunchType::In,
+            AttendanceStatus::Success,
+            test_support::at(14, 9, 0),
+        );
+        let query = |token: &str, query: Value| {
+            call(
+                &fixture,
+                token,
+                "query_attendance_records",
+                json!({ "query": query }),
+            )
+        };
+
+        let other = login(&fixture, "other", "admin123");
+        let (status, body) = query(&other, json!({}));
+        assert_eq!(status, 200);
+        assert_eq!(body, json!([]));
+        let (status, _) = query(&other, json!({ "adminId": null }));
+        assert_eq!(status, 200);
+        let (status, _) = query(&other, json!({ "adminId": fixture.admin.id }));
+        assert_eq!(status, 403);
+
+        let own = login(&fixture, "admin", "admin123");
+        let (status, body) = query(&own, json!({}));
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn explicit_null_user_ids_are_replaced_with_the_caller() {
+        let fixture = setup(Vec::new());
+        {
+            let db = fixture.db.lock().unwrap();
+            for user in [&fixture.alice, &fixture.bob] {
+                test_support::punch(
+                    &db,
+                    user,
+                    &fixture.own_location,
+                    PunchType::In,
+                    AttendanceStatus::Success,
+                    test_support::at(14, 9, 0),
+                );
+            }
+            let request = CheckInRequest {
+                user_id: fixture.bob.id.clone(),
+                latitude: 31.0,
+                longitude: 121.0,
+                punch_type: PunchType::In,
+                accuracy: None,
+                altitude: None,
+                fix_timestamp: None,
+                provider: None,
+                idempotency_key: None,
+            };
+            let captured_at = chrono::Utc::now().timestamp() - 60;
+            db.save_pending(&PendingCheckIn::new(request, captured_at))
+                .unwrap();
+        }
+        let token = login(&fixture, "alice", "secret1");
+
+        let (status, body) = call(
+            &fixture,
+            &token,
+            "get_attendance_records",
+            json!({ "userId": null }),
+        );
+        assert_eq!(status, 200);
+        let records = body.as_array().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["userId"], fixture.alice.id);
+
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "sync_pending_check_ins",
+            json!({ "userId": null }),
+        );
+        assert_eq!(status, 200);
+        let pending = fixture.db.lock().unwrap().get_all_pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].status, PendingStatus::Pending);
+    }
+
+    #[test]
+    fn settings_and_admin_accounts_cannot_be_changed_remotely() {
+        let fixture = setup(Vec::new());
+        let token = login(&fixture, "admin", "admin123");
+
+        let (status, _) = call(
+            &fixture,
+            &token,
+            "update_settings",
+            json!({ "settings": {} }),
+        );
+        assert_eq!(status, 403);
+        let new_user = |role: &str, admin_id: Option<&str>| json!({ "user": { "username": "carol", "password": "secret1", "role": role, "adminId": admin_id } });
+        let (status, _) = call(&fixture, &token, "create_user", new_user("admin", None));
+        assert_eq!(status, 403);
+        let (status, _) = call(&fixture, &token, "create_user", new_user("user", None));
+        assert_eq!(status, 403);
+
+        let (status, body) = call(
+            &fixture,
+            &token,
+            "create_user",
+            new_user("user", Some(&fixture.admin.id)),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["username"], "carol");
+        assert!(body.get("password").is_none());
+    }
+
+    #[test]
+    fn cross_origin_requests_need_an_allowed_origin() {
+        let preflight = |fixture: &Fixture, origin: &str| {
+            ureq::request("OPTIONS", &format!("{}/api/v1/session", fixture.base))
+                .set("Origin", origin)
+                .set("Access-Control-Request-Method", "POST")
+                .call()
+                .unwrap()
+                .header("access-control-allow-origin")
+                .map(str::to_string)
+        };
+
+        let fixture = setup(Vec::new());
+        assert_eq!(preflight(&fixture, "http://evil.example"), None);
+
+        let fixture = setup(vec!["http://tauri.localhost".to_string()]);
+        assert_eq!(preflight(&fixture, "http://evil.example"), None);
+        assert_eq!(
+            preflight(&fixture, "http://tauri.localhost").as_deref(),
+            Some("http://tauri.localhost")
+        );
+    }
+}
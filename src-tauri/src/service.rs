@@ -0,0 +1,1208 @@
+use crate::auth;
+use crate::checkin;
+use crate::correction;
+use crate::database::Database;
+use crate::export;
+use crate::geo;
+use crate::geojson;
+use crate::holiday;
+use crate::leave;
+use crate::models::*;
+use crate::overtime;
+use crate::report;
+use crate::schedule;
+use crate::summary;
+use crate::track;
+use crate::user_import;
+use crate::webhook;
+use crate::xlsx;
+use chrono::NaiveDate;
+use std::sync::{Arc, Mutex};
+
+/// 桌面端和 HTTP 接口共用的数据库句柄
+pub type AppState = Arc<Mutex<Database>>;
+
+pub fn login(db: &Database, request: LoginRequest) -> LoginResponse {
+    match db.get_user_by_username(&request.username) {
+        Ok(Some(user)) => {
+            if user.disabled {
+                LoginResponse {
+                    success: false,
+                    user: None,
+                    message: Some("账号已停用".to_string()),
+                }
+            } else if user.password == request.password {
+                LoginResponse {
+                    success: true,
+                    user: Some(user.into()),
+                    message: None,
+                }
+            } else {
+                LoginResponse {
+                    success: false,
+                    user: None,
+                    message: Some("密码错误".to_string()),
+                }
+            }
+        }
+        Ok(None) => {
+            LoginResponse {
+                success: false,
+                user: None,
+                message: Some("用户不存在".to_string()),
+            }
+        }
+        Err(e) => {
+            LoginResponse {
+                success: false,
+                user: None,
+                message: Some(format!("登录失败: {}", e)),
+            }
+        }
+    }
+}
+
+pub fn get_all_users(db: &Database) -> Vec<UserInfo> {
+    db.get_all_users()
+        .unwrap_or_default()
+        .into_iter()
+        .map(UserInfo::from)
+        .collect()
+}
+
+pub fn get_users_by_admin(db: &Database, admin_id: String) -> Vec<UserInfo> {
+    db.get_all_users()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|u| u.admin_id.as_ref() == Some(&admin_id))
+        .map(UserInfo::from)
+        .collect()
+}
+
+pub fn create_user(db: &Database, user: CreateUserRequest) -> Result<UserInfo, String> {
+    match db.get_user_by_username(&user.username) {
+        Ok(Some(_)) => Err("用户名已存在".to_string()),
+        Ok(None) => {
+            let new_user = User::new(
+                user.username.clone(),
+                user.password.clone(),
+                user.role.clone(),
+                user.admin_id.clone(),
+            );
+            db.save_user(&new_user).map_err(|e| e.to_string())?;
+            webhook::user_created(db, &new_user);
+            Ok(new_user.into())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 从 CSV 批量导入员工，`dry_run` 为 true 时只校验不写入
+pub fn import_users(
+    db: &Database,
+    admin_id: String,
+    content: String,
+    dry_run: bool,
+) -> Result<UserImportReport, String> {
+    user_import::import_users(db, &admin_id, &content, dry_run)
+}
+
+pub fn delete_user(db: &Database, user_id: String) -> Result<(), String> {
+    for mut group in db.get_all_user_groups().map_err(|e| e.to_string())? {
+        if group.user_ids.contains(&user_id) {
+            group.user_ids.retain(|id| *id != user_id);
+            db.save_user_group(&group).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    db.delete_user(&user_id).map_err(|e| e.to_string())
+}
+
+pub fn update_user_location(
+    db: &Database,
+    user_id: String,
+    location_id: String,
+) -> Result<UserInfo, String> {
+    let mut user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    
+    // 只能分配员工所属管理员名下的位置
+    db.get_location(&location_id)
+        .map_err(|e| e.to_string())?
+        .filter(|l| user.admin_id.as_ref() == Some(&l.admin_id))
+        .ok_or_else(|| "位置不存在".to_string())?;
+    
+    user.location_id = Some(location_id);
+    
+    db.save_user(&user).map_err(|e| e.to_string())?;
+    Ok(user.into())
+}
+
+pub fn get_user_location(db: &Database, user_id: String) -> Option<Location> {
+    let user = match db.get_user(&user_id) {
+        Ok(Some(u)) => u,
+        _ => return None,
+    };
+    
+    match user.location_id {
+        Some(location_id) => db.get_location(&location_id).ok().flatten(),
+        None => None,
+    }
+}
+
+pub fn get_user_locations(db: &Database, user_id: String) -> Vec<Location> {
+    let user = match db.get_user(&user_id) {
+        Ok(Some(u)) => u,
+        _ => return Vec::new(),
+    };
+    
+    let today = checkin::user_today(db, &user);
+    checkin::resolve_user_locations(db, &user, today)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(location, _)| location)
+        .collect()
+}
+
+pub fn update_user_location_assignments(
+    db: &Database,
+    user_id: String,
+    assignments: Vec<LocationAssignment>,
+) -> Result<UserInfo, String> {
+    let mut user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    
+    for assignment in &assignments {
+        if let (Some(from), Some(until)) = (assignment.valid_from, assignment.valid_until) {
+            if until < from {
+                return Err("结束日期不能早于开始日期".to_string());
+            }
+        }
+        match &assignment.target {
+            AssignmentTarget::Location(id) => {
+                db.get_location(id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| "位置不存在".to_string())?;
+            }
+            AssignmentTarget::Group(id) => {
+                db.get_location_group(id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| "位置组不存在".to_string())?;
+            }
+        }
+    }
+    
+    user.location_assignments = assignments;
+    
+    db.save_user(&user).map_err(|e| e.to_string())?;
+    Ok(user.into())
+}
+
+pub fn get_location_groups_by_admin(db: &Database, admin_id: String) -> Vec<LocationGroup> {
+    db.get_all_location_groups()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|g| g.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_location_group(
+    db: &Database,
+    group: CreateLocationGroupRequest,
+) -> Result<LocationGroup, String> {
+    for location_id in &group.location_ids {
+        db.get_location(location_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "位置不存在".to_string())?;
+    }
+    
+    let new_group = LocationGroup::new(group.name, group.location_ids, group.admin_id);
+    db.save_location_group(&new_group).map_err(|e| e.to_string())?;
+    Ok(new_group)
+}
+
+pub fn update_location_group(
+    db: &Database,
+    group_id: String,
+    group: UpdateLocationGroupRequest,
+) -> Result<LocationGroup, String> {
+    let mut existing_group = db.get_location_group(&group_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "位置组不存在".to_string())?;
+    
+    if let Some(name) = group.name {
+        existing_group.name = name;
+    }
+    if let Some(location_ids) = group.location_ids {
+        for location_id in &location_ids {
+            db.get_location(location_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "位置不存在".to_string())?;
+        }
+        existing_group.location_ids = location_ids;
+    }
+    
+    db.save_location_group(&existing_group).map_err(|e| e.to_string())?;
+    Ok(existing_group)
+}
+
+pub fn delete_location_group(db: &Database, group_id: String) -> Result<(), String> {
+    let target = AssignmentTarget::Group(group_id.clone());
+    for mut user in db.get_all_users().map_err(|e| e.to_string())? {
+        if user.location_assignments.iter().any(|a| a.target == target) {
+            user.location_assignments.retain(|a| a.target != target);
+            db.save_user(&user).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    db.delete_location_group(&group_id).map_err(|e| e.to_string())
+}
+
+pub fn get_user_groups_by_admin(db: &Database, admin_id: String) -> Vec<UserGroup> {
+    db.get_all_user_groups()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|g| g.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_user_group(db: &Database, group: CreateUserGroupRequest) -> Result<UserGroup, String> {
+    for user_id in &group.user_ids {
+        db.get_user(user_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "用户不存在".to_string())?;
+    }
+    
+    let new_group = UserGroup::new(group.name, group.user_ids, group.admin_id);
+    db.save_user_group(&new_group).map_err(|e| e.to_string())?;
+    Ok(new_group)
+}
+
+pub fn update_user_group(
+    db: &Database,
+    group_id: String,
+    group: UpdateUserGroupRequest,
+) -> Result<UserGroup, String> {
+    let mut existing_group = db.get_user_group(&group_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "员工分组不存在".to_string())?;
+    
+    if let Some(name) = group.name {
+        existing_group.name = name;
+    }
+    if let Some(user_ids) = group.user_ids {
+        for user_id in &user_ids {
+            db.get_user(user_id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "用户不存在".to_string())?;
+        }
+        existing_group.user_ids = user_ids;
+    }
+    
+    db.save_user_group(&existing_group).map_err(|e| e.to_string())?;
+    Ok(existing_group)
+}
+
+pub fn delete_user_group(db: &Database, group_id: String) -> Result<(), String> {
+    db.delete_user_group(&group_id).map_err(|e| e.to_string())
+}
+
+pub fn get_holiday_calendars_by_admin(db: &Database, admin_id: String) -> Vec<HolidayCalendar> {
+    db.get_all_calendars()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_holiday_calendar(
+    db: &Database,
+    calendar: CreateHolidayCalendarRequest,
+) -> Result<HolidayCalendar, String> {
+    let mut new_calendar = HolidayCalendar::new(calendar.name, Vec::new(), calendar.admin_id);
+    new_calendar.merge(calendar.days);
+    holiday::validate_calendar(&new_calendar)?;
+    db.save_calendar(&new_calendar).map_err(|e| e.to_string())?;
+    Ok(new_calendar)
+}
+
+pub fn update_holiday_calendar(
+    db: &Database,
+    calendar_id: String,
+    calendar: UpdateHolidayCalendarRequest,
+) -> Result<HolidayCalendar, String> {
+    let mut existing = db.get_calendar(&calendar_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "节假日日历不存在".to_string())?;
+    
+    if let Some(name) = calendar.name {
+        existing.name = name;
+    }
+    if let Some(mut days) = calendar.days {
+        days.sort_by_key(|d| d.date);
+        existing.days = days;
+    }
+    
+    holiday::validate_calendar(&existing)?;
+    db.save_calendar(&existing).map_err(|e| e.to_string())?;
+    Ok(existing)
+}
+
+pub fn delete_holiday_calendar(db: &Database, calendar_id: String) -> Result<(), String> {
+    for mut location in db.get_all_locations().map_err(|e| e.to_string())? {
+        if location.calendar_id.as_ref() == Some(&calendar_id) {
+            location.calendar_id = None;
+            db.save_location(&location).map_err(|e| e.to_string())?;
+        }
+    }
+    for mut group in db.get_all_location_groups().map_err(|e| e.to_string())? {
+        if group.calendar_id.as_ref() == Some(&calendar_id) {
+            group.calendar_id = None;
+            db.save_location_group(&group).map_err(|e| e.to_string())?;
+        }
+    }
+    for mut group in db.get_all_user_groups().map_err(|e| e.to_string())? {
+        if group.calendar_id.as_ref() == Some(&calendar_id) {
+            group.calendar_id = None;
+            db.save_user_group(&group).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    db.delete_calendar(&calendar_id).map_err(|e| e.to_string())
+}
+
+/// 导入 iCalendar 文件内容，与已有日期合并
+pub fn import_holiday_calendar_ics(
+    db: &Database,
+    calendar_id: String,
+    content: String,
+) -> Result<HolidayCalendar, String> {
+    let mut calendar = db.get_calendar(&calendar_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "节假日日历不存在".to_string())?;
+    
+    calendar.merge(holiday::parse_ics(&content)?);
+    db.save_calendar(&calendar).map_err(|e| e.to_string())?;
+    Ok(calendar)
+}
+
+pub fn import_cn_statutory_holidays(
+    db: &Database,
+    calendar_id: String,
+    year: i32,
+) -> Result<HolidayCalendar, String> {
+    let mut calendar = db.get_calendar(&calendar_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "节假日日历不存在".to_string())?;
+    
+    calendar.merge(holiday::cn_statutory(year)?);
+    db.save_calendar(&calendar).map_err(|e| e.to_string())?;
+    Ok(calendar)
+}
+
+/// 为位置、位置组或员工分组设置节假日日历，`calendar_id` 为空时取消
+pub fn set_holiday_calendar(
+    db: &Database,
+    target: CalendarTarget,
+    calendar_id: Option<String>,
+) -> Result<(), String> {
+    if let Some(id) = &calendar_id {
+        db.get_calendar(id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "节假日日历不存在".to_string())?;
+    }
+    
+    match target {
+        CalendarTarget::Location(id) => {
+            let mut location = db.get_location(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "位置不存在".to_string())?;
+            location.calendar_id = calendar_id;
+            db.save_location(&location).map_err(|e| e.to_string())
+        }
+        CalendarTarget::Group(id) => {
+            let mut group = db.get_location_group(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "位置组不存在".to_string())?;
+            group.calendar_id = calendar_id;
+            db.save_location_group(&group).map_err(|e| e.to_string())
+        }
+        CalendarTarget::UserGroup(id) => {
+            let mut group = db.get_user_group(&id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "员工分组不存在".to_string())?;
+            group.calendar_id = calendar_id;
+            db.save_user_group(&group).map_err(|e| e.to_string())
+        }
+    }
+}
+
+pub fn get_all_locations(db: &Database) -> Vec<Location> {
+    db.get_all_locations().unwrap_or_default()
+}
+
+pub fn get_locations_by_admin(db: &Database, admin_id: String) -> Vec<Location> {
+    db.get_all_locations()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|l| l.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_location(db: &Database, location: CreateLocationRequest) -> Result<Location, String> {
+    schedule::validate_windows(&location.check_in_windows)?;
+    if let Some(geofence) = &location.geofence {
+        geo::validate_geofence(geofence)?;
+    }
+    if location.buffer < 0.0 {
+        return Err("缓冲距离不能为负数".to_string());
+    }
+    
+    let mut new_location = Location::new(
+        location.name.clone(),
+        location.latitude,
+        location.longitude,
+        location.radius,
+        location.admin_id.clone(),
+    );
+    new_location.check_in_windows = location.check_in_windows;
+    new_location.window_policy = location.window_policy;
+    new_location.geofence = location.geofence;
+    new_location.buffer = location.buffer;
+    new_location.timezone = location.timezone;
+    db.save_location(&new_location).map_err(|e| e.to_string())?;
+    Ok(new_location)
+}
+
+/// 把管理员名下的位置导出为 GeoJSON FeatureCollection
+pub fn export_locations_geojson(
+    db: &Database,
+    admin_id: String,
+    path: String,
+) -> Result<usize, String> {
+    geojson::export_locations(db, &admin_id, &path)
+}
+
+/// 从 GeoJSON 导入位置，`dry_run` 为 true 时只返回差异不写入
+pub fn import_locations_geojson(
+    db: &Database,
+    admin_id: String,
+    content: String,
+    mode: GeoJsonImportMode,
+    dry_run: bool,
+) -> Result<LocationImportReport, String> {
+    geojson::import_locations(db, &admin_id, &content, mode, dry_run)
+}
+
+pub fn update_location(
+    db: &Database,
+    location_id: String,
+    location: UpdateLocationRequest,
+) -> Result<Location, String> {
+    let mut existing_location = db.get_location(&location_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "位置不存在".to_string())?;
+    
+    if let Some(name) = location.name {
+        existing_location.name = name;
+    }
+    if let Some(latitude) = location.latitude {
+        existing_location.latitude = latitude;
+    }
+    if let Some(longitude) = location.longitude {
+        existing_location.longitude = longitude;
+    }
+    if let Some(radius) = location.radius {
+        existing_location.radius = radius;
+    }
+    if let Some(check_in_windows) = location.check_in_windows {
+        schedule::validate_windows(&check_in_windows)?;
+        existing_location.check_in_windows = check_in_windows;
+    }
+    if let Some(window_policy) = location.window_policy {
+        existing_location.window_policy = window_policy;
+    }
+    if let Some(geofence) = location.geofence {
+        geo::validate_geofence(&geofence)?;
+        existing_location.geofence = Some(geofence);
+    }
+    if let Some(buffer) = location.buffer {
+        if buffer < 0.0 {
+            return Err("缓冲距离不能为负数".to_string());
+        }
+        existing_location.buffer = buffer;
+    }
+    if let Some(timezone) = location.timezone {
+        existing_location.timezone = timezone;
+    }
+    
+    db.save_location(&existing_location).map_err(|e| e.to_string())?;
+    Ok(existing_location)
+}
+
+pub fn delete_location(db: &Database, location_id: String) -> Result<(), String> {
+    db.delete_location(&location_id).map_err(|e| e.to_string())
+}
+
+pub fn get_attendance_records(db: &Database, user_id: Option<String>) -> Vec<AttendanceRecord> {
+    if let Some(uid) = user_id {
+        db.get_records_by_user(&uid).unwrap_or_default()
+    } else {
+        db.get_all_records().unwrap_or_default()
+    }
+}
+
+pub fn get_attendance_records_by_admin(db: &Database, admin_id: String) -> Vec<AttendanceRecord> {
+    let users: Vec<String> = db
+        .get_all_users()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|u| u.admin_id.as_ref() == Some(&admin_id))
+        .map(|u| u.id)
+        .collect();
+    
+    db.get_all_records()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| users.contains(&r.user_id))
+        .collect()
+}
+
+pub fn query_attendance_records(
+    db: &Database,
+    query: RecordQuery,
+) -> Result<Vec<AttendanceRecord>, String> {
+    export::query_records(db, &query)
+}
+
+/// 导出筛选后的打卡记录，返回导出的行数
+pub fn export_records_csv(
+    db: &Database,
+    query: RecordQuery,
+    columns: Vec<RecordColumn>,
+    path: String,
+) -> Result<usize, String> {
+    let records = export::query_records(db, &query)?;
+    let table = export::records_table(db, &records, &columns)?;
+    export::write_csv(&table, &path)?;
+    Ok(records.len())
+}
+
+pub fn export_monthly_report_csv(
+    db: &Database,
+    admin_id: String,
+    month: String,
+    columns: Vec<ReportColumn>,
+    path: String,
+) -> Result<usize, String> {
+    let report = report::monthly_report(db, &admin_id, &month)?;
+    export::write_csv(&export::report_table(&report, &columns), &path)?;
+    Ok(report.rows.len())
+}
+
+/// 导出用户打卡坐标及围栏，供在地图工具中核查
+pub fn export_attendance_track(
+    db: &Database,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    format: TrackFormat,
+    path: String,
+) -> Result<usize, String> {
+    track::export_track(db, &user_id, start_date, end_date, format, &path)
+}
+
+/// 导出包含汇总表和明细表的 Excel 月报
+pub fn export_monthly_report_xlsx(
+    db: &Database,
+    admin_id: String,
+    month: String,
+    columns: Vec<ReportColumn>,
+    path: String,
+) -> Result<usize, String> {
+    let team = report::team_month(db, &admin_id, &month)?;
+    let report = report::summarize(&admin_id, &team);
+    xlsx::write_monthly_report(db, &report, &team, &columns, &path)?;
+    Ok(report.rows.len())
+}
+
+pub fn get_suspicious_records(
+    db: &Database,
+    admin_id: String,
+    min_score: Option<u32>,
+) -> Vec<AttendanceRecord> {
+    let users: Vec<String> = db
+        .get_all_users()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|u| u.admin_id.as_ref() == Some(&admin_id))
+        .map(|u| u.id)
+        .collect();
+    
+    let mut records: Vec<AttendanceRecord> = db
+        .get_all_records()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| users.contains(&r.user_id))
+        .filter(|r| match min_score {
+            Some(score) => r.risk_score >= score,
+            None => r.suspicious,
+        })
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    records
+}
+
+pub fn check_in(db: &Database, request: CheckInRequest) -> CheckInResponse {
+    checkin::process_check_in(db, &request, chrono::Utc::now().timestamp(), RecordSource::Online)
+}
+
+pub fn queue_offline_check_ins(
+    db: &Database,
+    check_ins: Vec<OfflineCheckIn>,
+) -> Result<Vec<PendingCheckIn>, String> {
+    checkin::queue_offline(db, check_ins).map_err(|e| e.to_string())
+}
+
+pub fn sync_pending_check_ins(
+    db: &Database,
+    user_id: Option<String>,
+) -> Result<Vec<PendingCheckIn>, String> {
+    checkin::sync_pending(db, user_id.as_deref(), chrono::Utc::now().timestamp())
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_pending_check_ins(db: &Database, user_id: String) -> Vec<PendingCheckIn> {
+    let mut pending: Vec<PendingCheckIn> = db
+        .get_all_pending()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|p| p.user_id == user_id)
+        .collect();
+    pending.sort_by_key(|p| std::cmp::Reverse(p.captured_at));
+    pending
+}
+
+pub fn submit_correction_request(
+    db: &Database,
+    request: CreateCorrectionRequest,
+) -> Result<CorrectionRequest, String> {
+    correction::submit(db, request)
+}
+
+pub fn get_correction_requests_by_user(db: &Database, user_id: String) -> Vec<CorrectionRequest> {
+    let mut corrections: Vec<CorrectionRequest> = db
+        .get_all_corrections()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.user_id == user_id)
+        .collect();
+    corrections.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    corrections
+}
+
+pub fn get_correction_requests_by_admin(
+    db: &Database,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<CorrectionRequest> {
+    let mut corrections: Vec<CorrectionRequest> = db
+        .get_all_corrections()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.admin_id.as_ref() == Some(&admin_id))
+        .filter(|c| status.is_none_or(|s| c.status == s))
+        .collect();
+    corrections.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    corrections
+}
+
+pub fn approve_correction_request(
+    db: &Database,
+    correction_id: String,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    correction::approve(db, &correction_id, review)
+}
+
+pub fn reject_correction_request(
+    db: &Database,
+    correction_id: String,
+    review: ReviewRequest,
+) -> Result<CorrectionRequest, String> {
+    correction::reject(db, &correction_id, review)
+}
+
+pub fn get_leave_types_by_admin(db: &Database, admin_id: String) -> Vec<LeaveType> {
+    db.get_all_leave_types()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|t| t.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_leave_type(
+    db: &Database,
+    leave_type: CreateLeaveTypeRequest,
+) -> Result<LeaveType, String> {
+    let new_leave_type = LeaveType::new(
+        leave_type.name,
+        leave_type.annual_days,
+        leave_type.accrual,
+        leave_type.paid,
+        leave_type.admin_id,
+    );
+    leave::validate_leave_type(&new_leave_type)?;
+    db.save_leave_type(&new_leave_type).map_err(|e| e.to_string())?;
+    Ok(new_leave_type)
+}
+
+pub fn update_leave_type(
+    db: &Database,
+    leave_type_id: String,
+    leave_type: UpdateLeaveTypeRequest,
+) -> Result<LeaveType, String> {
+    let mut existing = db.get_leave_type(&leave_type_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "假期类型不存在".to_string())?;
+    
+    if let Some(name) = leave_type.name {
+        existing.name = name;
+    }
+    if let Some(annual_days) = leave_type.annual_days {
+        existing.annual_days = annual_days;
+    }
+    if let Some(accrual) = leave_type.accrual {
+        existing.accrual = accrual;
+    }
+    if let Some(paid) = leave_type.paid {
+        existing.paid = paid;
+    }
+    
+    leave::validate_leave_type(&existing)?;
+    db.save_leave_type(&existing).map_err(|e| e.to_string())?;
+    Ok(existing)
+}
+
+pub fn delete_leave_type(db: &Database, leave_type_id: String) -> Result<(), String> {
+    let in_use = db
+        .get_all_leave_requests()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .any(|r| r.leave_type_id == leave_type_id);
+    if in_use {
+        return Err("该假期类型已有请假记录，无法删除".to_string());
+    }
+    
+    db.delete_leave_type(&leave_type_id).map_err(|e| e.to_string())
+}
+
+pub fn submit_leave_request(
+    db: &Database,
+    request: CreateLeaveRequest,
+) -> Result<LeaveRequest, String> {
+    leave::submit(db, request)
+}
+
+pub fn get_leave_requests_by_user(db: &Database, user_id: String) -> Vec<LeaveRequest> {
+    let mut requests = db.get_leave_requests_by_user(&user_id).unwrap_or_default();
+    requests.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    requests
+}
+
+pub fn get_leave_requests_by_admin(
+    db: &Database,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<LeaveRequest> {
+    let mut requests: Vec<LeaveRequest> = db
+        .get_all_leave_requests()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.admin_id.as_ref() == Some(&admin_id))
+        .filter(|r| status.is_none_or(|s| r.status == s))
+        .collect();
+    requests.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    requests
+}
+
+pub fn approve_leave_request(
+    db: &Database,
+    leave_id: String,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    leave::approve(db, &leave_id, review)
+}
+
+pub fn reject_leave_request(
+    db: &Database,
+    leave_id: String,
+    review: ReviewRequest,
+) -> Result<LeaveRequest, String> {
+    leave::reject(db, &leave_id, review)
+}
+
+pub fn get_leave_balances(
+    db: &Database,
+    user_id: String,
+    year: i32,
+) -> Result<Vec<LeaveBalance>, String> {
+    let user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    let today = checkin::user_today(db, &user);
+    let admin_id = user.admin_id.unwrap_or_default();
+    
+    db.get_all_leave_types()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .filter(|t| t.admin_id == admin_id)
+        .map(|t| leave::balance(db, &user_id, t, year, today))
+        .collect()
+}
+
+pub fn set_leave_adjustment(
+    db: &Database,
+    user_id: String,
+    leave_type_id: String,
+    year: i32,
+    days: f64,
+) -> Result<(), String> {
+    db.save_leave_adjustment(&user_id, &leave_type_id, year, days)
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_overtime_by_user(db: &Database, user_id: String) -> Vec<OvertimeRecord> {
+    let mut overtime = db.get_overtime_by_user(&user_id).unwrap_or_default();
+    overtime.sort_by_key(|o| std::cmp::Reverse(o.date));
+    overtime
+}
+
+pub fn get_overtime_by_admin(
+    db: &Database,
+    admin_id: String,
+    status: Option<ApprovalStatus>,
+) -> Vec<OvertimeRecord> {
+    let mut overtime: Vec<OvertimeRecord> = db
+        .get_all_overtime()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|o| o.admin_id.as_ref() == Some(&admin_id))
+        .filter(|o| status.is_none_or(|s| o.status == s))
+        .collect();
+    overtime.sort_by_key(|o| std::cmp::Reverse(o.date));
+    overtime
+}
+
+/// 按当前打卡记录和加班规则重新计算，已审批的加班不受影响
+pub fn recalculate_overtime(
+    db: &Database,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<OvertimeRecord>, String> {
+    if end_date < start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err("查询范围不能超过一年".to_string());
+    }
+    
+    let user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    overtime::recalculate(db, &user, start_date, end_date)
+}
+
+pub fn approve_overtime(
+    db: &Database,
+    overtime_id: String,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    overtime::approve(db, &overtime_id, review)
+}
+
+pub fn reject_overtime(
+    db: &Database,
+    overtime_id: String,
+    review: ReviewRequest,
+) -> Result<OvertimeRecord, String> {
+    overtime::reject(db, &overtime_id, review)
+}
+
+pub fn get_settings(db: &Database) -> Result<Settings, String> {
+    db.get_settings().map_err(|e| e.to_string())
+}
+
+pub fn update_settings(db: &Database, settings: Settings) -> Result<Settings, String> {
+    if settings.accuracy.max_accuracy.is_some_and(|max| max <= 0.0) {
+        return Err("定位精度上限必须大于 0".to_string());
+    }
+    if !(0.0..=1.0).contains(&settings.accuracy.min_overlap) {
+        return Err("重叠比例必须在 0 到 1 之间".to_string());
+    }
+    if settings.plausibility.max_speed_kmh <= 0.0 {
+        return Err("最大移动速度必须大于 0".to_string());
+    }
+    if settings.plausibility.flag_threshold > 100 {
+        return Err("风险阈值不能超过 100".to_string());
+    }
+    if settings.punch.min_interval_seconds < 0 {
+        return Err("最短打卡间隔不能为负数".to_string());
+    }
+    if settings.offline.max_age_hours <= 0 {
+        return Err("离线打卡有效期必须大于 0".to_string());
+    }
+    overtime::validate_policy(&settings.overtime)?;
+    if settings.server.address.parse::<std::net::SocketAddr>().is_err() {
+        return Err("HTTP 接口监听地址格式应为 IP:端口".to_string());
+    }
+    if settings.server.session_hours <= 0 {
+        return Err("会话有效时长必须大于 0".to_string());
+    }
+    for origin in &settings.server.allowed_origins {
+        if !origin.contains("://") || origin.ends_with('/') || axum::http::HeaderValue::from_str(origin).is_err() {
+            return Err(format!("跨域来源格式应为 协议://主机[:端口]: {}", origin));
+        }
+    }
+    
+    db.save_settings(&settings).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+pub fn get_api_keys_by_admin(db: &Database, admin_id: String) -> Vec<ApiKey> {
+    db.get_all_api_keys()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|k| k.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_api_key(db: &Database, admin_id: String, name: String) -> Result<CreatedApiKey, String> {
+    auth::create_api_key(db, &admin_id, &name)
+}
+
+pub fn delete_api_key(db: &Database, admin_id: String, key_id: String) -> Result<(), String> {
+    let api_key = db.get_api_key(&key_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "密钥不存在".to_string())?;
+    if api_key.admin_id != admin_id {
+        return Err("无权删除该密钥".to_string());
+    }
+    db.delete_api_key(&key_id).map_err(|e| e.to_string())
+}
+
+pub fn get_webhooks_by_admin(db: &Database, admin_id: String) -> Vec<Webhook> {
+    db.get_all_webhooks()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|w| w.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_webhook(db: &Database, webhook: CreateWebhookRequest) -> Result<Webhook, String> {
+    webhook::create(db, webhook)
+}
+
+pub fn update_webhook(
+    db: &Database,
+    admin_id: String,
+    webhook_id: String,
+    webhook: UpdateWebhookRequest,
+) -> Result<Webhook, String> {
+    webhook::update(db, &admin_id, &webhook_id, webhook)
+}
+
+pub fn delete_webhook(db: &Database, admin_id: String, webhook_id: String) -> Result<(), String> {
+    webhook::delete(db, &admin_id, &webhook_id)
+}
+
+/// 发送一条测试事件，结果在投递记录中查看
+pub fn test_webhook(db: &Database, admin_id: String, webhook_id: String) -> Result<(), String> {
+    webhook::ping(db, &admin_id, &webhook_id)
+}
+
+pub fn get_webhook_deliveries(
+    db: &Database,
+    admin_id: String,
+    webhook_id: String,
+) -> Result<Vec<WebhookDeliveryLog>, String> {
+    webhook::delivery_logs(db, &admin_id, &webhook_id)
+}
+
+pub fn get_shifts_by_admin(db: &Database, admin_id: String) -> Vec<Shift> {
+    db.get_all_shifts()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|s| s.admin_id == admin_id)
+        .collect()
+}
+
+pub fn create_shift(db: &Database, shift: CreateShiftRequest) -> Result<Shift, String> {
+    let new_shift = Shift::new(
+        shift.name,
+        shift.start_time,
+        shift.end_time,
+        shift.grace_minutes,
+        shift.weekdays,
+        shift.admin_id,
+    );
+    schedule::validate_shift(&new_shift)?;
+    db.save_shift(&new_shift).map_err(|e| e.to_string())?;
+    Ok(new_shift)
+}
+
+pub fn update_shift(
+    db: &Database,
+    shift_id: String,
+    shift: UpdateShiftRequest,
+) -> Result<Shift, String> {
+    let mut existing_shift = db.get_shift(&shift_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "班次不存在".to_string())?;
+    
+    if let Some(name) = shift.name {
+        existing_shift.name = name;
+    }
+    if let Some(start_time) = shift.start_time {
+        existing_shift.start_time = start_time;
+    }
+    if let Some(end_time) = shift.end_time {
+        existing_shift.end_time = end_time;
+    }
+    if let Some(grace_minutes) = shift.grace_minutes {
+        existing_shift.grace_minutes = grace_minutes;
+    }
+    if let Some(weekdays) = shift.weekdays {
+        existing_shift.weekdays = weekdays;
+    }
+    
+    schedule::validate_shift(&existing_shift)?;
+    db.save_shift(&existing_shift).map_err(|e| e.to_string())?;
+    Ok(existing_shift)
+}
+
+pub fn delete_shift(db: &Database, shift_id: String) -> Result<(), String> {
+    for mut user in db.get_all_users().map_err(|e| e.to_string())? {
+        if user.shift_id.as_ref() == Some(&shift_id) {
+            user.shift_id = None;
+            db.save_user(&user).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    db.delete_shift(&shift_id).map_err(|e| e.to_string())
+}
+
+pub fn update_user_shift(
+    db: &Database,
+    user_id: String,
+    shift_id: Option<String>,
+) -> Result<UserInfo, String> {
+    let mut user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    
+    if let Some(id) = &shift_id {
+        db.get_shift(id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "班次不存在".to_string())?;
+    }
+    
+    user.shift_id = shift_id;
+    
+    db.save_user(&user).map_err(|e| e.to_string())?;
+    Ok(user.into())
+}
+
+pub fn get_daily_attendance(
+    db: &Database,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailyAttendance>, String> {
+    if end_date < start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err("查询范围不能超过一年".to_string());
+    }
+    
+    let user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    let shift = match &user.shift_id {
+        Some(id) => db.get_shift(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let records = db.get_records_by_user(&user_id).map_err(|e| e.to_string())?;
+    let leaves: Vec<LeaveRequest> = db
+        .get_leave_requests_by_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|l| l.status == ApprovalStatus::Approved)
+        .collect();
+    let overtime: Vec<OvertimeRecord> = db
+        .get_overtime_by_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|o| o.status == ApprovalStatus::Approved)
+        .collect();
+    
+    start_date
+        .iter_days()
+        .take_while(|date| *date <= end_date)
+        .map(|date| {
+            let calendar = holiday::user_calendar(db, &user, date).map_err(|e| e.to_string())?;
+            Ok(schedule::derive_day(&user_id, date, shift.as_ref(), calendar.as_ref(), &records, &leaves, &overtime))
+        })
+        .collect()
+}
+
+pub fn get_daily_summaries(
+    db: &Database,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    if end_date < start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err("查询范围不能超过一年".to_string());
+    }
+    
+    let user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    summary::summaries(db, &user, start_date, end_date)
+}
+
+pub fn recompute_daily_summaries(
+    db: &Database,
+    user_id: String,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    if end_date < start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err("查询范围不能超过一年".to_string());
+    }
+    
+    let user = db.get_user(&user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    summary::recompute(db, &user, start_date, end_date)
+}
+
+/// `month` 格式为 `YYYY-MM`
+pub fn generate_monthly_report(
+    db: &Database,
+    admin_id: String,
+    month: String,
+) -> Result<MonthlyReport, String> {
+    report::monthly_report(db, &admin_id, &month)
+}
@@ -0,0 +1,295 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::holiday;
+use crate::models::{ApprovalStatus, AttendanceStatus, DailySummary, User, UserRole};
+use crate::schedule;
+use chrono::{Duration, NaiveDate};
+use std::sync::Mutex;
+
+/// 计算并保存指定日期的汇总，各日期共用一次加载的记录
+fn build(db: &Database, user: &User, dates: &[NaiveDate]) -> Result<Vec<DailySummary>, String> {
+    if dates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let shift = match &user.shift_id {
+        Some(id) => db.get_shift(id).map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let records = db
+        .get_records_by_user(&user.id)
+        .map_err(|e| e.to_string())?;
+    let leaves: Vec<_> = db
+        .get_leave_requests_by_user(&user.id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|l| l.status == ApprovalStatus::Approved)
+        .collect();
+    let overtime: Vec<_> = db
+        .get_overtime_by_user(&user.id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|o| o.status == ApprovalStatus::Approved)
+        .collect();
+    let now = chrono::Utc::now().timestamp();
+
+    let mut summaries = Vec::new();
+    for &date in dates {
+        let calendar = holiday::user_calendar(db, user, date).map_err(|e| e.to_string())?;
+        let day = schedule::derive_day(
+            &user.id,
+            date,
+            shift.as_ref(),
+            calendar.as_ref(),
+            &records,
+            &leaves,
+            &overtime,
+        );
+        let failed_attempts = records
+            .iter()
+            .filter(|r| r.status != AttendanceStatus::Success)
+            .filter(|r| schedule::work_date(r, shift.as_ref()) == date)
+            .count() as u32;
+        let worked_minutes = match (day.first_in, day.last_out) {
+            (Some(first_in), Some(last_out)) if last_out > first_in => (last_out - first_in) / 60,
+            _ => 0,
+        };
+
+        let summary = DailySummary {
+            user_id: day.user_id,
+            date,
+            status: day.status,
+            first_in: day.first_in,
+            last_out: day.last_out,
+            worked_minutes,
+            late_minutes: day.late_minutes,
+            early_leave_minutes: day.early_leave_minutes,
+            leave_days: day.leave_days,
+            overtime_minutes: day.overtime_minutes,
+            failed_attempts,
+            scheduled: shift.is_some()
+                && schedule::is_working_day(shift.as_ref(), calendar.as_ref(), date),
+            computed_at: now,
+        };
+        db.save_summary(&summary).map_err(|e| e.to_string())?;
+        summaries.push(summary);
+    }
+    Ok(summaries)
+}
+
+/// 读取日期范围内的汇总，缺失或已失效的日期会重新计算
+pub fn summaries(
+    db: &Database,
+    user: &User,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    let mut summaries = db
+        .get_summaries(&user.id, start_date, end_date)
+        .map_err(|e| e.to_string())?;
+    let missing: Vec<NaiveDate> = start_date
+        .iter_days()
+        .take_while(|d| *d <= end_date)
+        .filter(|d| !summaries.iter().any(|s| s.date == *d))
+        .collect();
+
+    summaries.extend(build(db, user, &missing)?);
+    summaries.sort_by_key(|s| s.date);
+    Ok(summaries)
+}
+
+/// 丢弃已有的汇总并重新计算
+pub fn recompute(
+    db: &Database,
+    user: &User,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<DailySummary>, String> {
+    db.invalidate_summaries(&user.id, start_date, end_date)
+        .map_err(|e| e.to_string())?;
+    summaries(db, user, start_date, end_date)
+}
+
+/// 补齐单个员工最近 `days` 天缺失的汇总，返回新计算的条数
+fn refresh_user(db: &Database, user: &User, days: i64) -> Result<usize, String> {
+    let today = checkin::user_today(db, user);
+    let start_date = today - Duration::days(days - 1);
+    let existing = db
+        .get_summaries(&user.id, start_date, today)
+        .map_err(|e| e.to_string())?;
+    let missing: Vec<NaiveDate> = start_date
+        .iter_days()
+        .take_while(|d| *d <= today)
+        .filter(|d| !existing.iter().any(|s| s.date == *d))
+        .collect();
+    Ok(build(db, user, &missing)?.len())
+}
+
+/// 后台任务：补齐所有员工最近 `days` 天的汇总，返回新计算的条数。
+/// 每个员工单独加锁，避免长时间阻塞其他请求
+pub fn refresh_recent(db: &Mutex<Database>, days: i64) -> Result<usize, String> {
+    let user_ids: Vec<String> = db
+        .lock()
+        .unwrap()
+        .get_all_users()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|u| u.role == UserRole::User)
+        .map(|u| u.id)
+        .collect();
+
+    let mut computed = 0;
+    for user_id in user_ids {
+        let db = db.lock().unwrap();
+        // 加锁间隙中可能已被删除
+        if let Some(user) = db.get_user(&user_id).map_err(|e| e.to_string())? {
+            computed += refresh_user(&db, &user, days)?;
+        }
+    }
+    Ok(computed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DayStatus, HolidayCalendar, PunchType, UserGroup};
+    use crate::test_support::{self, at, date, Fixture};
+
+    fn setup() -> Fixture {
+        let mut fixture = test_support::setup();
+        let shift = test_support::day_shift(&fixture.db, &fixture.admin, vec![1, 2, 3, 4, 5, 6, 7]);
+        fixture.user.shift_id = Some(shift.id);
+        fixture.db.save_user(&fixture.user).unwrap();
+        fixture
+    }
+
+    fn punch(
+        fixture: &Fixture,
+        punch_type: PunchType,
+        status: AttendanceStatus,
+        hour: u32,
+        minute: u32,
+    ) {
+        test_support::punch(
+            &fixture.db,
+            &fixture.user,
+            &fixture.location,
+            punch_type,
+            status,
+            at(14, hour, minute),
+        );
+    }
+
+    #[test]
+    fn summary_counts_worked_minutes_and_failed_attempts() {
+        let fixture = setup();
+        punch(&fixture, PunchType::In, AttendanceStatus::Failed, 8, 50);
+        punch(&fixture, PunchType::In, AttendanceStatus::Success, 9, 0);
+        punch(&fixture, PunchType::Out, AttendanceStatus::Success, 18, 0);
+
+        let days = summaries(&fixture.db, &fixture.user, date(13), date(14)).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].status, DayStatus::Absent);
+        assert!(days[0].scheduled);
+        assert_eq!(days[1].status, DayStatus::Present);
+        assert_eq!(days[1].worked_minutes, 540);
+        assert_eq!(days[1].failed_attempts, 1);
+    }
+
+    #[test]
+    fn new_records_invalidate_stored_summaries() {
+        let fixture = setup();
+        punch(&fixture, PunchType::In, AttendanceStatus::Success, 9, 0);
+        let before = summaries(&fixture.db, &fixture.user, date(14), date(14)).unwrap();
+        assert_eq!(before[0].status, DayStatus::Incomplete);
+
+        punch(&fixture, PunchType::Out, AttendanceStatus::Success, 17, 0);
+        let after = summaries(&fixture.db, &fixture.user, date(14), date(14)).unwrap();
+        assert_eq!(after[0].status, DayStatus::Present);
+        assert_eq!(after[0].worked_minutes, 480);
+    }
+
+    #[test]
+    fn batch_saves_invalidate_stored_summaries() {
+        let fixture = setup();
+        summaries(&fixture.db, &fixture.user, date(13), date(14)).unwrap();
+        assert_eq!(
+            fixture
+                .db
+                .get_summaries(&fixture.user.id, date(13), date(14))
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let mut user = fixture.user.clone();
+        user.shift_id = None;
+        fixture.db.save_users(&[user]).unwrap();
+        assert!(fixture
+            .db
+            .get_summaries(&fixture.user.id, date(13), date(14))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn refresh_recent_only_fills_missing_days_for_employees() {
+        let fixture = setup();
+        test_support::employee(&fixture.db, "bob", &fixture.admin);
+        let db = Mutex::new(fixture.db);
+
+        assert_eq!(refresh_recent(&db, 3).unwrap(), 6);
+        assert_eq!(refresh_recent(&db, 3).unwrap(), 0);
+        assert_eq!(refresh_recent(&db, 4).unwrap(), 2);
+    }
+
+    /// 计算两人 3 月 10 日至 14 日的汇总，再执行 `change`，返回两人仍保留的汇总条数
+    fn stored_after(fixture: &Fixture, bob: &User, change: &dyn Fn(&Database)) -> (usize, usize) {
+        let db = &fixture.db;
+        for user in [&fixture.user, bob] {
+            summaries(db, user, date(10), date(14)).unwrap();
+        }
+        change(db);
+        let stored = |user: &User| {
+            db.get_summaries(&user.id, date(10), date(14))
+                .unwrap()
+                .len()
+        };
+        (stored(&fixture.user), stored(bob))
+    }
+
+    #[test]
+    fn configuration_changes_only_invalidate_affected_users() {
+        let fixture = setup();
+        let bob = test_support::employee(&fixture.db, "bob", &fixture.admin);
+        let shift = fixture
+            .db
+            .get_shift(fixture.user.shift_id.as_ref().unwrap())
+            .unwrap()
+            .unwrap();
+        let calendar =
+            HolidayCalendar::new("日历".to_string(), Vec::new(), fixture.admin.id.clone());
+        let mut location = fixture.location.clone();
+        location.calendar_id = Some(calendar.id.clone());
+        let group = UserGroup::new(
+            "一组".to_string(),
+            vec![fixture.user.id.clone()],
+            fixture.admin.id.clone(),
+        );
+
+        let stored = |change: &dyn Fn(&Database)| stored_after(&fixture, &bob, change);
+        assert_eq!(stored(&|db| db.save_shift(&shift).unwrap()), (0, 5));
+        // 尚无人使用的日历
+        assert_eq!(stored(&|db| db.save_calendar(&calendar).unwrap()), (5, 5));
+        assert_eq!(stored(&|db| db.save_location(&location).unwrap()), (0, 5));
+        assert_eq!(
+            stored(&|db| db.delete_calendar(&calendar.id).unwrap()),
+            (0, 5)
+        );
+        assert_eq!(stored(&|db| db.save_user_group(&group).unwrap()), (0, 5));
+        assert_eq!(
+            stored(&|db| db.delete_user_group(&group.id).unwrap()),
+            (0, 5)
+        );
+    }
+}
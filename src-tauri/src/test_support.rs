@@ -0,0 +1,114 @@
+//! 测试共用的数据：管理员、打卡位置和员工，各模块的测试只补充自己需要的部分
+
+use crate::database::Database;
+use crate::models::{
+    AttendanceRecord, AttendanceStatus, Location, PunchType, Shift, User, UserRole,
+};
+use crate::schedule;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+
+pub const TZ: Tz = chrono_tz::Asia::Shanghai;
+
+/// 一个管理员、其名下的一个位置，以及分配到该位置的员工
+pub struct Fixture {
+    pub db: Database,
+    pub admin: User,
+    pub user: User,
+    pub location: Location,
+}
+
+/// 管理员 admin、位置“总部”和员工 alice
+pub fn setup() -> Fixture {
+    let db = Database::temporary();
+    let admin = admin(&db, "admin");
+    let location = location(&db, "总部", &admin);
+    let mut user = employee(&db, "alice", &admin);
+    user.location_id = Some(location.id.clone());
+    db.save_user(&user).unwrap();
+    Fixture {
+        db,
+        admin,
+        user,
+        location,
+    }
+}
+
+/// 密码为 admin123 的管理员
+pub fn admin(db: &Database, username: &str) -> User {
+    let admin = User::new(
+        username.to_string(),
+        "admin123".to_string(),
+        UserRole::Admin,
+        None,
+    );
+    db.save_user(&admin).unwrap();
+    admin
+}
+
+/// 密码为 secret1 的员工
+pub fn employee(db: &Database, username: &str, admin: &User) -> User {
+    let user = User::new(
+        username.to_string(),
+        "secret1".to_string(),
+        UserRole::User,
+        Some(admin.id.clone()),
+    );
+    db.save_user(&user).unwrap();
+    user
+}
+
+/// 位于 (31.0, 121.0)、半径 100 米的位置
+pub fn location(db: &Database, name: &str, admin: &User) -> Location {
+    let location = Location::new(name.to_string(), 31.0, 121.0, 100.0, admin.id.clone());
+    db.save_location(&location).unwrap();
+    location
+}
+
+/// 09:00–18:00、宽限 5 分钟的白班
+pub fn day_shift(db: &Database, admin: &User, weekdays: Vec<u32>) -> Shift {
+    let shift = Shift::new(
+        "白班".to_string(),
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+        5,
+        weekdays,
+        admin.id.clone(),
+    );
+    db.save_shift(&shift).unwrap();
+    shift
+}
+
+/// 2025 年 3 月的某一天（3 月 14 日是周五）
+pub fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+}
+
+/// 2025 年 3 月 `day` 日 `hour`:`minute` 的本地时间
+pub fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    date(day).and_hms_opt(hour, minute, 0).unwrap()
+}
+
+/// 以本地时间 `time` 在 `location` 保存一条打卡记录
+pub fn punch(
+    db: &Database,
+    user: &User,
+    location: &Location,
+    punch_type: PunchType,
+    status: AttendanceStatus,
+    time: NaiveDateTime,
+) -> AttendanceRecord {
+    let mut record = AttendanceRecord::new(
+        user.id.clone(),
+        location.id.clone(),
+        31.0,
+        121.0,
+        punch_type,
+        status,
+        None,
+    );
+    record.timestamp = schedule::local_timestamp(TZ, time);
+    record.localize(TZ);
+    db.save_record(&record).unwrap();
+    record
+}
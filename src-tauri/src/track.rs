@@ -0,0 +1,269 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::export;
+use crate::geo;
+use crate::models::{
+    AttendanceRecord, AttendanceStatus, GeoPoint, Geofence, Location, RecordQuery, TrackFormat,
+    DEFAULT_TIMEZONE,
+};
+use crate::schedule;
+use chrono::{DateTime, NaiveDate};
+use std::fmt::Write;
+use std::path::Path;
+
+/// 圆形围栏绘制时使用的边数
+const CIRCLE_SEGMENTS: usize = 64;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 围栏的各个闭合边界，圆形用多边形近似
+fn outlines(fence: &Geofence) -> Vec<Vec<GeoPoint>> {
+    let mut rings = match fence {
+        Geofence::Circle {
+            latitude,
+            longitude,
+            radius,
+        } => vec![geo::circle_outline(
+            GeoPoint {
+                latitude: *latitude,
+                longitude: *longitude,
+            },
+            *radius,
+            CIRCLE_SEGMENTS,
+        )],
+        Geofence::Polygon { vertices } => vec![vertices.clone()],
+        Geofence::Union { shapes } => shapes.iter().flat_map(outlines).collect(),
+    };
+    for ring in &mut rings {
+        if let Some(first) = ring.first().copied() {
+            ring.push(first);
+        }
+    }
+    rings
+}
+
+/// 打卡点的标注：位置时区的本地时间、打卡类型和状态
+fn label(record: &AttendanceRecord, locations: &[Location]) -> String {
+    let tz = record
+        .timezone
+        .or_else(|| {
+            locations
+                .iter()
+                .find(|l| l.id == record.location_id)
+                .map(|l| l.timezone)
+        })
+        .unwrap_or(DEFAULT_TIMEZONE);
+    format!(
+        "{} {} {}",
+        schedule::local_datetime(record.timestamp, tz).format("%Y-%m-%d %H:%M:%S"),
+        export::punch_type_label(record.punch_type),
+        export::status_label(&record.status),
+    )
+}
+
+fn description(record: &AttendanceRecord) -> String {
+    let mut parts = Vec::new();
+    if let Some(accuracy) = record.accuracy {
+        parts.push(format!("定位精度 {} 米", accuracy));
+    }
+    if record.risk_score > 0 {
+        parts.push(format!("风险分 {}", record.risk_score));
+    }
+    if let Some(message) = &record.error_message {
+        parts.push(message.clone());
+    }
+    parts.join("；")
+}
+
+fn utc_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+/// 打卡点写为航点，围栏边界写为航迹，每个围栏形状一段
+fn gpx(records: &[AttendanceRecord], locations: &[Location]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<gpx version=\"1.1\" creator=\"attendance\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+
+    for record in records {
+        let _ = writeln!(
+            out,
+            "  <wpt lat=\"{}\" lon=\"{}\">",
+            record.latitude, record.longitude
+        );
+        if let Some(altitude) = record.altitude {
+            let _ = writeln!(out, "    <ele>{}</ele>", altitude);
+        }
+        let _ = writeln!(out, "    <time>{}</time>", utc_time(record.timestamp));
+        let _ = writeln!(
+            out,
+            "    <name>{}</name>",
+            escape(&label(record, locations))
+        );
+        let _ = writeln!(out, "    <desc>{}</desc>", escape(&description(record)));
+        let _ = writeln!(
+            out,
+            "    <type>{}</type>",
+            escape(export::status_label(&record.status))
+        );
+        out.push_str("  </wpt>\n");
+    }
+
+    for location in locations {
+        let _ = writeln!(out, "  <trk>\n    <name>{}</name>", escape(&location.name));
+        for ring in outlines(&location.fence()) {
+            out.push_str("    <trkseg>\n");
+            for point in ring {
+                let _ = writeln!(
+                    out,
+                    "      <trkpt lat=\"{}\" lon=\"{}\"/>",
+                    point.latitude, point.longitude
+                );
+            }
+            out.push_str("    </trkseg>\n");
+        }
+        out.push_str("  </trk>\n");
+    }
+
+    out.push_str("</gpx>\n");
+    out
+}
+
+fn kml_coordinates(ring: &[GeoPoint]) -> String {
+    ring.iter()
+        .map(|p| format!("{},{}", p.longitude, p.latitude))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 成功的打卡为绿色，其余为红色；围栏绘制为半透明多边形
+fn kml(records: &[AttendanceRecord], locations: &[Location]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n<Document>\n");
+    out.push_str(concat!(
+        "  <Style id=\"success\"><IconStyle><color>ff00c000</color></IconStyle></Style>\n",
+        "  <Style id=\"failed\"><IconStyle><color>ff0000ff</color></IconStyle></Style>\n",
+        "  <Style id=\"geofence\"><LineStyle><color>ffff8000</color><width>2</width></LineStyle>",
+        "<PolyStyle><color>40ff8000</color></PolyStyle></Style>\n",
+    ));
+
+    out.push_str("  <Folder>\n    <name>打卡记录</name>\n");
+    for record in records {
+        let style = if record.status == AttendanceStatus::Success {
+            "success"
+        } else {
+            "failed"
+        };
+        let _ = writeln!(
+            out,
+            concat!(
+                "    <Placemark>\n",
+                "      <name>{}</name>\n",
+                "      <description>{}</description>\n",
+                "      <TimeStamp><when>{}</when></TimeStamp>\n",
+                "      <styleUrl>#{}</styleUrl>\n",
+                "      <Point><coordinates>{},{}</coordinates></Point>\n",
+                "    </Placemark>"
+            ),
+            escape(&label(record, locations)),
+            escape(&description(record)),
+            utc_time(record.timestamp),
+            style,
+            record.longitude,
+            record.latitude,
+        );
+    }
+    out.push_str("  </Folder>\n");
+
+    out.push_str("  <Folder>\n    <name>打卡围栏</name>\n");
+    for location in locations {
+        let _ = writeln!(
+            out,
+            "    <Placemark>\n      <name>{}</name>\n      <styleUrl>#geofence</styleUrl>\n      <MultiGeometry>",
+            escape(&location.name)
+        );
+        for ring in outlines(&location.fence()) {
+            let _ = writeln!(
+                out,
+                "        <Polygon><outerBoundaryIs><LinearRing><coordinates>{}</coordinates></LinearRing></outerBoundaryIs></Polygon>",
+                kml_coordinates(&ring)
+            );
+        }
+        out.push_str("      </MultiGeometry>\n    </Placemark>\n");
+    }
+    out.push_str("  </Folder>\n</Document>\n</kml>\n");
+    out
+}
+
+/// 导出用户在日期范围内的打卡坐标及其可打卡位置的围栏，返回导出的打卡点数量
+pub fn export_track(
+    db: &Database,
+    user_id: &str,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    format: TrackFormat,
+    path: &str,
+) -> Result<usize, String> {
+    let path = Path::new(path);
+    export::ensure_parent_dir(path)?;
+    if end_date < start_date {
+        return Err("结束日期不能早于开始日期".to_string());
+    }
+    if (end_date - start_date).num_days() > 366 {
+        return Err("导出时间范围不能超过一年".to_string());
+    }
+
+    let user = db
+        .get_user(user_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "用户不存在".to_string())?;
+    let records = export::query_records(
+        db,
+        &RecordQuery {
+            user_id: Some(user.id.clone()),
+            start_date: Some(start_date),
+            end_date: Some(end_date),
+            ..Default::default()
+        },
+    )?;
+
+    // 期间分配过的位置以及实际打卡的位置都需要绘制
+    let mut locations: Vec<Location> = Vec::new();
+    for date in start_date.iter_days().take_while(|d| *d <= end_date) {
+        for (location, _) in
+            checkin::resolve_user_locations(db, &user, date).map_err(|e| e.to_string())?
+        {
+            if !locations.iter().any(|l| l.id == location.id) {
+                locations.push(location);
+            }
+        }
+    }
+    for record in &records {
+        if !locations.iter().any(|l| l.id == record.location_id) {
+            if let Some(location) = db
+                .get_location(&record.location_id)
+                .map_err(|e| e.to_string())?
+            {
+                locations.push(location);
+            }
+        }
+    }
+
+    let content = match format {
+        TrackFormat::Gpx => gpx(&records, &locations),
+        TrackFormat::Kml => kml(&records, &locations),
+    };
+    std::fs::write(path, content).map_err(|e| format!("写入文件失败: {}", e))?;
+    Ok(records.len())
+}
@@ -0,0 +1,343 @@
+use crate::database::Database;
+use crate::models::{ImportRowError, User, UserImportReport, UserInfo, UserProfile, UserRole};
+use crate::webhook;
+use std::collections::{HashMap, HashSet};
+
+const MIN_PASSWORD_LENGTH: usize = 6;
+const MAX_USERNAME_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Field {
+    Username,
+    Password,
+    Role,
+    Location,
+    Shift,
+    DisplayName,
+    EmployeeNo,
+    Phone,
+    Email,
+}
+
+/// 表头同时接受中文和英文名称
+fn field(header: &str) -> Option<Field> {
+    match header.trim().to_lowercase().as_str() {
+        "username" | "用户名" => Some(Field::Username),
+        "password" | "初始密码" | "密码" => Some(Field::Password),
+        "role" | "角色" => Some(Field::Role),
+        "location" | "位置" | "打卡位置" => Some(Field::Location),
+        "shift" | "班次" => Some(Field::Shift),
+        "display_name" | "姓名" => Some(Field::DisplayName),
+        "employee_no" | "工号" => Some(Field::EmployeeNo),
+        "phone" | "手机号" => Some(Field::Phone),
+        "email" | "邮箱" => Some(Field::Email),
+        _ => None,
+    }
+}
+
+/// 导入的账号都归属当前管理员，不能借此创建新的管理员
+fn role(value: &str) -> Result<UserRole, String> {
+    match value.trim().to_lowercase().as_str() {
+        "" | "user" | "员工" => Ok(UserRole::User),
+        "admin" | "管理员" => Err("不能通过导入创建管理员".to_string()),
+        other => Err(format!("未知角色: {}", other)),
+    }
+}
+
+pub fn validate_username(username: &str) -> Result<(), String> {
+    if username.is_empty() {
+        return Err("用户名不能为空".to_string());
+    }
+    if username.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(format!("用户名不能超过 {} 个字符", MAX_USERNAME_LENGTH));
+    }
+    if username.chars().any(char::is_whitespace) {
+        return Err("用户名不能包含空白字符".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!("初始密码至少 {} 位", MIN_PASSWORD_LENGTH));
+    }
+    Ok(())
+}
+
+/// 按名称查找管理员名下的位置或班次，重名时无法确定对应哪一个
+fn lookup<'a>(
+    names: &'a HashMap<String, Vec<String>>,
+    name: &str,
+    kind: &str,
+) -> Result<&'a str, String> {
+    match names.get(name).map(Vec::as_slice) {
+        Some([id]) => Ok(id),
+        Some(_) => Err(format!("存在多个名为 {} 的{}", name, kind)),
+        None => Err(format!("{}不存在: {}", kind, name)),
+    }
+}
+
+fn by_name(entries: impl Iterator<Item = (String, String)>) -> HashMap<String, Vec<String>> {
+    let mut names: HashMap<String, Vec<String>> = HashMap::new();
+    for (name, id) in entries {
+        names.entry(name).or_default().push(id);
+    }
+    names
+}
+
+/// 校验整个 CSV 文件后一次性写入所有有效行；`dry_run` 时只返回校验结果
+pub fn import_users(
+    db: &Database,
+    admin_id: &str,
+    content: &str,
+    dry_run: bool,
+) -> Result<UserImportReport, String> {
+    let admin = db
+        .get_user(admin_id)
+        .map_err(|e| e.to_string())?
+        .filter(|u| u.role == UserRole::Admin)
+        .ok_or_else(|| "管理员不存在".to_string())?;
+
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("无法读取表头: {}", e))?;
+    let mut columns: HashMap<Field, usize> = HashMap::new();
+    for (index, header) in headers.iter().enumerate() {
+        match field(header) {
+            Some(field) if columns.insert(field, index).is_some() => {
+                return Err(format!("表头重复: {}", header));
+            }
+            Some(_) => {}
+            None => return Err(format!("无法识别的表头: {}", header)),
+        }
+    }
+    if !columns.contains_key(&Field::Username) || !columns.contains_key(&Field::Password) {
+        return Err("表头必须包含用户名和初始密码".to_string());
+    }
+
+    let existing: HashSet<String> = db
+        .get_all_users()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|u| u.username)
+        .collect();
+    let locations = by_name(
+        db.get_all_locations()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|l| l.admin_id == admin.id)
+            .map(|l| (l.name, l.id)),
+    );
+    let shifts = by_name(
+        db.get_all_shifts()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|s| s.admin_id == admin.id)
+            .map(|s| (s.name, s.id)),
+    );
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut imported = Vec::new();
+    let mut errors = Vec::new();
+    let mut total_rows = 0;
+
+    for (index, row) in reader.records().enumerate() {
+        let line = index + 2;
+        total_rows += 1;
+        let row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    line,
+                    username: None,
+                    message: format!("无法解析: {}", e),
+                });
+                continue;
+            }
+        };
+        // 带引号的字段可能跨行，以解析器记录的行号为准
+        let line = row.position().map_or(line, |p| p.line() as usize);
+        let value = |field: Field| -> &str {
+            columns
+                .get(&field)
+                .and_then(|i| row.get(*i))
+                .unwrap_or_default()
+        };
+        let optional = |field: Field| -> Option<String> {
+            Some(value(field).to_string()).filter(|v| !v.is_empty())
+        };
+
+        let username = value(Field::Username).to_string();
+        let parsed = (|| -> Result<User, String> {
+            validate_username(&username)?;
+            if existing.contains(&username) {
+                return Err("用户名已存在".to_string());
+            }
+            if let Some(first) = seen.get(&username) {
+                return Err(format!("与第 {} 行用户名重复", first));
+            }
+            validate_password(value(Field::Password))?;
+
+            let mut user = User::new(
+                username.clone(),
+                value(Field::Password).to_string(),
+                role(value(Field::Role))?,
+                Some(admin.id.clone()),
+            );
+
+            if let Some(name) = optional(Field::Location) {
+                user.location_id = Some(lookup(&locations, &name, "位置")?.to_string());
+            }
+            if let Some(name) = optional(Field::Shift) {
+                user.shift_id = Some(lookup(&shifts, &name, "班次")?.to_string());
+            }
+
+            let email = optional(Field::Email);
+            if email.as_ref().is_some_and(|e| !e.contains('@')) {
+                return Err("邮箱格式不正确".to_string());
+            }
+            user.profile = UserProfile {
+                display_name: optional(Field::DisplayName),
+                employee_no: optional(Field::EmployeeNo),
+                phone: optional(Field::Phone),
+                email,
+            };
+            Ok(user)
+        })();
+
+        if !username.is_empty() {
+            seen.entry(username.clone()).or_insert(line);
+        }
+        match parsed {
+            Ok(user) => imported.push(user),
+            Err(message) => errors.push(ImportRowError {
+                line,
+                username: Some(username).filter(|u| !u.is_empty()),
+                message,
+            }),
+        }
+    }
+
+    if total_rows == 0 {
+        return Err("文件中没有数据行".to_string());
+    }
+
+    let applied = !dry_run && !imported.is_empty();
+    if applied {
+        db.save_users(&imported).map_err(|e| e.to_string())?;
+        for user in &imported {
+            webhook::user_created(db, user);
+        }
+    }
+
+    Ok(UserImportReport {
+        total_rows,
+        imported: imported.into_iter().map(UserInfo::from).collect(),
+        errors,
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn setup() -> (Database, User) {
+        let db = Database::temporary();
+        let admin = test_support::admin(&db, "admin");
+        test_support::location(&db, "总部", &admin);
+        test_support::day_shift(&db, &admin, vec![1, 2, 3, 4, 5]);
+        (db, admin)
+    }
+
+    fn messages(report: &UserImportReport) -> Vec<(usize, &str)> {
+        report
+            .errors
+            .iter()
+            .map(|e| (e.line, e.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_rows_are_imported_under_the_admin() {
+        let (db, admin) = setup();
+        let content = "\u{feff}用户名,初始密码,打卡位置,班次,姓名,邮箱\n\
+                       alice,secret1,总部,白班,张三,alice@example.com\n\
+                       bob,secret2,,,,\n";
+        let report = import_users(&db, &admin.id, content, false).unwrap();
+        assert!(report.errors.is_empty());
+        assert!(report.applied);
+        assert_eq!(report.total_rows, 2);
+
+        let alice = db.get_user_by_username("alice").unwrap().unwrap();
+        assert_eq!(alice.admin_id.as_deref(), Some(admin.id.as_str()));
+        assert!(alice.location_id.is_some());
+        assert!(alice.shift_id.is_some());
+        assert_eq!(alice.profile.display_name.as_deref(), Some("张三"));
+        assert!(db.get_user_by_username("bob").unwrap().is_some());
+    }
+
+    #[test]
+    fn admin_rows_are_rejected() {
+        let (db, admin) = setup();
+        let content = "username,password,role\n\
+                       boss,secret1,admin\n\
+                       carol,secret2,user\n";
+        let report = import_users(&db, &admin.id, content, false).unwrap();
+        assert_eq!(messages(&report), vec![(2, "不能通过导入创建管理员")]);
+        assert_eq!(report.imported.len(), 1);
+        assert!(db.get_user_by_username("boss").unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_by_line() {
+        let (db, admin) = setup();
+        let content = "username,password,location\n\
+                       admin,secret1,\n\
+                       dave,123,\n\
+                       erin,secret1,分部\n\
+                       frank,secret1,\n\
+                       frank,secret2,\n";
+        let report = import_users(&db, &admin.id, content, false).unwrap();
+        assert_eq!(
+            messages(&report),
+            vec![
+                (2, "用户名已存在"),
+                (3, "初始密码至少 6 位"),
+                (4, "位置不存在: 分部"),
+                (6, "与第 5 行用户名重复"),
+            ]
+        );
+        assert_eq!(report.imported.len(), 1);
+    }
+
+    #[test]
+    fn dry_run_does_not_write() {
+        let (db, admin) = setup();
+        let report =
+            import_users(&db, &admin.id, "username,password\nalice,secret1\n", true).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        assert!(!report.applied);
+        assert!(db.get_user_by_username("alice").unwrap().is_none());
+    }
+
+    #[test]
+    fn unknown_headers_are_rejected() {
+        let (db, admin) = setup();
+        assert_eq!(
+            import_users(&db, &admin.id, "username,password,age\n", false).unwrap_err(),
+            "无法识别的表头: age"
+        );
+        assert_eq!(
+            import_users(&db, &admin.id, "username\nalice\n", false).unwrap_err(),
+            "表头必须包含用户名和初始密码"
+        );
+    }
+}
@@ -0,0 +1,539 @@
+//! 向管理员登记的端点推送考勤事件。
+//!
+//! 请求体为 JSON：`{"id", "event", "createdAt", "data"}`，`id` 在重试时保持不变，接收方可据此去重。
+//! 签名为 `HMAC-SHA256(secret, "{X-Attendance-Timestamp}.{请求体}")` 的十六进制，
+//! 放在 `X-Attendance-Signature: sha256=<签名>` 中。
+
+use crate::auth;
+use crate::database::Database;
+use crate::models::{
+    AttendanceRecord, AttendanceStatus, CorrectionRequest, CreateWebhookRequest, Punctuality,
+    UpdateWebhookRequest, User, UserInfo, UserRole, Webhook, WebhookDelivery, WebhookDeliveryLog,
+    WebhookEvent,
+};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const SECRET_PREFIX: &str = "whsec_";
+
+/// 超过该次数仍失败则放弃投递
+const MAX_ATTEMPTS: u32 = 8;
+/// 第一次重试的间隔，之后每次翻倍，最长 6 小时
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 6 * 3600;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn validate(name: &str, url: &str, events: &[WebhookEvent]) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("名称不能为空".to_string());
+    }
+    let url = url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err("地址必须以 http:// 或 https:// 开头".to_string());
+    }
+    if events.is_empty() {
+        return Err("请至少选择一个事件".to_string());
+    }
+    if events.contains(&WebhookEvent::Ping) {
+        return Err("测试事件不能订阅".to_string());
+    }
+    Ok(())
+}
+
+fn owned(db: &Database, admin_id: &str, webhook_id: &str) -> Result<Webhook, String> {
+    db.get_webhook(webhook_id)
+        .map_err(|e| e.to_string())?
+        .filter(|w| w.admin_id == admin_id)
+        .ok_or_else(|| "Webhook 不存在".to_string())
+}
+
+pub fn create(db: &Database, request: CreateWebhookRequest) -> Result<Webhook, String> {
+    validate(&request.name, &request.url, &request.events)?;
+    db.get_user(&request.admin_id)
+        .map_err(|e| e.to_string())?
+        .filter(|u| u.role == UserRole::Admin)
+        .ok_or_else(|| "管理员不存在".to_string())?;
+
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        admin_id: request.admin_id,
+        name: request.name.trim().to_string(),
+        url: request.url.trim().to_string(),
+        secret: format!("{}{}", SECRET_PREFIX, auth::random_token()),
+        events: request.events,
+        enabled: true,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    db.save_webhook(&webhook).map_err(|e| e.to_string())?;
+    Ok(webhook)
+}
+
+pub fn update(
+    db: &Database,
+    admin_id: &str,
+    webhook_id: &str,
+    request: UpdateWebhookRequest,
+) -> Result<Webhook, String> {
+    validate(&request.name, &request.url, &request.events)?;
+    let mut webhook = owned(db, admin_id, webhook_id)?;
+    webhook.name = request.name.trim().to_string();
+    webhook.url = request.url.trim().to_string();
+    webhook.events = request.events;
+    webhook.enabled = request.enabled;
+    db.save_webhook(&webhook).map_err(|e| e.to_string())?;
+    Ok(webhook)
+}
+
+pub fn delete(db: &Database, admin_id: &str, webhook_id: &str) -> Result<(), String> {
+    owned(db, admin_id, webhook_id)?;
+    db.delete_webhook(webhook_id).map_err(|e| e.to_string())
+}
+
+pub fn delivery_logs(
+    db: &Database,
+    admin_id: &str,
+    webhook_id: &str,
+) -> Result<Vec<WebhookDeliveryLog>, String> {
+    owned(db, admin_id, webhook_id)?;
+    db.get_delivery_logs(webhook_id).map_err(|e| e.to_string())
+}
+
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn event_name(event: WebhookEvent) -> String {
+    serde_json::to_value(event)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn new_delivery(webhook: &Webhook, event: WebhookEvent, data: &Value, now: i64) -> WebhookDelivery {
+    let id = Uuid::new_v4().to_string();
+    let payload = json!({
+        "id": id,
+        "event": event,
+        "createdAt": now,
+        "data": data,
+    });
+    WebhookDelivery {
+        id,
+        webhook_id: webhook.id.clone(),
+        event,
+        payload: payload.to_string(),
+        attempts: 0,
+        next_attempt_at: now,
+        created_at: now,
+    }
+}
+
+/// 为订阅了该事件的端点写入发件箱，返回写入的数量
+pub fn enqueue(
+    db: &Database,
+    admin_id: &str,
+    event: WebhookEvent,
+    data: Value,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let now = chrono::Utc::now().timestamp();
+    let mut queued = 0;
+    for webhook in db.get_all_webhooks()? {
+        if webhook.admin_id == admin_id && webhook.enabled && webhook.events.contains(&event) {
+            db.save_delivery(&new_delivery(&webhook, event, &data, now))?;
+            queued += 1;
+        }
+    }
+    Ok(queued)
+}
+
+/// 事件通知失败不影响业务操作本身
+fn notify(db: &Database, admin_id: &str, event: WebhookEvent, data: Value) {
+    if let Err(e) = enqueue(db, admin_id, event, data) {
+        println!("Failed to queue webhook event: {}", e);
+    }
+}
+
+/// 推送的用户信息不包含密码
+fn public_user(user: &User) -> Value {
+    serde_json::to_value(UserInfo::from(user.clone())).unwrap_or_default()
+}
+
+/// 新保存的打卡记录：成功或失败，迟到时另外发送迟到事件
+pub fn check_in_recorded(db: &Database, user: &User, record: &AttendanceRecord) {
+    let Some(admin_id) = &user.admin_id else {
+        return;
+    };
+    let data = json!({ "record": record, "user": public_user(user) });
+    if record.status != AttendanceStatus::Success {
+        notify(db, admin_id, WebhookEvent::CheckInFailed, data);
+        return;
+    }
+    notify(db, admin_id, WebhookEvent::CheckInSuccess, data.clone());
+    if let Some(Punctuality::Late(minutes)) = record.punctuality {
+        let mut data = data;
+        data["lateMinutes"] = json!(minutes);
+        notify(db, admin_id, WebhookEvent::LateArrival, data);
+    }
+}
+
+pub fn correction_approved(
+    db: &Database,
+    user: &User,
+    correction: &CorrectionRequest,
+    record: &AttendanceRecord,
+) {
+    let Some(admin_id) = &user.admin_id else {
+        return;
+    };
+    let data = json!({ "correction": correction, "record": record, "user": public_user(user) });
+    notify(db, admin_id, WebhookEvent::CorrectionApproved, data);
+}
+
+pub fn user_created(db: &Database, user: &User) {
+    let Some(admin_id) = &user.admin_id else {
+        return;
+    };
+    notify(
+        db,
+        admin_id,
+        WebhookEvent::UserCreated,
+        json!({ "user": public_user(user) }),
+    );
+}
+
+/// 向端点发送一条测试事件，不受订阅和启用状态限制
+pub fn ping(db: &Database, admin_id: &str, webhook_id: &str) -> Result<(), String> {
+    let webhook = owned(db, admin_id, webhook_id)?;
+    let data = json!({ "webhookId": webhook.id, "name": webhook.name });
+    let delivery = new_delivery(
+        &webhook,
+        WebhookEvent::Ping,
+        &data,
+        chrono::Utc::now().timestamp(),
+    );
+    db.save_delivery(&delivery).map_err(|e| e.to_string())
+}
+
+/// 已到重试时间的投递及其端点；端点被删除的投递直接丢弃，停用的暂不投递
+pub fn due_deliveries(
+    db: &Database,
+    now: i64,
+) -> Result<Vec<(WebhookDelivery, Webhook)>, Box<dyn std::error::Error>> {
+    let mut due = Vec::new();
+    for delivery in db.get_all_deliveries()? {
+        if delivery.next_attempt_at > now {
+            continue;
+        }
+        match db.get_webhook(&delivery.webhook_id)? {
+            Some(webhook) if webhook.enabled || delivery.event == WebhookEvent::Ping => {
+                due.push((delivery, webhook))
+            }
+            Some(_) => {}
+            None => db.delete_delivery(&delivery.id)?,
+        }
+    }
+    due.sort_by_key(|(d, _)| d.created_at);
+    Ok(due)
+}
+
+/// 一次投递尝试的结果，只有 2xx 响应视为成功
+pub struct Attempt {
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+pub fn send(webhook: &Webhook, delivery: &WebhookDelivery, now: i64) -> Attempt {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let started = Instant::now();
+    let result = agent
+        .post(&webhook.url)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", "attendance-webhook")
+        .set("X-Attendance-Event", &event_name(delivery.event))
+        .set("X-Attendance-Delivery", &delivery.id)
+        .set("X-Attendance-Timestamp", &now.to_string())
+        .set(
+            "X-Attendance-Signature",
+            &format!("sha256={}", sign(&webhook.secret, now, &delivery.payload)),
+        )
+        .send_string(&delivery.payload);
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(response) => Attempt {
+            success: true,
+            status_code: Some(response.status()),
+            error: None,
+            duration_ms,
+        },
+        Err(ureq::Error::Status(code, _)) => Attempt {
+            success: false,
+            status_code: Some(code),
+            error: Some(format!("端点返回 HTTP {}", code)),
+            duration_ms,
+        },
+        Err(e) => Attempt {
+            success: false,
+            status_code: None,
+            error: Some(e.to_string()),
+            duration_ms,
+        },
+    }
+}
+
+fn retry_delay(attempts: u32) -> i64 {
+    let factor = 2_i64.saturating_pow(attempts.saturating_sub(1));
+    RETRY_BASE_SECONDS
+        .saturating_mul(factor)
+        .min(RETRY_MAX_SECONDS)
+}
+
+/// 写入投递记录；成功或达到最大次数后移出发件箱，否则按退避时间安排下一次尝试
+pub fn record_attempt(
+    db: &Database,
+    mut delivery: WebhookDelivery,
+    attempt: Attempt,
+    now: i64,
+) -> Result<WebhookDeliveryLog, Box<dyn std::error::Error>> {
+    delivery.attempts += 1;
+    let next_attempt_at = (!attempt.success && delivery.attempts < MAX_ATTEMPTS)
+        .then(|| now + retry_delay(delivery.attempts));
+
+    let log = WebhookDeliveryLog {
+        id: Uuid::new_v4().to_string(),
+        delivery_id: delivery.id.clone(),
+        webhook_id: delivery.webhook_id.clone(),
+        event: delivery.event,
+        attempt: delivery.attempts,
+        timestamp: now,
+        success: attempt.success,
+        status_code: attempt.status_code,
+        error: attempt.error,
+        duration_ms: attempt.duration_ms,
+        next_attempt_at,
+    };
+    db.save_delivery_log(&log)?;
+
+    match next_attempt_at {
+        Some(at) => {
+            delivery.next_attempt_at = at;
+            db.save_delivery(&delivery)?;
+        }
+        None => db.delete_delivery(&delivery.id)?,
+    }
+    Ok(log)
+}
+
+/// 后台任务调用：发送请求时不持有数据库锁，返回尝试的次数
+pub fn deliver_due(db: &Mutex<Database>) -> Result<usize, Box<dyn std::error::Error>> {
+    let now = chrono::Utc::now().timestamp();
+    let due = due_deliveries(&db.lock().unwrap(), now)?;
+    let count = due.len();
+    for (delivery, webhook) in due {
+        let sent_at = chrono::Utc::now().timestamp();
+        let attempt = send(&webhook, &delivery, sent_at);
+        record_attempt(&db.lock().unwrap(), delivery, attempt, sent_at)?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    struct Received {
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    /// 依次按 `statuses` 应答的本地端点，收到的请求通过通道返回
+    fn endpoint(statuses: Vec<u16>) -> (String, mpsc::Receiver<Received>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    match line.trim_end().split_once(": ") {
+                        Some((name, value)) => {
+                            headers.insert(name.to_lowercase(), value.to_string());
+                        }
+                        None => break,
+                    }
+                }
+                let length = headers["content-length"].parse().unwrap();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+                let body = String::from_utf8(body).unwrap();
+                sender.send(Received { headers, body }).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    fn setup(url: &str, events: Vec<WebhookEvent>) -> (Database, User, Webhook) {
+        let db = Database::temporary();
+        let admin = test_support::admin(&db, "admin");
+        let webhook = create(
+            &db,
+            CreateWebhookRequest {
+                admin_id: admin.id.clone(),
+                name: "考勤系统".to_string(),
+                url: url.to_string(),
+                events,
+            },
+        )
+        .unwrap();
+        (db, admin, webhook)
+    }
+
+    fn failure() -> Attempt {
+        Attempt {
+            success: false,
+            status_code: Some(500),
+            error: Some("端点返回 HTTP 500".to_string()),
+            duration_ms: 1,
+        }
+    }
+
+    #[test]
+    fn deliveries_are_signed_and_logged() {
+        let (url, received) = endpoint(vec![200]);
+        let (db, admin, webhook) = setup(&url, vec![WebhookEvent::UserCreated]);
+        ping(&db, &admin.id, &webhook.id).unwrap();
+        let delivery = db.get_all_deliveries().unwrap().remove(0);
+
+        let db = Mutex::new(db);
+        assert_eq!(deliver_due(&db).unwrap(), 1);
+        let request = received.recv_timeout(REQUEST_TIMEOUT).unwrap();
+
+        let timestamp: i64 = request.headers["x-attendance-timestamp"].parse().unwrap();
+        assert_eq!(
+            request.headers["x-attendance-signature"],
+            format!("sha256={}", sign(&webhook.secret, timestamp, &request.body))
+        );
+        assert_eq!(request.headers["x-attendance-event"], "ping");
+        assert_eq!(request.headers["x-attendance-delivery"], delivery.id);
+        assert_eq!(request.body, delivery.payload);
+
+        let db = db.into_inner().unwrap();
+        assert!(db.get_all_deliveries().unwrap().is_empty());
+        let logs = delivery_logs(&db, &admin.id, &webhook.id).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].success);
+        assert_eq!(logs[0].status_code, Some(200));
+        assert_eq!(logs[0].attempt, 1);
+        assert_eq!(logs[0].next_attempt_at, None);
+    }
+
+    #[test]
+    fn failed_deliveries_are_retried_with_backoff() {
+        let (url, received) = endpoint(vec![500]);
+        let (db, admin, webhook) = setup(&url, vec![WebhookEvent::UserCreated]);
+        ping(&db, &admin.id, &webhook.id).unwrap();
+
+        let db = Mutex::new(db);
+        deliver_due(&db).unwrap();
+        received.recv_timeout(REQUEST_TIMEOUT).unwrap();
+        let db = db.into_inner().unwrap();
+
+        let logs = delivery_logs(&db, &admin.id, &webhook.id).unwrap();
+        assert!(!logs[0].success);
+        assert_eq!(logs[0].status_code, Some(500));
+        assert_eq!(logs[0].error.as_deref(), Some("端点返回 HTTP 500"));
+        let delivery = db.get_all_deliveries().unwrap().remove(0);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(logs[0].next_attempt_at, Some(logs[0].timestamp + 30));
+        assert_eq!(delivery.next_attempt_at, logs[0].timestamp + 30);
+        // 下一次重试之前不会再次投递
+        assert!(due_deliveries(&db, logs[0].timestamp).unwrap().is_empty());
+    }
+
+    #[test]
+    fn backoff_doubles_until_the_cap_and_gives_up_after_max_attempts() {
+        let (db, admin, webhook) =
+            setup("http://127.0.0.1:9/hook", vec![WebhookEvent::UserCreated]);
+        ping(&db, &admin.id, &webhook.id).unwrap();
+
+        let mut now = 1_000_000;
+        let mut delays = Vec::new();
+        for _ in 0..MAX_ATTEMPTS {
+            let delivery = db.get_all_deliveries().unwrap().remove(0);
+            let log = record_attempt(&db, delivery, failure(), now).unwrap();
+            if let Some(at) = log.next_attempt_at {
+                delays.push(at - now);
+                now = at;
+            }
+        }
+        assert_eq!(delays, vec![30, 60, 120, 240, 480, 960, 1920]);
+        assert_eq!(retry_delay(20), RETRY_MAX_SECONDS);
+
+        // 最后一次失败后移出发件箱，投递记录保留每次尝试
+        assert!(db.get_all_deliveries().unwrap().is_empty());
+        let logs = delivery_logs(&db, &admin.id, &webhook.id).unwrap();
+        assert_eq!(logs.len(), MAX_ATTEMPTS as usize);
+        assert_eq!(logs[0].attempt, MAX_ATTEMPTS);
+        assert_eq!(logs[0].next_attempt_at, None);
+    }
+
+    #[test]
+    fn events_go_only_to_enabled_subscribers_without_passwords() {
+        let (db, admin, webhook) =
+            setup("http://127.0.0.1:9/hook", vec![WebhookEvent::UserCreated]);
+        let user = User::new(
+            "alice".to_string(),
+            "secret1".to_string(),
+            UserRole::User,
+            Some(admin.id.clone()),
+        );
+        user_created(&db, &user);
+        assert_eq!(
+            enqueue(&db, &admin.id, WebhookEvent::CheckInSuccess, json!({})).unwrap(),
+            0
+        );
+
+        let deliveries = db.get_all_deliveries().unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let payload: Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+        assert_eq!(payload["event"], "userCreated");
+        assert_eq!(payload["data"]["user"]["username"], "alice");
+        assert!(payload["data"]["user"].get("password").is_none());
+
+        // 停用的端点暂不投递，端点删除后投递被丢弃
+        let mut disabled = webhook.clone();
+        disabled.enabled = false;
+        db.save_webhook(&disabled).unwrap();
+        assert!(due_deliveries(&db, i64::MAX).unwrap().is_empty());
+        assert_eq!(db.get_all_deliveries().unwrap().len(), 1);
+        db.delete_webhook(&webhook.id).unwrap();
+        assert!(due_deliveries(&db, i64::MAX).unwrap().is_empty());
+        assert!(db.get_all_deliveries().unwrap().is_empty());
+    }
+}
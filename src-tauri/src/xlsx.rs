@@ -0,0 +1,285 @@
+use crate::checkin;
+use crate::database::Database;
+use crate::export;
+use crate::models::{MonthlyReport, ReportColumn};
+use crate::report::TeamMonth;
+use crate::schedule;
+use chrono::Datelike;
+use rust_xlsxwriter::{
+    Color, ConditionalFormatCell, ConditionalFormatCellRule, ConditionalFormatFormula, Format,
+    FormatBorder, Workbook, Worksheet, XlsxError,
+};
+use std::path::Path;
+
+const DETAIL_HEADERS: &[&str] = &[
+    "用户名",
+    "日期",
+    "星期",
+    "状态",
+    "上班打卡",
+    "下班打卡",
+    "工作时长(分钟)",
+    "迟到(分钟)",
+    "早退(分钟)",
+    "请假(天)",
+    "加班(分钟)",
+    "失败打卡次数",
+];
+
+const WEEKDAYS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
+fn header_format() -> Format {
+    Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xD9E1F2))
+        .set_border(FormatBorder::Thin)
+}
+
+/// 迟到、缺勤等需要关注的数据使用浅红色背景
+fn warning_format() -> Format {
+    Format::new()
+        .set_background_color(Color::RGB(0xFFC7CE))
+        .set_font_color(Color::RGB(0x9C0006))
+}
+
+fn late_format() -> Format {
+    Format::new()
+        .set_background_color(Color::RGB(0xFFEB9C))
+        .set_font_color(Color::RGB(0x9C5700))
+}
+
+fn write_headers(sheet: &mut Worksheet, headers: &[&str]) -> Result<(), XlsxError> {
+    let format = header_format();
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *header, &format)?;
+        sheet.set_column_width(col as u16, 14)?;
+    }
+    Ok(())
+}
+
+fn summary_sheet(report: &MonthlyReport, columns: &[ReportColumn]) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("汇总")?;
+
+    let headers: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+    write_headers(&mut sheet, &headers)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    let total_format = Format::new().set_bold();
+    let rows = report
+        .rows
+        .iter()
+        .map(|r| (r.username.as_str(), &r.figures, None))
+        .chain(std::iter::once((
+            "合计",
+            &report.totals,
+            Some(&total_format),
+        )));
+
+    for (index, (name, figures, format)) in rows.enumerate() {
+        let row = index as u32 + 1;
+        for (col, column) in columns.iter().enumerate() {
+            let col = col as u16;
+            match (column, format) {
+                (ReportColumn::Username, Some(format)) => {
+                    sheet.write_string_with_format(row, col, name, format)?
+                }
+                (ReportColumn::Username, None) => sheet.write_string(row, col, name)?,
+                (_, Some(format)) => {
+                    sheet.write_number_with_format(row, col, column.number(figures), format)?
+                }
+                (_, None) => sheet.write_number(row, col, column.number(figures))?,
+            };
+        }
+    }
+
+    // 合计行不参与高亮
+    let last_row = report.rows.len() as u32;
+    if last_row > 0 {
+        for (col, column) in columns.iter().enumerate() {
+            let format = match column {
+                ReportColumn::AbsentDays | ReportColumn::IncompleteDays => warning_format(),
+                ReportColumn::LateCount | ReportColumn::EarlyLeaveCount => late_format(),
+                _ => continue,
+            };
+            let rule = ConditionalFormatCell::new()
+                .set_rule(ConditionalFormatCellRule::GreaterThan(0))
+                .set_format(format);
+            sheet.add_conditional_format(1, col as u16, last_row, col as u16, &rule)?;
+        }
+    }
+
+    Ok(sheet)
+}
+
+fn detail_sheet(db: &Database, team: &TeamMonth) -> Result<Worksheet, XlsxError> {
+    let mut sheet = Worksheet::new();
+    sheet.set_name("明细")?;
+    write_headers(&mut sheet, DETAIL_HEADERS)?;
+    sheet.set_freeze_panes(1, 1)?;
+
+    let mut row = 0u32;
+    for (user, days) in &team.members {
+        let tz = checkin::user_timezone(db, user);
+        let time = |timestamp: Option<i64>| {
+            timestamp
+                .map(|t| schedule::local_datetime(t, tz).format("%H:%M").to_string())
+                .unwrap_or_default()
+        };
+
+        for day in days {
+            row += 1;
+            sheet.write_string(row, 0, &user.username)?;
+            sheet.write_string(row, 1, day.date.format("%Y-%m-%d").to_string())?;
+            sheet.write_string(
+                row,
+                2,
+                WEEKDAYS[day.date.weekday().num_days_from_monday() as usize],
+            )?;
+            sheet.write_string(row, 3, export::day_status_label(day.status))?;
+            sheet.write_string(row, 4, time(day.first_in))?;
+            sheet.write_string(row, 5, time(day.last_out))?;
+            sheet.write_number(row, 6, day.worked_minutes as f64)?;
+            sheet.write_number(row, 7, day.late_minutes as f64)?;
+            sheet.write_number(row, 8, day.early_leave_minutes as f64)?;
+            sheet.write_number(row, 9, day.leave_days)?;
+            sheet.write_number(row, 10, day.overtime_minutes as f64)?;
+            sheet.write_number(row, 11, day.failed_attempts as f64)?;
+        }
+    }
+
+    // 整行高亮：缺勤和缺卡为红色，迟到或早退为黄色
+    if row > 0 {
+        let last_col = DETAIL_HEADERS.len() as u16 - 1;
+        let absent = ConditionalFormatFormula::new()
+            .set_rule(r#"=OR($D2="缺勤",$D2="缺卡")"#)
+            .set_format(warning_format());
+        let late = ConditionalFormatFormula::new()
+            .set_rule("=OR($H2>0,$I2>0)")
+            .set_format(late_format());
+        sheet.add_conditional_format(1, 0, row, last_col, &absent)?;
+        sheet.add_conditional_format(1, 0, row, last_col, &late)?;
+    }
+
+    Ok(sheet)
+}
+
+/// 生成月报工作簿：汇总表每人一行，明细表每人每天一行；`columns` 为空时使用默认列
+pub fn write_monthly_report(
+    db: &Database,
+    report: &MonthlyReport,
+    team: &TeamMonth,
+    columns: &[ReportColumn],
+    path: &str,
+) -> Result<(), String> {
+    export::ensure_parent_dir(Path::new(path))?;
+    let columns = if columns.is_empty() {
+        export::DEFAULT_REPORT_COLUMNS
+    } else {
+        columns
+    };
+
+    let build = || -> Result<Workbook, XlsxError> {
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(summary_sheet(report, columns)?);
+        workbook.push_worksheet(detail_sheet(db, team)?);
+        Ok(workbook)
+    };
+
+    build()
+        .and_then(|mut workbook| workbook.save(path))
+        .map_err(|e| format!("生成 Excel 文件失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AttendanceStatus, PunchType, User};
+    use crate::report;
+    use crate::test_support;
+    use calamine::{open_workbook, Data, Range, Reader, Xlsx};
+
+    fn setup() -> (Database, User) {
+        let test_support::Fixture {
+            db,
+            admin,
+            mut user,
+            location,
+        } = test_support::setup();
+        let shift = test_support::day_shift(&db, &admin, vec![1, 2, 3, 4, 5]);
+        user.shift_id = Some(shift.id);
+        db.save_user(&user).unwrap();
+        for (punch_type, hour) in [(PunchType::In, 9), (PunchType::Out, 18)] {
+            test_support::punch(
+                &db,
+                &user,
+                &location,
+                punch_type,
+                AttendanceStatus::Success,
+                test_support::at(14, hour, 0),
+            );
+        }
+        (db, admin)
+    }
+
+    fn text(range: &Range<Data>, row: u32, col: u32) -> String {
+        match range.get_value((row, col)) {
+            Some(Data::String(s)) => s.clone(),
+            other => panic!("单元格 ({}, {}) 不是文本: {:?}", row, col, other),
+        }
+    }
+
+    fn number(range: &Range<Data>, row: u32, col: u32) -> f64 {
+        match range.get_value((row, col)) {
+            Some(Data::Float(n)) => *n,
+            other => panic!("单元格 ({}, {}) 不是数字: {:?}", row, col, other),
+        }
+    }
+
+    #[test]
+    fn monthly_report_round_trips_through_excel() {
+        let (db, admin) = setup();
+        let team = report::team_month(&db, &admin.id, "2025-03").unwrap();
+        let report = report::summarize(&admin.id, &team);
+        let columns = [
+            ReportColumn::Username,
+            ReportColumn::WorkingDays,
+            ReportColumn::PresentDays,
+            ReportColumn::AbsentDays,
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("2025-03.xlsx");
+        write_monthly_report(&db, &report, &team, &columns, path.to_str().unwrap()).unwrap();
+
+        let mut workbook: Xlsx<_> = open_workbook(&path).unwrap();
+        assert_eq!(workbook.sheet_names(), vec!["汇总", "明细"]);
+
+        let summary = workbook.worksheet_range("汇总").unwrap();
+        let headers: Vec<String> = (0..4).map(|col| text(&summary, 0, col)).collect();
+        assert_eq!(
+            headers,
+            vec!["用户名", "应出勤天数", "出勤天数", "缺勤天数"]
+        );
+        assert_eq!(summary.get_size(), (3, 4));
+        assert_eq!(text(&summary, 1, 0), "alice");
+        assert_eq!(number(&summary, 1, 1), 21.0);
+        assert_eq!(number(&summary, 1, 2), 1.0);
+        assert_eq!(number(&summary, 1, 3), 20.0);
+        assert_eq!(text(&summary, 2, 0), "合计");
+
+        let detail = workbook.worksheet_range("明细").unwrap();
+        let headers: Vec<String> = (0..DETAIL_HEADERS.len() as u32)
+            .map(|col| text(&detail, 0, col))
+            .collect();
+        assert_eq!(headers, DETAIL_HEADERS);
+        assert_eq!(detail.get_size(), (32, DETAIL_HEADERS.len()));
+        // 第 14 行为 3 月 14 日（周五）
+        assert_eq!(text(&detail, 14, 1), "2025-03-14");
+        assert_eq!(text(&detail, 14, 2), "周五");
+        assert_eq!(text(&detail, 14, 3), "出勤");
+        assert_eq!(text(&detail, 14, 4), "09:00");
+        assert_eq!(text(&detail, 14, 5), "18:00");
+        assert_eq!(number(&detail, 14, 6), 540.0);
+        assert_eq!(text(&detail, 15, 3), "休息");
+    }
+}
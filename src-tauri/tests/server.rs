@@ -0,0 +1,108 @@
+//! 启动独立服务端，通过 HTTP 接口登录并调用命令
+
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+/// 在临时数据目录上启动服务端，返回接口地址；数据目录需在测试结束前保持存在
+fn start_server() -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+
+    let data_dir = dir.path().to_path_buf();
+    let listen = address.clone();
+    std::thread::spawn(move || {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(attendance_lib::run_server(Some(data_dir), Some(listen)))
+            .unwrap();
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while std::net::TcpStream::connect(&address).is_err() {
+        assert!(Instant::now() < deadline, "服务端未能启动");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    (dir, format!("http://{}", address))
+}
+
+fn response(result: Result<ureq::Response, ureq::Error>) -> (u16, Value) {
+    let response = match result {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(e) => panic!("请求失败: {}", e),
+    };
+    let status = response.status();
+    let body = response.into_string().unwrap();
+    (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+}
+
+fn post(base: &str, token: Option<&str>, path: &str, body: Value) -> (u16, Value) {
+    let mut request =
+        ureq::post(&format!("{}{}", base, path)).set("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    response(request.send_string(&body.to_string()))
+}
+
+#[test]
+fn login_and_create_a_location() {
+    let (_dir, base) = start_server();
+
+    let (status, _) = post(
+        &base,
+        None,
+        "/api/v1/session",
+        json!({ "username": "admin", "password": "wrong" }),
+    );
+    assert_eq!(status, 401);
+
+    let (status, session) = post(
+        &base,
+        None,
+        "/api/v1/session",
+        json!({ "username": "admin", "password": "admin123" }),
+    );
+    assert_eq!(status, 200);
+    let token = session["token"].as_str().unwrap();
+    let admin_id = session["user"]["id"].as_str().unwrap();
+    assert!(session["user"].get("password").is_none());
+
+    let (status, created) = post(
+        &base,
+        Some(token),
+        "/api/v1/commands/create_location",
+        json!({
+            "location": {
+                "name": "总部",
+                "latitude": 31.23,
+                "longitude": 121.47,
+                "radius": 200.0,
+                "adminId": admin_id,
+            }
+        }),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(created["name"], "总部");
+
+    let (status, locations) = response(
+        ureq::get(&format!("{}/api/v1/locations", base))
+            .set("Authorization", &format!("Bearer {}", token))
+            .call(),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(locations.as_array().unwrap().len(), 1);
+    assert_eq!(locations[0]["id"], created["id"]);
+
+    let (status, _) = post(
+        &base,
+        None,
+        "/api/v1/commands/get_locations_by_admin",
+        json!({ "adminId": admin_id }),
+    );
+    assert_eq!(status, 401);
+}
@@ -0,0 +1,83 @@
+import { invoke as invokeLocal } from '@tauri-apps/api/core';
+import type { LoginRequest, LoginResponse } from '../types';
+
+// 客户端模式：配置了服务器地址时，命令通过 attendance-server 的 HTTP 接口执行
+const SERVER_URL_KEY = 'server-url';
+const TOKEN_KEY = 'server-token';
+
+// 只与本机有关的命令始终在本地执行
+const LOCAL_COMMANDS = ['get_current_location'];
+
+// 写入本机文件的导出服务端不提供
+const FILE_COMMANDS = [
+  'export_records_csv',
+  'export_monthly_report_csv',
+  'export_monthly_report_xlsx',
+  'export_locations_geojson',
+  'export_attendance_track',
+];
+
+export const getServerUrl = (): string => localStorage.getItem(SERVER_URL_KEY) ?? '';
+
+export const setServerUrl = (url: string) => {
+  const trimmed = url.trim().replace(/\/+$/, '');
+  if (trimmed) {
+    localStorage.setItem(SERVER_URL_KEY, trimmed);
+  } else {
+    localStorage.removeItem(SERVER_URL_KEY);
+  }
+  localStorage.removeItem(TOKEN_KEY);
+};
+
+export const isClientMode = (): boolean => getServerUrl() !== '';
+
+async function request<T>(method: string, path: string, body?: unknown): Promise<T> {
+  const headers: Record<string, string> = { 'Content-Type': 'application/json' };
+  const token = localStorage.getItem(TOKEN_KEY);
+  if (token) {
+    headers.Authorization = `Bearer ${token}`;
+  }
+
+  const response = await fetch(`${getServerUrl()}${path}`, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  if (response.status === 204) {
+    return undefined as T;
+  }
+  const data = await response.json().catch(() => null);
+  if (!response.ok) {
+    // 与本地命令一致，错误以字符串抛出
+    throw data?.error ?? `请求失败 (${response.status})`;
+  }
+  return data as T;
+}
+
+async function login(credentials: LoginRequest): Promise<LoginResponse> {
+  localStorage.removeItem(TOKEN_KEY);
+  try {
+    const session = await request<{ token: string; user: LoginResponse['user'] }>(
+      'POST',
+      '/api/v1/session',
+      credentials,
+    );
+    localStorage.setItem(TOKEN_KEY, session.token);
+    return { success: true, user: session.user };
+  } catch (error) {
+    return { success: false, message: String(error) };
+  }
+}
+
+export async function invoke<T>(command: string, args: Record<string, unknown> = {}): Promise<T> {
+  if (!isClientMode() || LOCAL_COMMANDS.includes(command)) {
+    return invokeLocal<T>(command, args);
+  }
+  if (command === 'login') {
+    return login(args.request as LoginRequest) as Promise<T>;
+  }
+  if (FILE_COMMANDS.includes(command)) {
+    throw '客户端模式下不支持导出到本机文件';
+  }
+  return request<T>('POST', `/api/v1/commands/${command}`, args);
+}
@@ -0,0 +1,24 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { UnlistenFn } from '@tauri-apps/api/event';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
+import { isClientMode } from './client';
+import type { RecordSavedEvent, UserChangedEvent, LocationChangedEvent } from '../types';
+
+// 后端只把事件发给登录了对应管理员的窗口，因此需要通过当前窗口订阅
+const subscribe = <T>(name: string, adminId: string, handler: (payload: T) => void): Promise<UnlistenFn> =>
+  getCurrentWebviewWindow().listen<T>(`${name}:${adminId}`, (event) => handler(event.payload));
+
+export const events = {
+  // 恢复已保存的登录状态时关联当前窗口，退出登录时传 null；客户端模式下没有本地事件
+  watch: (adminId: string | null): Promise<void> =>
+    isClientMode() ? Promise.resolve() : invoke('watch_data_events', { adminId }),
+
+  onRecordSaved: (adminId: string, handler: (event: RecordSavedEvent) => void) =>
+    subscribe('record-saved', adminId, handler),
+
+  onUserChanged: (adminId: string, handler: (event: UserChangedEvent) => void) =>
+    subscribe('user-changed', adminId, handler),
+
+  onLocationChanged: (adminId: string, handler: (event: LocationChangedEvent) => void) =>
+    subscribe('location-changed', adminId, handler),
+};
@@ -1,5 +1,5 @@
-import { invoke } from '@tauri-apps/api/core';
-import type { User, Location, AttendanceRecord, LoginRequest, LoginResponse, CheckInRequest, CheckInResponse, CreateUserRequest, CreateLocationRequest, UpdateLocationRequest } from '../types';
+import { invoke } from './client';
+import type { User, Location, AttendanceRecord, LoginRequest, LoginResponse, CheckInRequest, CheckInResponse, CreateUserRequest, CreateLocationRequest, UpdateLocationRequest, Shift, CreateShiftRequest, UpdateShiftRequest, DailyAttendance, LocationAssignment, LocationGroup, CreateLocationGroupRequest, UpdateLocationGroupRequest, Settings, OfflineCheckIn, PendingCheckIn, ApprovalStatus, CorrectionRequest, CreateCorrectionRequest, ReviewRequest, LeaveType, CreateLeaveTypeRequest, UpdateLeaveTypeRequest, LeaveRequest, CreateLeaveRequest, LeaveBalance, HolidayCalendar, CreateHolidayCalendarRequest, UpdateHolidayCalendarRequest, CalendarTarget, UserGroup, CreateUserGroupRequest, UpdateUserGroupRequest, OvertimeRecord, DailySummary, MonthlyReport, RecordQuery, RecordColumn, ReportColumn, UserImportReport, GeoJsonImportMode, LocationImportReport, TrackFormat, ApiKey, CreatedApiKey, Webhook, CreateWebhookRequest, UpdateWebhookRequest, WebhookDeliveryLog } from '../types';
 
 export const commands = {
   login: (request: LoginRequest): Promise<LoginResponse> => 
@@ -14,6 +14,9 @@ export const commands = {
   createUser: (user: CreateUserRequest): Promise<User> => 
     invoke('create_user', { user }),
   
+  importUsers: (adminId: string, content: string, dryRun: boolean): Promise<UserImportReport> => 
+    invoke('import_users', { adminId, content, dryRun }),
+  
   deleteUser: (userId: string): Promise<void> => 
     invoke('delete_user', { userId }),
   
@@ -23,6 +26,57 @@ export const commands = {
   getUserLocation: (userId: string): Promise<Location | null> => 
     invoke('get_user_location', { userId }),
   
+  getUserLocations: (userId: string): Promise<Location[]> => 
+    invoke('get_user_locations', { userId }),
+  
+  updateUserLocationAssignments: (userId: string, assignments: LocationAssignment[]): Promise<User> => 
+    invoke('update_user_location_assignments', { userId, assignments }),
+  
+  getLocationGroupsByAdmin: (adminId: string): Promise<LocationGroup[]> => 
+    invoke('get_location_groups_by_admin', { adminId }),
+  
+  createLocationGroup: (group: CreateLocationGroupRequest): Promise<LocationGroup> => 
+    invoke('create_location_group', { group }),
+  
+  updateLocationGroup: (groupId: string, group: UpdateLocationGroupRequest): Promise<LocationGroup> => 
+    invoke('update_location_group', { groupId, group }),
+  
+  deleteLocationGroup: (groupId: string): Promise<void> => 
+    invoke('delete_location_group', { groupId }),
+  
+  getUserGroupsByAdmin: (adminId: string): Promise<UserGroup[]> => 
+    invoke('get_user_groups_by_admin', { adminId }),
+  
+  createUserGroup: (group: CreateUserGroupRequest): Promise<UserGroup> => 
+    invoke('create_user_group', { group }),
+  
+  updateUserGroup: (groupId: string, group: UpdateUserGroupRequest): Promise<UserGroup> => 
+    invoke('update_user_group', { groupId, group }),
+  
+  deleteUserGroup: (groupId: string): Promise<void> => 
+    invoke('delete_user_group', { groupId }),
+  
+  getHolidayCalendarsByAdmin: (adminId: string): Promise<HolidayCalendar[]> => 
+    invoke('get_holiday_calendars_by_admin', { adminId }),
+  
+  createHolidayCalendar: (calendar: CreateHolidayCalendarRequest): Promise<HolidayCalendar> => 
+    invoke('create_holiday_calendar', { calendar }),
+  
+  updateHolidayCalendar: (calendarId: string, calendar: UpdateHolidayCalendarRequest): Promise<HolidayCalendar> => 
+    invoke('update_holiday_calendar', { calendarId, calendar }),
+  
+  deleteHolidayCalendar: (calendarId: string): Promise<void> => 
+    invoke('delete_holiday_calendar', { calendarId }),
+  
+  importHolidayCalendarIcs: (calendarId: string, content: string): Promise<HolidayCalendar> => 
+    invoke('import_holiday_calendar_ics', { calendarId, content }),
+  
+  importCnStatutoryHolidays: (calendarId: string, year: number): Promise<HolidayCalendar> => 
+    invoke('import_cn_statutory_holidays', { calendarId, year }),
+  
+  setHolidayCalendar: (target: CalendarTarget, calendarId?: string): Promise<void> => 
+    invoke('set_holiday_calendar', { target, calendarId }),
+  
   getAllLocations: (): Promise<Location[]> => 
     invoke('get_all_locations'),
   
@@ -32,6 +86,12 @@ export const commands = {
   createLocation: (location: CreateLocationRequest): Promise<Location> => 
     invoke('create_location', { location }),
   
+  exportLocationsGeoJson: (adminId: string, path: string): Promise<number> => 
+    invoke('export_locations_geojson', { adminId, path }),
+  
+  importLocationsGeoJson: (adminId: string, content: string, mode: GeoJsonImportMode, dryRun: boolean): Promise<LocationImportReport> => 
+    invoke('import_locations_geojson', { adminId, content, mode, dryRun }),
+  
   updateLocation: (locationId: string, location: UpdateLocationRequest): Promise<Location> => 
     invoke('update_location', { locationId, location }),
   
@@ -44,9 +104,159 @@ export const commands = {
   getAttendanceRecordsByAdmin: (adminId: string): Promise<AttendanceRecord[]> => 
     invoke('get_attendance_records_by_admin', { adminId }),
   
+  getSuspiciousRecords: (adminId: string, minScore?: number): Promise<AttendanceRecord[]> => 
+    invoke('get_suspicious_records', { adminId, minScore }),
+  
   checkIn: (request: CheckInRequest): Promise<CheckInResponse> => 
     invoke('check_in', { request }),
   
+  queueOfflineCheckIns: (checkIns: OfflineCheckIn[]): Promise<PendingCheckIn[]> => 
+    invoke('queue_offline_check_ins', { checkIns }),
+  
+  syncPendingCheckIns: (userId?: string): Promise<PendingCheckIn[]> => 
+    invoke('sync_pending_check_ins', { userId }),
+  
+  getPendingCheckIns: (userId: string): Promise<PendingCheckIn[]> => 
+    invoke('get_pending_check_ins', { userId }),
+  
+  submitCorrectionRequest: (request: CreateCorrectionRequest): Promise<CorrectionRequest> => 
+    invoke('submit_correction_request', { request }),
+  
+  getCorrectionRequestsByUser: (userId: string): Promise<CorrectionRequest[]> => 
+    invoke('get_correction_requests_by_user', { userId }),
+  
+  getCorrectionRequestsByAdmin: (adminId: string, status?: ApprovalStatus): Promise<CorrectionRequest[]> => 
+    invoke('get_correction_requests_by_admin', { adminId, status }),
+  
+  approveCorrectionRequest: (correctionId: string, review: ReviewRequest): Promise<CorrectionRequest> => 
+    invoke('approve_correction_request', { correctionId, review }),
+  
+  rejectCorrectionRequest: (correctionId: string, review: ReviewRequest): Promise<CorrectionRequest> => 
+    invoke('reject_correction_request', { correctionId, review }),
+  
+  getLeaveTypesByAdmin: (adminId: string): Promise<LeaveType[]> => 
+    invoke('get_leave_types_by_admin', { adminId }),
+  
+  createLeaveType: (leaveType: CreateLeaveTypeRequest): Promise<LeaveType> => 
+    invoke('create_leave_type', { leaveType }),
+  
+  updateLeaveType: (leaveTypeId: string, leaveType: UpdateLeaveTypeRequest): Promise<LeaveType> => 
+    invoke('update_leave_type', { leaveTypeId, leaveType }),
+  
+  deleteLeaveType: (leaveTypeId: string): Promise<void> => 
+    invoke('delete_leave_type', { leaveTypeId }),
+  
+  submitLeaveRequest: (request: CreateLeaveRequest): Promise<LeaveRequest> => 
+    invoke('submit_leave_request', { request }),
+  
+  getLeaveRequestsByUser: (userId: string): Promise<LeaveRequest[]> => 
+    invoke('get_leave_requests_by_user', { userId }),
+  
+  getLeaveRequestsByAdmin: (adminId: string, status?: ApprovalStatus): Promise<LeaveRequest[]> => 
+    invoke('get_leave_requests_by_admin', { adminId, status }),
+  
+  approveLeaveRequest: (leaveId: string, review: ReviewRequest): Promise<LeaveRequest> => 
+    invoke('approve_leave_request', { leaveId, review }),
+  
+  rejectLeaveRequest: (leaveId: string, review: ReviewRequest): Promise<LeaveRequest> => 
+    invoke('reject_leave_request', { leaveId, review }),
+  
+  getLeaveBalances: (userId: string, year: number): Promise<LeaveBalance[]> => 
+    invoke('get_leave_balances', { userId, year }),
+  
+  setLeaveAdjustment: (userId: string, leaveTypeId: string, year: number, days: number): Promise<void> => 
+    invoke('set_leave_adjustment', { userId, leaveTypeId, year, days }),
+  
+  getOvertimeByUser: (userId: string): Promise<OvertimeRecord[]> => 
+    invoke('get_overtime_by_user', { userId }),
+  
+  getOvertimeByAdmin: (adminId: string, status?: ApprovalStatus): Promise<OvertimeRecord[]> => 
+    invoke('get_overtime_by_admin', { adminId, status }),
+  
+  recalculateOvertime: (userId: string, startDate: string, endDate: string): Promise<OvertimeRecord[]> => 
+    invoke('recalculate_overtime', { userId, startDate, endDate }),
+  
+  approveOvertime: (overtimeId: string, review: ReviewRequest): Promise<OvertimeRecord> => 
+    invoke('approve_overtime', { overtimeId, review }),
+  
+  rejectOvertime: (overtimeId: string, review: ReviewRequest): Promise<OvertimeRecord> => 
+    invoke('reject_overtime', { overtimeId, review }),
+  
   getCurrentLocation: (): Promise<{ latitude: number; longitude: number }> => 
     invoke('get_current_location'),
+  
+  getShiftsByAdmin: (adminId: string): Promise<Shift[]> => 
+    invoke('get_shifts_by_admin', { adminId }),
+  
+  createShift: (shift: CreateShiftRequest): Promise<Shift> => 
+    invoke('create_shift', { shift }),
+  
+  updateShift: (shiftId: string, shift: UpdateShiftRequest): Promise<Shift> => 
+    invoke('update_shift', { shiftId, shift }),
+  
+  deleteShift: (shiftId: string): Promise<void> => 
+    invoke('delete_shift', { shiftId }),
+  
+  updateUserShift: (userId: string, shiftId: string | null): Promise<User> => 
+    invoke('update_user_shift', { userId, shiftId }),
+  
+  getDailyAttendance: (userId: string, startDate: string, endDate: string): Promise<DailyAttendance[]> => 
+    invoke('get_daily_attendance', { userId, startDate, endDate }),
+  
+  getDailySummaries: (userId: string, startDate: string, endDate: string): Promise<DailySummary[]> => 
+    invoke('get_daily_summaries', { userId, startDate, endDate }),
+  
+  recomputeDailySummaries: (userId: string, startDate: string, endDate: string): Promise<DailySummary[]> => 
+    invoke('recompute_daily_summaries', { userId, startDate, endDate }),
+  
+  generateMonthlyReport: (adminId: string, month: string): Promise<MonthlyReport> => 
+    invoke('generate_monthly_report', { adminId, month }),
+  
+  queryAttendanceRecords: (query: RecordQuery): Promise<AttendanceRecord[]> => 
+    invoke('query_attendance_records', { query }),
+  
+  exportRecordsCsv: (query: RecordQuery, columns: RecordColumn[], path: string): Promise<number> => 
+    invoke('export_records_csv', { query, columns, path }),
+  
+  exportMonthlyReportCsv: (adminId: string, month: string, columns: ReportColumn[], path: string): Promise<number> => 
+    invoke('export_monthly_report_csv', { adminId, month, columns, path }),
+  
+  exportAttendanceTrack: (userId: string, startDate: string, endDate: string, format: TrackFormat, path: string): Promise<number> => 
+    invoke('export_attendance_track', { userId, startDate, endDate, format, path }),
+  
+  exportMonthlyReportXlsx: (adminId: string, month: string, columns: ReportColumn[], path: string): Promise<number> => 
+    invoke('export_monthly_report_xlsx', { adminId, month, columns, path }),
+  
+  getApiKeysByAdmin: (adminId: string): Promise<ApiKey[]> => 
+    invoke('get_api_keys_by_admin', { adminId }),
+  
+  createApiKey: (adminId: string, name: string): Promise<CreatedApiKey> => 
+    invoke('create_api_key', { adminId, name }),
+  
+  deleteApiKey: (adminId: string, keyId: string): Promise<void> => 
+    invoke('delete_api_key', { adminId, keyId }),
+  
+  getWebhooksByAdmin: (adminId: string): Promise<Webhook[]> => 
+    invoke('get_webhooks_by_admin', { adminId }),
+  
+  createWebhook: (webhook: CreateWebhookRequest): Promise<Webhook> => 
+    invoke('create_webhook', { webhook }),
+  
+  updateWebhook: (adminId: string, webhookId: string, webhook: UpdateWebhookRequest): Promise<Webhook> => 
+    invoke('update_webhook', { adminId, webhookId, webhook }),
+  
+  deleteWebhook: (adminId: string, webhookId: string): Promise<void> => 
+    invoke('delete_webhook', { adminId, webhookId }),
+  
+  testWebhook: (adminId: string, webhookId: string): Promise<void> => 
+    invoke('test_webhook', { adminId, webhookId }),
+  
+  getWebhookDeliveries: (adminId: string, webhookId: string): Promise<WebhookDeliveryLog[]> => 
+    invoke('get_webhook_deliveries', { adminId, webhookId }),
+  
+  getSettings: (): Promise<Settings> => 
+    invoke('get_settings'),
+  
+  updateSettings: (settings: Settings): Promise<Settings> => 
+    invoke('update_settings', { settings }),
 };
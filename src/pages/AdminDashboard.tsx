@@ -20,6 +20,7 @@ import {
   DeleteOutlined,
 } from '@ant-design/icons';
 import { commands } from '../api/tauri';
+import { events } from '../api/events';
 import { useAuthStore } from '../store/authStore';
 import { useNavigate } from 'react-router-dom';
 import type { User, Location, AttendanceRecord } from '../types';
@@ -51,6 +52,36 @@ export default function AdminDashboard() {
     }
   }, [user, selectedMenu]);
 
+  // 后端推送的数据变化直接合并到列表中，无需重新加载
+  useEffect(() => {
+    if (!user) return;
+    const adminId = user.id;
+    const upsert = <T extends { id: string }>(items: T[], item: T) =>
+      items.some(i => i.id === item.id) ? items.map(i => (i.id === item.id ? item : i)) : [item, ...items];
+
+    events.watch(adminId).catch(console.error);
+    const subscriptions = [
+      events.onRecordSaved(adminId, ({ record }) => {
+        if (record.userId === adminId) return;
+        setRecords(prev => upsert(prev, record));
+      }),
+      events.onUserChanged(adminId, ({ user: changed, change }) => {
+        if (changed.id === adminId) return;
+        setUsers(prev =>
+          change === 'deleted' ? prev.filter(u => u.id !== changed.id) : upsert(prev, changed)
+        );
+      }),
+      events.onLocationChanged(adminId, ({ location, change }) => {
+        setLocations(prev =>
+          change === 'deleted' ? prev.filter(l => l.id !== location.id) : upsert(prev, location)
+        );
+      }),
+    ];
+    return () => {
+      subscriptions.forEach(p => p.then(unlisten => unlisten()));
+    };
+  }, [user]);
+
   const loadData = async () => {
     if (!user) return;
     
@@ -196,6 +227,7 @@ export default function AdminDashboard() {
   };
 
   const handleLogout = () => {
+    events.watch(null).catch(console.error);
     logout();
     navigate('/login');
   };
@@ -214,7 +246,10 @@ export default function AdminDashboard() {
             <div className="admin-list">
               {users.map(u => (
                 <div key={u.id} className="admin-list-item">
-                  <div className="list-item-title">{u.username}</div>
+                  <div className="list-item-title">
+                    {u.username}
+                    {u.disabled && <Tag color="default" style={{ marginLeft: 8 }}>已停用</Tag>}
+                  </div>
                   <div className="list-item-sub">
                     {u.locationId ? (
                       <>
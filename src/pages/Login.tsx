@@ -1,7 +1,8 @@
 import { useState } from 'react';
 import { Form, Input, Button, Card, message } from 'antd';
-import { UserOutlined, LockOutlined } from '@ant-design/icons';
+import { UserOutlined, LockOutlined, CloudServerOutlined } from '@ant-design/icons';
 import { commands } from '../api/tauri';
+import { getServerUrl, setServerUrl } from '../api/client';
 import { useAuthStore } from '../store/authStore';
 import { useNavigate } from 'react-router-dom';
 import './Login.css';
@@ -11,9 +12,11 @@ export default function Login() {
   const login = useAuthStore((state) => state.login);
   const navigate = useNavigate();
 
-  const onFinish = async (values: { username: string; password: string }) => {
+  const onFinish = async (values: { username: string; password: string; serverUrl?: string }) => {
     setLoading(true);
     try {
+      setServerUrl(values.serverUrl ?? '');
+
       const response = await commands.login({
         username: values.username,
         password: values.password,
@@ -51,6 +54,7 @@ export default function Login() {
           autoComplete="off"
           size="large"
           className="login-form"
+          initialValues={{ serverUrl: getServerUrl() }}
         >
           <Form.Item
             name="username"
@@ -66,6 +70,10 @@ export default function Login() {
             <Input.Password prefix={<LockOutlined style={{ color: '#bfbfbf' }} />} placeholder="密码" />
           </Form.Item>
 
+          <Form.Item name="serverUrl" extra="留空使用本机数据，连接服务器时填写如 http://192.168.1.10:8787">
+            <Input prefix={<CloudServerOutlined style={{ color: '#bfbfbf' }} />} placeholder="服务器地址（可选）" />
+          </Form.Item>
+
           <Form.Item>
             <Button type="primary" htmlType="submit" loading={loading} block className="login-submit-btn">
               登录
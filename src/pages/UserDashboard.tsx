@@ -12,7 +12,13 @@ import MobileLayout from '../components/MobileLayout';
 import './UserDashboard.css';
 
 export default function UserDashboard() {
-  const [location, setLocation] = useState<{ latitude: number; longitude: number } | null>(null);
+  const [location, setLocation] = useState<{
+    latitude: number;
+    longitude: number;
+    accuracy?: number;
+    altitude?: number;
+    timestamp?: number;
+  } | null>(null);
   const [loading, setLoading] = useState(false);
   const [checkingIn, setCheckingIn] = useState(false);
   const [records, setRecords] = useState<AttendanceRecord[]>([]);
@@ -125,6 +131,9 @@ export default function UserDashboard() {
         const newLocation = {
           latitude: position.coords.latitude,
           longitude: position.coords.longitude,
+          accuracy: position.coords.accuracy,
+          altitude: position.coords.altitude ?? undefined,
+          timestamp: Math.floor(position.timestamp / 1000),
         };
         setLocation(newLocation);
         
@@ -168,6 +177,11 @@ export default function UserDashboard() {
         user_id: user.id,
         latitude: location.latitude,
         longitude: location.longitude,
+        accuracy: location.accuracy,
+        altitude: location.altitude,
+        fix_timestamp: location.timestamp,
+        provider: 'browser',
+        idempotency_key: crypto.randomUUID(),
       });
 
       if (response.success) {
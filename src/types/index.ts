@@ -3,10 +3,70 @@ export type UserRole = 'admin' | 'user';
 export interface User {
   id: string;
   username: string;
-  password: string;
   role: UserRole;
   adminId?: string;
   locationId?: string;
+  shiftId?: string;
+  locationAssignments: LocationAssignment[];
+  profile: UserProfile;
+  disabled?: boolean;
+}
+
+export interface UserProfile {
+  displayName?: string;
+  employeeNo?: string;
+  phone?: string;
+  email?: string;
+}
+
+export type AssignmentTarget =
+  | { type: 'location'; id: string }
+  | { type: 'group'; id: string };
+
+export interface LocationAssignment {
+  target: AssignmentTarget;
+  validFrom?: string;
+  validUntil?: string;
+}
+
+export interface LocationGroup {
+  id: string;
+  name: string;
+  locationIds: string[];
+  adminId: string;
+  calendarId?: string;
+}
+
+export interface UserGroup {
+  id: string;
+  name: string;
+  userIds: string[];
+  adminId: string;
+  calendarId?: string;
+}
+
+export interface CreateUserGroupRequest {
+  name: string;
+  userIds: string[];
+  adminId: string;
+}
+
+export interface UpdateUserGroupRequest {
+  name?: string;
+  userIds?: string[];
+}
+
+export type CalendarTarget = AssignmentTarget | { type: 'userGroup'; id: string };
+
+export interface CreateLocationGroupRequest {
+  name: string;
+  locationIds: string[];
+  adminId: string;
+}
+
+export interface UpdateLocationGroupRequest {
+  name?: string;
+  locationIds?: string[];
 }
 
 export interface CreateUserRequest {
@@ -16,6 +76,32 @@ export interface CreateUserRequest {
   adminId?: string;
 }
 
+export type DayKind = 'holiday' | 'workday';
+
+export interface CalendarDay {
+  date: string;
+  kind: DayKind;
+  name: string;
+}
+
+export interface HolidayCalendar {
+  id: string;
+  name: string;
+  days: CalendarDay[];
+  adminId: string;
+}
+
+export interface CreateHolidayCalendarRequest {
+  name: string;
+  days?: CalendarDay[];
+  adminId: string;
+}
+
+export interface UpdateHolidayCalendarRequest {
+  name?: string;
+  days?: CalendarDay[];
+}
+
 export interface Location {
   id: string;
   name: string;
@@ -23,14 +109,43 @@ export interface Location {
   longitude: number;
   radius: number;
   adminId: string;
+  checkInWindows: CheckInWindow[];
+  windowPolicy: WindowPolicy;
+  geofence?: Geofence;
+  buffer: number;
+  calendarId?: string;
+  timezone: string;
 }
 
+export interface GeoPoint {
+  latitude: number;
+  longitude: number;
+}
+
+export type Geofence =
+  | { type: 'circle'; latitude: number; longitude: number; radius: number }
+  | { type: 'polygon'; vertices: GeoPoint[] }
+  | { type: 'union'; shapes: Geofence[] };
+
+export interface CheckInWindow {
+  startTime: string;
+  endTime: string;
+  weekdays: number[];
+}
+
+export type WindowPolicy = 'reject' | 'flag';
+
 export interface CreateLocationRequest {
   name: string;
   latitude: number;
   longitude: number;
   radius: number;
   adminId: string;
+  checkInWindows?: CheckInWindow[];
+  windowPolicy?: WindowPolicy;
+  geofence?: Geofence;
+  buffer?: number;
+  timezone?: string;
 }
 
 export interface UpdateLocationRequest {
@@ -38,17 +153,388 @@ export interface UpdateLocationRequest {
   latitude?: number;
   longitude?: number;
   radius?: number;
+  checkInWindows?: CheckInWindow[];
+  windowPolicy?: WindowPolicy;
+  geofence?: Geofence;
+  buffer?: number;
+  timezone?: string;
 }
 
 export interface AttendanceRecord {
   id: string;
   userId: string;
   locationId: string;
+  locationGroupId?: string;
   latitude: number;
   longitude: number;
   timestamp: number;
-  status: 'success' | 'failed';
+  status: AttendanceStatus;
   errorMessage?: string;
+  punchType: PunchType;
+  punctuality?: Punctuality;
+  flags: RecordFlag[];
+  accuracy?: number;
+  altitude?: number;
+  fixTimestamp?: number;
+  provider?: string;
+  riskScore: number;
+  riskReasons: RiskReason[];
+  suspicious: boolean;
+  idempotencyKey?: string;
+  source: RecordSource;
+  syncedAt?: number;
+  correctionId?: string;
+  timezone?: string;
+  localDate?: string;
+}
+
+export type RecordSource = 'online' | 'lateSynced' | 'manual';
+
+export type ApprovalStatus = 'pending' | 'approved' | 'rejected';
+
+export interface CorrectionRequest {
+  id: string;
+  userId: string;
+  adminId?: string;
+  punchType: PunchType;
+  requestedTime: number;
+  locationId?: string;
+  reason: string;
+  status: ApprovalStatus;
+  createdAt: number;
+  reviewedBy?: string;
+  reviewedAt?: number;
+  reviewComment?: string;
+  recordId?: string;
+}
+
+export interface CreateCorrectionRequest {
+  userId: string;
+  punchType: PunchType;
+  requestedTime: number;
+  locationId?: string;
+  reason: string;
+}
+
+export interface ReviewRequest {
+  adminId: string;
+  comment?: string;
+}
+
+export type AccrualMethod = 'annual' | 'monthly';
+
+export interface LeaveType {
+  id: string;
+  name: string;
+  annualDays: number;
+  accrual: AccrualMethod;
+  paid: boolean;
+  adminId: string;
+}
+
+export interface CreateLeaveTypeRequest {
+  name: string;
+  annualDays: number;
+  accrual: AccrualMethod;
+  paid: boolean;
+  adminId: string;
+}
+
+export interface UpdateLeaveTypeRequest {
+  name?: string;
+  annualDays?: number;
+  accrual?: AccrualMethod;
+  paid?: boolean;
+}
+
+export type DayPart = 'full' | 'morning' | 'afternoon';
+
+export interface LeaveRequest {
+  id: string;
+  userId: string;
+  adminId?: string;
+  leaveTypeId: string;
+  startDate: string;
+  endDate: string;
+  startPart: DayPart;
+  endPart: DayPart;
+  days: number;
+  reason: string;
+  status: ApprovalStatus;
+  createdAt: number;
+  reviewedBy?: string;
+  reviewedAt?: number;
+  reviewComment?: string;
+}
+
+export interface CreateLeaveRequest {
+  userId: string;
+  leaveTypeId: string;
+  startDate: string;
+  endDate: string;
+  startPart?: DayPart;
+  endPart?: DayPart;
+  reason: string;
+}
+
+export interface LeaveBalance {
+  userId: string;
+  leaveTypeId: string;
+  year: number;
+  entitled: number;
+  accrued: number;
+  adjustment: number;
+  used: number;
+  pending: number;
+  available: number;
+}
+
+export type RiskKind = 'impossibleTravel' | 'repeatedCoordinates' | 'staleFix' | 'fenceCentre';
+
+export interface RiskReason {
+  kind: RiskKind;
+  detail: string;
+}
+
+export type AttendanceStatus = 'success' | 'failed' | 'outsideWindow' | 'lowAccuracy';
+
+export interface RecordFlag {
+  kind: 'outsideWindow';
+  reason: string;
+}
+
+export type PunchType = 'in' | 'out';
+
+export type Punctuality =
+  | { kind: 'onTime' }
+  | { kind: 'late'; minutes: number }
+  | { kind: 'earlyLeave'; minutes: number };
+
+export interface Shift {
+  id: string;
+  name: string;
+  startTime: string;
+  endTime: string;
+  graceMinutes: number;
+  weekdays: number[];
+  adminId: string;
+}
+
+export interface CreateShiftRequest {
+  name: string;
+  startTime: string;
+  endTime: string;
+  graceMinutes: number;
+  weekdays: number[];
+  adminId: string;
+}
+
+export interface UpdateShiftRequest {
+  name?: string;
+  startTime?: string;
+  endTime?: string;
+  graceMinutes?: number;
+  weekdays?: number[];
+}
+
+export type DayStatus = 'present' | 'absent' | 'incomplete' | 'rest' | 'leave';
+
+export interface DailyAttendance {
+  userId: string;
+  date: string;
+  status: DayStatus;
+  firstIn?: number;
+  lastOut?: number;
+  lateMinutes: number;
+  earlyLeaveMinutes: number;
+  leaveDays: number;
+  overtimeMinutes: number;
+}
+
+export interface DailySummary {
+  userId: string;
+  date: string;
+  status: DayStatus;
+  firstIn?: number;
+  lastOut?: number;
+  workedMinutes: number;
+  lateMinutes: number;
+  earlyLeaveMinutes: number;
+  leaveDays: number;
+  overtimeMinutes: number;
+  failedAttempts: number;
+  scheduled: boolean;
+  computedAt: number;
+}
+
+export interface ReportFigures {
+  workingDays: number;
+  presentDays: number;
+  lateCount: number;
+  lateMinutes: number;
+  earlyLeaveCount: number;
+  earlyLeaveMinutes: number;
+  absentDays: number;
+  incompleteDays: number;
+  leaveDays: number;
+  overtimeMinutes: number;
+  failedAttempts: number;
+}
+
+export interface MonthlyReportRow {
+  userId: string;
+  username: string;
+  figures: ReportFigures;
+}
+
+export interface MonthlyReport {
+  adminId: string;
+  month: string;
+  startDate: string;
+  endDate: string;
+  rows: MonthlyReportRow[];
+  totals: ReportFigures;
+}
+
+export type OvertimeDayType = 'weekday' | 'weekend' | 'holiday';
+
+export interface OvertimeRecord {
+  id: string;
+  userId: string;
+  adminId?: string;
+  date: string;
+  dayType: OvertimeDayType;
+  start: number;
+  end: number;
+  minutes: number;
+  multiplier: number;
+  status: ApprovalStatus;
+  createdAt: number;
+  reviewedBy?: string;
+  reviewedAt?: number;
+  reviewComment?: string;
+}
+
+export interface AccuracyPolicy {
+  maxAccuracy?: number;
+  minOverlap: number;
+}
+
+export interface PlausibilityPolicy {
+  maxSpeedKmh: number;
+  repeatWindow: number;
+  maxFixAgeSeconds: number;
+  flagThreshold: number;
+}
+
+export interface PunchPolicy {
+  minIntervalSeconds: number;
+}
+
+export interface OfflinePolicy {
+  maxAgeHours: number;
+}
+
+export interface CorrectionPolicy {
+  monthlyQuota: number;
+}
+
+export interface OvertimePolicy {
+  minMinutes: number;
+  roundingMinutes: number;
+  weekdayMultiplier: number;
+  weekendMultiplier: number;
+  holidayMultiplier: number;
+}
+
+export interface Settings {
+  accuracy: AccuracyPolicy;
+  plausibility: PlausibilityPolicy;
+  punch: PunchPolicy;
+  offline: OfflinePolicy;
+  correction: CorrectionPolicy;
+  overtime: OvertimePolicy;
+  server: ServerSettings;
+}
+
+export interface ServerSettings {
+  enabled: boolean;
+  address: string;
+  sessionHours: number;
+  allowedOrigins: string[];
+}
+
+export interface ApiKey {
+  id: string;
+  name: string;
+  adminId: string;
+  prefix: string;
+  createdAt: number;
+}
+
+export interface CreatedApiKey {
+  apiKey: ApiKey;
+  secret: string;
+}
+
+export type WebhookEvent = 'checkInSuccess' | 'checkInFailed' | 'lateArrival' | 'correctionApproved' | 'userCreated' | 'ping';
+
+export interface Webhook {
+  id: string;
+  adminId: string;
+  name: string;
+  url: string;
+  secret: string;
+  events: WebhookEvent[];
+  enabled: boolean;
+  createdAt: number;
+}
+
+export interface CreateWebhookRequest {
+  adminId: string;
+  name: string;
+  url: string;
+  events: WebhookEvent[];
+}
+
+export interface UpdateWebhookRequest {
+  name: string;
+  url: string;
+  events: WebhookEvent[];
+  enabled: boolean;
+}
+
+export interface WebhookDeliveryLog {
+  id: string;
+  deliveryId: string;
+  webhookId: string;
+  event: WebhookEvent;
+  attempt: number;
+  timestamp: number;
+  success: boolean;
+  statusCode?: number;
+  error?: string;
+  durationMs: number;
+  nextAttemptAt?: number;
+}
+
+export type ChangeKind = 'saved' | 'deleted';
+
+export interface RecordSavedEvent {
+  adminId: string;
+  record: AttendanceRecord;
+}
+
+export interface UserChangedEvent {
+  adminId: string;
+  change: ChangeKind;
+  user: User;
+}
+
+export interface LocationChangedEvent {
+  adminId: string;
+  change: ChangeKind;
+  location: Location;
 }
 
 export interface LoginRequest {
@@ -66,6 +552,12 @@ export interface CheckInRequest {
   user_id: string;
   latitude: number;
   longitude: number;
+  punch_type?: PunchType;
+  accuracy?: number;
+  altitude?: number;
+  fix_timestamp?: number;
+  provider?: string;
+  idempotency_key?: string;
 }
 
 export interface CheckInResponse {
@@ -73,3 +565,106 @@ export interface CheckInResponse {
   record?: AttendanceRecord;
   message?: string;
 }
+
+export interface OfflineCheckIn {
+  request: CheckInRequest;
+  capturedAt: number;
+}
+
+export type PendingStatus = 'pending' | 'synced' | 'rejected';
+
+export interface PendingCheckIn {
+  id: string;
+  userId: string;
+  request: CheckInRequest;
+  capturedAt: number;
+  queuedAt: number;
+  status: PendingStatus;
+  recordId?: string;
+  message?: string;
+}
+
+export interface RecordQuery {
+  adminId?: string;
+  userId?: string;
+  locationId?: string;
+  startDate?: string;
+  endDate?: string;
+  status?: AttendanceStatus;
+  punchType?: PunchType;
+}
+
+export type RecordColumn =
+  | 'username'
+  | 'locationName'
+  | 'date'
+  | 'time'
+  | 'timezone'
+  | 'punchType'
+  | 'status'
+  | 'punctuality'
+  | 'latitude'
+  | 'longitude'
+  | 'accuracy'
+  | 'riskScore'
+  | 'source'
+  | 'errorMessage';
+
+export type ReportColumn =
+  | 'username'
+  | 'workingDays'
+  | 'presentDays'
+  | 'lateCount'
+  | 'lateMinutes'
+  | 'earlyLeaveCount'
+  | 'earlyLeaveMinutes'
+  | 'absentDays'
+  | 'incompleteDays'
+  | 'leaveDays'
+  | 'overtimeMinutes'
+  | 'failedAttempts';
+
+export interface ImportRowError {
+  line: number;
+  username?: string;
+  message: string;
+}
+
+export interface UserImportReport {
+  totalRows: number;
+  imported: User[];
+  errors: ImportRowError[];
+  applied: boolean;
+}
+
+export type TrackFormat = 'gpx' | 'kml';
+
+export type GeoJsonImportMode = 'updateByName' | 'createNew';
+
+export type ImportAction = 'create' | 'update' | 'unchanged';
+
+export interface FieldChange {
+  field: string;
+  before: string;
+  after: string;
+}
+
+export interface LocationChange {
+  index: number;
+  name: string;
+  action: ImportAction;
+  locationId?: string;
+  fields: FieldChange[];
+}
+
+export interface FeatureError {
+  index: number;
+  name?: string;
+  message: string;
+}
+
+export interface LocationImportReport {
+  changes: LocationChange[];
+  errors: FeatureError[];
+  applied: boolean;
+}